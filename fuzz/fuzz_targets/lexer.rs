@@ -0,0 +1,24 @@
+//! Feeds arbitrary byte strings through the lexer.
+//!
+//! Run with `cargo fuzz run lexer` from the repository root. Any panic is a
+//! bug: every failure path in the lexer must surface as an `errors::Error`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sap::lexer::{token::TokenKind, Lexer};
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let char_count = input.chars().count();
+
+    let mut count = 0;
+    let mut last = None;
+    for token in Lexer::from_str(&input) {
+        count += 1;
+        // Every call to the lexer must make progress, so the number of
+        // results is bounded by the length of the input.
+        assert!(count <= char_count + 2);
+        last = Some(token);
+    }
+    assert!(matches!(last, Some(Ok(token)) if token.kind == TokenKind::Eof));
+});
@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+
+/// An integer of any size, which ints are promoted to when they don't fit in
+/// 64 bits. It's stored as a sign and the digits of its magnitude in base
+/// 2^32, least significant first. The magnitude never has leading zero
+/// digits and zero is never negative, so equal values are stored the same
+/// way.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Self {
+        trim(&mut digits);
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        Self::from_parts(value < 0, from_u64(value.unsigned_abs()))
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::from_parts(false, from_u64(value))
+    }
+
+    /// Converts a float to the int it contains, rounding towards zero.
+    /// Returns `None` for infinities and NaN.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let bits = value.abs().to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i64;
+        if exponent < 1023 {
+            // The magnitude is less than one
+            return Some(Self::from_i64(0));
+        }
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let shift = exponent - 1075;
+        let digits = if shift >= 0 {
+            shift_left(&from_u64(mantissa), shift as usize)
+        } else {
+            from_u64(mantissa >> -shift)
+        };
+        Some(Self::from_parts(value < 0.0, digits))
+    }
+
+    /// Parses a base 10 integer with an optional sign.
+    pub fn parse(src: &str) -> Option<Self> {
+        let (negative, digits) = match src.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, src.strip_prefix('+').unwrap_or(src)),
+        };
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for c in digits.chars() {
+            let digit = c.to_digit(10)?;
+            mul_add_small(&mut magnitude, 10, digit);
+        }
+        Some(Self::from_parts(negative, magnitude))
+    }
+
+    /// Returns the value as an `i64`, if it fits in one.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &digit| (acc << 32) | digit as u64);
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// Returns the nearest float to the value, which is infinite if the
+    /// value is too large for a float.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &digit| acc * 4294967296.0 + digit as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::from_parts(!self.negative, self.digits.clone())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return Self::from_parts(self.negative, add_magnitudes(&self.digits, &other.digits));
+        }
+        match compare_magnitudes(&self.digits, &other.digits) {
+            Ordering::Less => {
+                Self::from_parts(other.negative, sub_magnitudes(&other.digits, &self.digits))
+            }
+            _ => Self::from_parts(self.negative, sub_magnitudes(&self.digits, &other.digits)),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.digits.iter().enumerate() {
+                let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        Self::from_parts(self.negative != other.negative, digits)
+    }
+
+    /// Divides by `other`, rounding towards zero, returning the quotient and
+    /// the remainder, which has the same sign as `self` (like `/` and `%` on
+    /// Rust's ints). Returns `None` if `other` is zero.
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitudes(&self.digits, &other.digits);
+        Some((
+            Self::from_parts(self.negative != other.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.digits, &other.digits),
+            (true, true) => compare_magnitudes(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl std::fmt::Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Split the magnitude into base 10^9 chunks, least significant first
+        const CHUNK: u32 = 1_000_000_000;
+        let mut magnitude = self.digits.clone();
+        let mut chunks = Vec::new();
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, CHUNK));
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        match chunks.split_last() {
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                for chunk in rest.iter().rev() {
+                    write!(f, "{:09}", chunk)?;
+                }
+                Ok(())
+            }
+            None => write!(f, "0"),
+        }
+    }
+}
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn from_u64(value: u64) -> Vec<u32> {
+    let mut digits = vec![value as u32, (value >> 32) as u32];
+    trim(&mut digits);
+    digits
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut digits = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &digit) in long.iter().enumerate() {
+        let sum = digit as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+        digits.push(sum as u32);
+        carry = sum >> 32;
+    }
+    digits.push(carry as u32);
+    trim(&mut digits);
+    digits
+}
+
+/// Subtracts `b` from `a`, where `a` is at least as large as `b`.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut digits = Vec::with_capacity(a.len());
+    let mut borrow = false;
+    for (i, &digit) in a.iter().enumerate() {
+        let (difference, borrowed) = digit.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (difference, borrowed_again) = difference.overflowing_sub(borrow as u32);
+        digits.push(difference);
+        borrow = borrowed || borrowed_again;
+    }
+    trim(&mut digits);
+    digits
+}
+
+fn shift_left(digits: &[u32], bits: usize) -> Vec<u32> {
+    let (whole, part) = (bits / 32, bits % 32);
+    let mut shifted = vec![0u32; whole];
+    let mut carry = 0u32;
+    for &digit in digits {
+        shifted.push((digit << part) | carry);
+        carry = if part == 0 { 0 } else { digit >> (32 - part) };
+    }
+    shifted.push(carry);
+    trim(&mut shifted);
+    shifted
+}
+
+/// Sets `digits` to `digits * mul + add`.
+fn mul_add_small(digits: &mut Vec<u32>, mul: u32, add: u32) {
+    let mut carry = add as u64;
+    for digit in digits.iter_mut() {
+        let value = *digit as u64 * mul as u64 + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+    if carry != 0 {
+        digits.push(carry as u32);
+    }
+}
+
+/// Divides `digits` by `divisor` in place, returning the remainder.
+fn div_rem_small(digits: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for digit in digits.iter_mut().rev() {
+        let value = (remainder << 32) | *digit as u64;
+        *digit = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+    trim(digits);
+    remainder as u32
+}
+
+/// Long division, one bit of the quotient at a time.
+fn div_rem_magnitudes(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [divisor] = b {
+        let mut quotient = a.to_vec();
+        let remainder = div_rem_small(&mut quotient, *divisor);
+        return (quotient, from_u64(remainder as u64));
+    }
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        // Shift the next bit of `a` into the remainder
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for digit in remainder.iter_mut() {
+            let next = *digit >> 31;
+            *digit = (*digit << 1) | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+        if compare_magnitudes(&remainder, b) != Ordering::Less {
+            remainder = sub_magnitudes(&remainder, b);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
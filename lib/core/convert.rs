@@ -0,0 +1,157 @@
+//! Conversions between objects of different types, which the `int()`,
+//! `float()`, `str()`, `bool()` and `list()` builtins are made of.
+
+use crate::{
+    core::{ops, BigInt, Bool, Decimal, Float, Int, List, Object, Str},
+    errors::{ErrorType, RuntimeError},
+};
+
+/// Why an object couldn't be converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionErrorKind {
+    /// Objects of its type can't be converted to the target type at all,
+    /// e.g. a list to an int.
+    Unsupported,
+    /// Objects of its type can be converted to the target type, but not
+    /// this one, e.g. `"abc"` or an infinite float to an int.
+    Invalid,
+}
+
+/// An object which couldn't be converted to another type.
+#[derive(Debug, Clone)]
+pub struct ConversionError {
+    /// The object which was being converted.
+    pub value: Object,
+    /// The name of the type it was being converted to, e.g. `int`.
+    pub target: &'static str,
+    pub kind: ConversionErrorKind,
+}
+
+impl ConversionError {
+    fn unsupported(value: &Object, target: &'static str) -> Self {
+        Self {
+            value: value.clone(),
+            target,
+            kind: ConversionErrorKind::Unsupported,
+        }
+    }
+
+    fn invalid(value: &Object, target: &'static str) -> Self {
+        Self {
+            value: value.clone(),
+            target,
+            kind: ConversionErrorKind::Invalid,
+        }
+    }
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ConversionErrorKind::Unsupported => write!(
+                f,
+                "Cannot convert '{}' to {}",
+                self.value.type_name(),
+                self.target
+            ),
+            ConversionErrorKind::Invalid => {
+                write!(f, "Cannot convert {} to {}", self.value.repr(), self.target)
+            }
+        }
+    }
+}
+
+/// Unsupported conversions are `TypeError`s and invalid values are
+/// `ValueError`s.
+impl From<ConversionError> for RuntimeError {
+    fn from(error: ConversionError) -> Self {
+        let error_type = match error.kind {
+            ConversionErrorKind::Unsupported => ErrorType::TypeError,
+            ConversionErrorKind::Invalid => ErrorType::ValueError,
+        };
+        RuntimeError::new(error_type, &error.to_string())
+    }
+}
+
+impl Object {
+    /// Converts the object to an int. Floats and decimals are rounded
+    /// towards zero, bools are `0` or `1`, and strings are parsed, ignoring
+    /// surrounding whitespace.
+    pub fn to_int(&self) -> Result<Int, ConversionError> {
+        match self {
+            Object::Int(int) => Ok(int.clone()),
+            Object::Bool(bool) => Ok(Int::new(bool.value as i64)),
+            Object::Decimal(decimal) => Ok(decimal.to_int()),
+            // Floats too large for 64 bits become big ints, while infinities
+            // and NaN have no int value.
+            Object::Float(float) if float.value.abs() < 9.2e18 => Ok(Int::new(float.value as i64)),
+            Object::Float(float) => BigInt::from_f64(float.value)
+                .map(Int::from_big)
+                .ok_or_else(|| ConversionError::invalid(self, "int")),
+            Object::Str(str) => {
+                Int::parse(str.value.trim()).ok_or_else(|| ConversionError::invalid(self, "int"))
+            }
+            _ => Err(ConversionError::unsupported(self, "int")),
+        }
+    }
+
+    /// Converts the object to a float. Ints and decimals become the nearest
+    /// float, bools are `0.0` or `1.0`, and strings are parsed, ignoring
+    /// surrounding whitespace.
+    pub fn to_float(&self) -> Result<Float, ConversionError> {
+        let value = match self {
+            Object::Float(float) => float.value,
+            Object::Int(int) => int.to_f64(),
+            Object::Bool(bool) => bool.value as i64 as f64,
+            Object::Decimal(decimal) => decimal.to_f64(),
+            Object::Str(str) => str
+                .value
+                .trim()
+                .parse()
+                .map_err(|_| ConversionError::invalid(self, "float"))?,
+            _ => return Err(ConversionError::unsupported(self, "float")),
+        };
+        Ok(Float { value })
+    }
+
+    /// Converts the object to a decimal. Floats become the shortest decimal
+    /// which converts back to them, and strings are parsed without a `d`
+    /// suffix, ignoring surrounding whitespace.
+    pub fn to_decimal(&self) -> Result<Decimal, ConversionError> {
+        match self {
+            Object::Decimal(decimal) => Ok(decimal.clone()),
+            Object::Int(int) => Ok(Decimal::from_int(int)),
+            Object::Bool(bool) => Ok(Decimal::from_int(&Int::new(bool.value as i64))),
+            Object::Float(float) => Decimal::from_f64(float.value)
+                .ok_or_else(|| ConversionError::invalid(self, "decimal")),
+            Object::Str(str) => Decimal::parse(str.value.trim())
+                .ok_or_else(|| ConversionError::invalid(self, "decimal")),
+            _ => Err(ConversionError::unsupported(self, "decimal")),
+        }
+    }
+
+    /// Converts the object to the string it is shown as to the user (see
+    /// `Object::repr` for how it would be written in a program). Every
+    /// object can be converted.
+    pub fn to_str(&self) -> Str {
+        Str {
+            value: self.to_string(),
+        }
+    }
+
+    /// Converts the object to whether it counts as true when used as a
+    /// condition. Every object can be converted.
+    pub fn to_bool(&self) -> Bool {
+        Bool {
+            value: self.is_truthy(),
+        }
+    }
+
+    /// Converts the object to a new list of the objects iterating over it
+    /// produces (see `ops::iterate`), so a list is copied.
+    pub fn to_list(&self) -> Result<List, ConversionError> {
+        let elements =
+            ops::iterate(self).map_err(|_| ConversionError::unsupported(self, "list"))?;
+        Ok(List::new(elements.collect()))
+    }
+}
@@ -0,0 +1,307 @@
+use std::cmp::Ordering;
+
+use crate::core::{BigInt, Int};
+
+/// An exact base 10 number, written with a `d` suffix (`19.99d`), for
+/// calculations such as money where binary floats would introduce rounding
+/// errors. It's stored as an int coefficient and the number of digits after
+/// the decimal point, so `19.99d` is `1999` with a scale of `2`.
+///
+/// Addition, subtraction, multiplication and remainders are always exact,
+/// and keep the trailing zeros of their operands (`1.10d + 1d` is `2.10`).
+/// Division rounds to a number of digits set by a `DecimalContext`.
+#[derive(Clone)]
+pub struct Decimal {
+    pub coefficient: BigInt,
+    pub scale: u32,
+}
+
+/// How a decimal is rounded when it has more digits than can be kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// To the nearest value, with ties going to the even neighbour (so
+    /// `0.125` becomes `0.12`). Also known as banker's rounding.
+    #[default]
+    HalfEven,
+    /// To the nearest value, with ties going away from zero.
+    HalfUp,
+    /// To the nearest value, with ties going towards zero.
+    HalfDown,
+    /// Away from zero.
+    Up,
+    /// Towards zero, i.e. truncating.
+    Down,
+    /// Towards positive infinity.
+    Ceiling,
+    /// Towards negative infinity.
+    Floor,
+}
+
+/// How decimal division is rounded. See `Interpreter::set_decimal_context`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalContext {
+    /// The most digits kept after the decimal point.
+    pub precision: u32,
+    pub rounding: Rounding,
+}
+
+impl Default for DecimalContext {
+    fn default() -> Self {
+        Self {
+            precision: 28,
+            rounding: Rounding::HalfEven,
+        }
+    }
+}
+
+/// Returns `value * 10^exponent`.
+fn scale_up(value: &BigInt, exponent: u32) -> BigInt {
+    let ten = BigInt::from_i64(10);
+    let mut scaled = value.clone();
+    for _ in 0..exponent {
+        scaled = scaled.mul(&ten);
+    }
+    scaled
+}
+
+fn abs(value: &BigInt) -> BigInt {
+    if value.is_negative() {
+        value.neg()
+    } else {
+        value.clone()
+    }
+}
+
+impl Decimal {
+    pub fn new(coefficient: BigInt, scale: u32) -> Self {
+        Self { coefficient, scale }
+    }
+
+    pub fn from_int(int: &Int) -> Self {
+        Self::new(int.to_big(), 0)
+    }
+
+    /// Parses a decimal such as `-19.99`, without the `d` suffix.
+    pub fn parse(src: &str) -> Option<Self> {
+        let (whole, fraction) = src.split_once('.').unwrap_or((src, ""));
+        let coefficient = BigInt::parse(&format!("{}{}", whole, fraction))?;
+        Some(Self::new(coefficient, fraction.len() as u32))
+    }
+
+    /// Converts a float to the shortest decimal which converts back to it.
+    /// Returns `None` for infinities and NaN.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Self::parse(&value.to_string())
+    }
+
+    /// Returns the nearest float to the value.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Returns the int part of the value, rounding towards zero.
+    pub fn to_int(&self) -> Int {
+        let divisor = scale_up(&BigInt::from_i64(1), self.scale);
+        match self.coefficient.div_rem(&divisor) {
+            Some((quotient, _)) => Int::from_big(quotient),
+            None => Int::new(0),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficient.is_zero()
+    }
+
+    /// Returns both coefficients at the larger of the two scales, and that
+    /// scale.
+    fn align(&self, other: &Self) -> (BigInt, BigInt, u32) {
+        let scale = self.scale.max(other.scale);
+        (
+            scale_up(&self.coefficient, scale - self.scale),
+            scale_up(&other.coefficient, scale - other.scale),
+            scale,
+        )
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::new(self.coefficient.neg(), self.scale)
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let (a, b, scale) = self.align(other);
+        Self::new(a.add(&b), scale)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        let (a, b, scale) = self.align(other);
+        Self::new(a.sub(&b), scale)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(
+            self.coefficient.mul(&other.coefficient),
+            self.scale + other.scale,
+        )
+    }
+
+    /// The remainder of dividing by `other`, which has the same sign as
+    /// `self`. Returns `None` if `other` is zero.
+    pub fn rem(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.align(other);
+        let (_, remainder) = a.div_rem(&b)?;
+        Some(Self::new(remainder, scale))
+    }
+
+    /// Divides by `other`, rounding the result to the context's precision.
+    /// Trailing zeros are removed from the result, but it keeps at least as
+    /// many digits as `self` has more than `other` (so `10.00d / 4` is
+    /// `2.50`). Returns `None` if `other` is zero.
+    pub fn div(&self, other: &Self, context: &DecimalContext) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        // Scale the operands so that their quotient is the result's
+        // coefficient at the context's precision
+        let exponent = context.precision as i64 + other.scale as i64 - self.scale as i64;
+        let (numerator, denominator) = if exponent >= 0 {
+            (
+                scale_up(&self.coefficient, exponent as u32),
+                other.coefficient.clone(),
+            )
+        } else {
+            (
+                self.coefficient.clone(),
+                scale_up(&other.coefficient, (-exponent) as u32),
+            )
+        };
+        let (quotient, remainder) = numerator.div_rem(&denominator)?;
+        let negative = numerator.is_negative() != denominator.is_negative();
+        let mut quotient = round(
+            quotient,
+            &remainder,
+            &denominator,
+            negative,
+            context.rounding,
+        );
+
+        let ten = BigInt::from_i64(10);
+        let mut scale = context.precision;
+        let min_scale = self.scale.saturating_sub(other.scale).min(scale);
+        while scale > min_scale {
+            match quotient.div_rem(&ten) {
+                Some((shorter, digit)) if digit.is_zero() => {
+                    quotient = shorter;
+                    scale -= 1;
+                }
+                _ => break,
+            }
+        }
+        Some(Self::new(quotient, scale))
+    }
+
+    /// Rounds to `digits` digits after the decimal point, or to a multiple of
+    /// `10^-digits` if `digits` is negative, with ties going to the even
+    /// neighbour. Values with no more digits than that are returned as they
+    /// are.
+    pub fn round(&self, digits: i64) -> Self {
+        let shift = (self.scale as i64).saturating_sub(digits);
+        if shift <= 0 {
+            return self.clone();
+        }
+        let scale = digits.max(0) as u32;
+        // Values less than a tenth of the unit being rounded to round to
+        // zero, which saves scaling by huge powers of ten.
+        if shift > abs(&self.coefficient).to_string().len() as i64 {
+            return Self::new(BigInt::from_i64(0), scale);
+        }
+        let divisor = scale_up(&BigInt::from_i64(1), shift as u32);
+        let Some((quotient, remainder)) = self.coefficient.div_rem(&divisor) else {
+            return self.clone();
+        };
+        let negative = self.coefficient.is_negative();
+        let quotient = round(quotient, &remainder, &divisor, negative, Rounding::HalfEven);
+        match u32::try_from(-digits) {
+            Ok(exponent) if exponent > 0 => Self::new(scale_up(&quotient, exponent), 0),
+            _ => Self::new(quotient, scale),
+        }
+    }
+}
+
+/// Rounds a truncated quotient, given the remainder and divisor it was left
+/// with, and whether the exact result is negative.
+fn round(
+    quotient: BigInt,
+    remainder: &BigInt,
+    divisor: &BigInt,
+    negative: bool,
+    rounding: Rounding,
+) -> BigInt {
+    if remainder.is_zero() {
+        return quotient;
+    }
+    // How the remainder compares to half of the divisor
+    let twice = abs(remainder).add(&abs(remainder));
+    let half = twice.cmp(&abs(divisor));
+    let is_odd = quotient
+        .div_rem(&BigInt::from_i64(2))
+        .is_some_and(|(_, parity)| !parity.is_zero());
+    let away_from_zero = match rounding {
+        Rounding::Up => true,
+        Rounding::Down => false,
+        Rounding::Ceiling => !negative,
+        Rounding::Floor => negative,
+        Rounding::HalfUp => half != Ordering::Less,
+        Rounding::HalfDown => half == Ordering::Greater,
+        Rounding::HalfEven => half == Ordering::Greater || (half == Ordering::Equal && is_odd),
+    };
+    if !away_from_zero {
+        quotient
+    } else if negative {
+        quotient.sub(&BigInt::from_i64(1))
+    } else {
+        quotient.add(&BigInt::from_i64(1))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    /// Compares the values numerically, so `1.0d` and `1.00d` are equal.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b, _) = self.align(other);
+        a.cmp(&b)
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = abs(&self.coefficient).to_string();
+        let scale = self.scale as usize;
+        // Pad with zeros so there's at least one digit before the point
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        if self.coefficient.is_negative() {
+            write!(f, "-")?;
+        }
+        if fraction.is_empty() {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, fraction)
+        }
+    }
+}
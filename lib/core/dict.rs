@@ -0,0 +1,171 @@
+use std::{cell::RefCell, collections::HashMap, mem::size_of, rc::Rc};
+
+use crate::{
+    core::{heap, ops, Bool, Int, Object, Str},
+    errors::{ErrorType, RuntimeError},
+};
+
+/// An object which can be used as a key in a `Dict`. Only ints, strings and
+/// bools can be hashed, and keys of different types are never equal, so `1`
+/// and `true` are distinct keys.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Int(Int),
+    Str(String),
+    Bool(bool),
+}
+
+impl Key {
+    pub fn from_object(object: &Object) -> Result<Key, RuntimeError> {
+        match object {
+            Object::Int(int) => Ok(Key::Int(int.clone())),
+            Object::Str(str) => Ok(Key::Str(str.value.clone())),
+            Object::Bool(bool) => Ok(Key::Bool(bool.value)),
+            _ => Err(RuntimeError::new(
+                ErrorType::TypeError,
+                &format!(
+                    "Unhashable type '{}': only int, str and bool can be used as dict keys",
+                    object.type_name()
+                ),
+            )),
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            Key::Int(value) => value.clone().into(),
+            Key::Str(value) => Str {
+                value: value.clone(),
+            }
+            .into(),
+            Key::Bool(value) => Bool { value: *value }.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Int(value) => write!(f, "{}", value),
+            Key::Str(value) => write!(f, "{:?}", value),
+            Key::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: Vec<(Key, Object)>,
+    indices: HashMap<Key, usize>,
+}
+
+impl heap::Trace for Entries {
+    fn trace(&self, visit: &mut dyn FnMut(Rc<dyn heap::Node>)) {
+        for (_, value) in &self.entries {
+            heap::trace_object(value, visit);
+        }
+    }
+
+    fn clear(&mut self) {
+        std::mem::take(self);
+    }
+
+    fn size(&self) -> usize {
+        self.entries.capacity() * size_of::<(Key, Object)>()
+            + self.indices.capacity() * size_of::<(Key, usize)>()
+    }
+}
+
+/// A mapping from keys to objects, which iterates in the order keys were
+/// first inserted. Like lists, dicts are shared references, so cloning a
+/// `Dict` only clones the reference (see `List`).
+#[derive(Clone)]
+pub struct Dict {
+    entries: Rc<RefCell<Entries>>,
+}
+
+impl Default for Dict {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self {
+            entries: heap::track(Entries::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.entries.borrow().indices.contains_key(key)
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Object> {
+        let entries = self.entries.borrow();
+        let &i = entries.indices.get(key)?;
+        Some(entries.entries[i].1.clone())
+    }
+
+    /// Inserts a value, returning the value previously stored under the key.
+    /// Replacing a value keeps the key's original position.
+    pub fn insert(&self, key: Key, value: Object) -> Option<Object> {
+        let mut entries = self.entries.borrow_mut();
+        let Entries { entries, indices } = &mut *entries;
+        match indices.get(&key) {
+            Some(&i) => Some(std::mem::replace(&mut entries[i].1, value)),
+            None => {
+                indices.insert(key.clone(), entries.len());
+                entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Returns a copy of the entries in insertion order.
+    pub fn entries(&self) -> Vec<(Key, Object)> {
+        self.entries.borrow().entries.clone()
+    }
+
+    pub fn keys(&self) -> Vec<Key> {
+        let entries = self.entries.borrow();
+        entries.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    /// Whether both dicts are the same dict.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.entries, &other.entries)
+    }
+
+    /// The dict's address, which identifies it while it's alive.
+    pub fn id(&self) -> *const () {
+        Rc::as_ptr(&self.entries) as *const ()
+    }
+
+    pub(crate) fn node(&self) -> Rc<dyn heap::Node> {
+        self.entries.clone()
+    }
+}
+
+/// Dicts are equal if they have the same keys with equal values, whatever
+/// order the keys were inserted in.
+impl PartialEq for Dict {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || ops::equals_once(self.id(), other.id(), || {
+                self.len() == other.len()
+                    && self
+                        .entries()
+                        .iter()
+                        .all(|(key, value)| other.get(key).as_ref() == Some(value))
+            })
+    }
+}
@@ -0,0 +1,68 @@
+use std::rc::Rc;
+
+use crate::{
+    core::{heap, Object},
+    interpreter::EnvRef,
+    parser::ast::{Node, Param},
+};
+
+/// A function value, created by a `fn` declaration or expression. The scope
+/// the function was created in is shared rather than copied, so the function
+/// sees any later changes to the variables it captures.
+pub struct Function {
+    /// The declared name, or `None` for an anonymous `fn(...) ... end`.
+    pub name: Option<String>,
+    pub params: Rc<[Param]>,
+    pub body: Rc<Node>,
+    pub env: EnvRef,
+}
+
+impl Function {
+    /// The name used to refer to the function in error messages.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("<lambda>")
+    }
+
+    /// The function's name and parameters as shown in error messages, e.g.
+    /// `connect(host, port = ..., *rest)`.
+    pub fn signature(&self) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|param| match param {
+                Param { variadic: true, .. } => format!("*{}", param.name),
+                Param {
+                    default: Some(_), ..
+                } => format!("{} = ...", param.name),
+                _ => param.name.clone(),
+            })
+            .collect();
+        format!("{}({})", self.name(), params.join(", "))
+    }
+}
+
+impl heap::Node for Function {
+    fn trace_node(&self, visit: &mut dyn FnMut(Rc<dyn heap::Node>)) -> bool {
+        visit(self.env.clone());
+        true
+    }
+
+    fn clear_node(&self) {}
+
+    fn node_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+// The captured scope may contain the function itself, so it is left out.
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<function {}>", self.name())
+    }
+}
+
+impl From<Function> for Object {
+    fn from(value: Function) -> Object {
+        Object::Function(Rc::new(value))
+    }
+}
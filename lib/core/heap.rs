@@ -0,0 +1,257 @@
+//! The garbage collector, which frees objects that only refer to each other.
+//!
+//! Objects are reference counted, so most are freed as soon as nothing
+//! refers to them. Objects which refer to each other in a cycle never are,
+//! e.g. a list which contains itself, or a function stored in a variable of
+//! the scope it captures. So every list, dict, struct instance and scope is
+//! tracked when it's created, and `collect` traces the graph of tracked
+//! objects and frees the ones which can't be reached, by emptying them to
+//! break their cycles.
+//!
+//! An object can be reached if it's one of the roots given to `collect` (the
+//! interpreter passes the scopes on its call stack), if something outside of
+//! the graph refers to it (e.g. the host, or a value the interpreter is in
+//! the middle of using), or if an object which can be reached refers to it.
+//! Objects are tracked per thread, so a collection only sees the objects
+//! created on the thread it runs on.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    mem::size_of,
+    rc::{Rc, Weak},
+};
+
+use crate::core::Object;
+
+/// The contents of an object which can be part of a cycle.
+pub(crate) trait Trace {
+    /// Calls `visit` with each object in the graph this one refers to.
+    fn trace(&self, visit: &mut dyn FnMut(Rc<dyn Node>));
+    /// Removes everything the object refers to.
+    fn clear(&mut self);
+    /// Roughly how many bytes the object uses.
+    fn size(&self) -> usize;
+}
+
+/// An object in the graph, which is either the contents of a container
+/// behind a `RefCell`, or a function or struct type, which can't be changed
+/// once created.
+pub(crate) trait Node {
+    /// Calls `visit` with each object this one refers to, returning false if
+    /// the object is being modified so can't be inspected.
+    fn trace_node(&self, visit: &mut dyn FnMut(Rc<dyn Node>)) -> bool;
+    fn clear_node(&self);
+    fn node_size(&self) -> usize;
+}
+
+impl<T: Trace> Node for RefCell<T> {
+    fn trace_node(&self, visit: &mut dyn FnMut(Rc<dyn Node>)) -> bool {
+        match self.try_borrow() {
+            Ok(contents) => {
+                contents.trace(visit);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn clear_node(&self) {
+        if let Ok(mut contents) = self.try_borrow_mut() {
+            contents.clear();
+        }
+    }
+
+    fn node_size(&self) -> usize {
+        size_of::<Self>() + self.try_borrow().map_or(0, |contents| contents.size())
+    }
+}
+
+/// Calls `visit` with the objects in the graph which `object` refers to.
+pub(crate) fn trace_object(object: &Object, visit: &mut dyn FnMut(Rc<dyn Node>)) {
+    match object {
+        Object::List(list) => visit(list.node()),
+        Object::Dict(dict) => visit(dict.node()),
+        Object::Struct(instance) => {
+            visit(instance.node());
+            visit(instance.ty.clone());
+        }
+        Object::Function(function) => visit(function.clone()),
+        Object::StructType(ty) => visit(ty.clone()),
+        _ => {}
+    }
+}
+
+/// When the interpreter collects garbage. See
+/// `Interpreter::set_gc_settings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcSettings {
+    /// Whether garbage is collected automatically. `collect` can still be
+    /// called when it isn't.
+    pub enabled: bool,
+    /// How many objects are created between automatic collections.
+    pub threshold: usize,
+}
+
+impl Default for GcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 1000,
+        }
+    }
+}
+
+/// What a collection freed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collection {
+    /// How many objects were freed.
+    pub freed: usize,
+    /// Roughly how many bytes the freed objects used.
+    pub bytes_freed: usize,
+}
+
+/// Totals over every collection an interpreter has run. See
+/// `Interpreter::gc_stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    pub collections: usize,
+    pub freed: usize,
+    pub bytes_freed: usize,
+}
+
+impl GcStats {
+    pub fn record(&mut self, collection: Collection) {
+        self.collections += 1;
+        self.freed += collection.freed;
+        self.bytes_freed += collection.bytes_freed;
+    }
+}
+
+#[derive(Default)]
+struct Heap {
+    tracked: Vec<Weak<dyn Node>>,
+    /// How many objects were tracked when freed ones were last forgotten.
+    pruned_at: usize,
+    /// How many objects have been created since the last collection.
+    allocations: usize,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::default());
+}
+
+/// Creates an object which can be part of a cycle, and starts tracking it.
+pub(crate) fn track<T: Trace + 'static>(contents: T) -> Rc<RefCell<T>> {
+    let object = Rc::new(RefCell::new(contents));
+    let node: Rc<dyn Node> = object.clone();
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.tracked.push(Rc::downgrade(&node));
+        heap.allocations += 1;
+        // Forget objects which have been freed once the list has doubled in
+        // size, so it doesn't grow forever.
+        if heap.tracked.len() >= (heap.pruned_at * 2).max(64) {
+            heap.tracked.retain(|object| object.strong_count() > 0);
+            heap.pruned_at = heap.tracked.len();
+        }
+    });
+    object
+}
+
+/// Returns how many objects have been created on this thread since the last
+/// collection.
+pub fn allocations() -> usize {
+    HEAP.with(|heap| heap.borrow().allocations)
+}
+
+/// Returns how many tracked objects created on this thread are still alive.
+pub fn live() -> usize {
+    HEAP.with(|heap| {
+        heap.borrow()
+            .tracked
+            .iter()
+            .filter(|object| object.strong_count() > 0)
+            .count()
+    })
+}
+
+/// The address of an object in the graph, which identifies it.
+fn address(node: &Rc<dyn Node>) -> *const () {
+    Rc::as_ptr(node) as *const ()
+}
+
+/// Frees the objects created on this thread which can't be reached from
+/// `roots` (the addresses of objects, e.g. from `List::id`) or from outside
+/// of the graph.
+pub fn collect(roots: &[*const ()]) -> Collection {
+    let mut nodes: Vec<Rc<dyn Node>> = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.allocations = 0;
+        heap.tracked.retain(|object| object.strong_count() > 0);
+        heap.pruned_at = heap.tracked.len();
+        heap.tracked.iter().filter_map(Weak::upgrade).collect()
+    });
+    let mut index: HashMap<*const (), usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (address(node), i))
+        .collect();
+
+    // Trace the graph, adding the functions and struct types it refers to,
+    // which aren't tracked themselves. An object which is being modified is
+    // treated as a root.
+    let mut edges: Vec<Vec<usize>> = Vec::new();
+    let mut busy = Vec::new();
+    let mut i = 0;
+    while i < nodes.len() {
+        let mut children = Vec::new();
+        let node = nodes[i].clone();
+        let traced = node.trace_node(&mut |child| {
+            let next = nodes.len();
+            let child_index = *index.entry(address(&child)).or_insert(next);
+            if child_index == next {
+                nodes.push(child);
+            }
+            children.push(child_index);
+        });
+        if !traced {
+            busy.push(i);
+        }
+        edges.push(children);
+        i += 1;
+    }
+
+    // Count the references to each object which come from outside of the
+    // graph, not counting the one held here.
+    let mut external: Vec<usize> = nodes
+        .iter()
+        .map(|node| Rc::strong_count(node) - 1)
+        .collect();
+    for children in &edges {
+        for &child in children {
+            external[child] -= 1;
+        }
+    }
+
+    let mut stack: Vec<usize> = (0..nodes.len()).filter(|&i| external[i] > 0).collect();
+    stack.extend(busy);
+    stack.extend(roots.iter().filter_map(|root| index.get(root)));
+    let mut reachable = vec![false; nodes.len()];
+    while let Some(i) = stack.pop() {
+        if !reachable[i] {
+            reachable[i] = true;
+            stack.extend(&edges[i]);
+        }
+    }
+
+    let mut collection = Collection::default();
+    for (node, reachable) in nodes.iter().zip(reachable) {
+        if !reachable {
+            collection.freed += 1;
+            collection.bytes_freed += node.node_size();
+            node.clear_node();
+        }
+    }
+    collection
+}
@@ -1,14 +1,103 @@
-use crate::core::{Bool, Float, Str};
+use std::cmp::Ordering;
 
-pub struct Int {
-    pub value: i32,
+use crate::core::BigInt;
+
+/// An int, which is stored in 64 bits unless it's too large, in which case
+/// it's promoted to a `BigInt`. Ints are always stored in the smallest
+/// representation which fits, so equal ints are stored the same way.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Int {
+    Small(i64),
+    Big(BigInt),
+}
+
+/// What happens when the result of integer arithmetic doesn't fit in 64
+/// bits. See `Interpreter::set_overflow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// The result is promoted to a `BigInt`, so arithmetic is always exact.
+    #[default]
+    Promote,
+    /// An `OverflowError` is raised.
+    Error,
 }
 
 impl Int {
-    pub fn new(value: i32) -> Self {
-        Self { value }
+    pub fn new(value: i64) -> Self {
+        Int::Small(value)
+    }
+
+    /// Creates an int from a `BigInt`, storing it in 64 bits if it fits.
+    pub fn from_big(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Int::Small(value),
+            None => Int::Big(value),
+        }
+    }
+
+    /// Parses a base 10 int of any size, with an optional sign.
+    pub fn parse(src: &str) -> Option<Self> {
+        match src.parse::<i64>() {
+            Ok(value) => Some(Int::Small(value)),
+            Err(_) => BigInt::parse(src).map(Self::from_big),
+        }
+    }
+
+    /// Returns the value as an `i64`, if it fits in one.
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Int::Small(value) => Some(*value),
+            Int::Big(_) => None,
+        }
+    }
+
+    pub fn to_big(&self) -> BigInt {
+        match self {
+            Int::Small(value) => BigInt::from_i64(*value),
+            Int::Big(value) => value.clone(),
+        }
+    }
+
+    /// Returns the nearest float to the value.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Int::Small(value) => *value as f64,
+            Int::Big(value) => value.to_f64(),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Int::Small(0))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Int::Small(value) => *value < 0,
+            Int::Big(value) => value.is_negative(),
+        }
+    }
+}
+
+impl PartialOrd for Int {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Int::Small(a), Int::Small(b)) => a.cmp(b),
+            _ => self.to_big().cmp(&other.to_big()),
+        }
+    }
+}
+
+impl std::fmt::Display for Int {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Int::Small(value) => write!(f, "{}", value),
+            Int::Big(value) => write!(f, "{}", value),
+        }
     }
 }
-impl_into!(Int => Float, |self| { Ok(Float { value: self.value as f32 }) });
-impl_into!(Int => Str, |self| { Ok(Str { value: self.value.to_string() }) });
-impl_into!(Int => Bool, |self| { Ok(Bool { value: self.value != 0 }) });
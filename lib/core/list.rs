@@ -1,5 +1,102 @@
-use crate::core::Object;
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    rc::Rc,
+};
 
+use crate::core::{heap, ops, Object};
+
+/// A list of objects.
+///
+/// Lists are shared references: assigning a list to a variable, passing it
+/// to a function or storing it in another container doesn't copy it, so
+/// changes made through any of these names are seen by all of them. Cloning
+/// a `List` likewise only clones the reference. `copy` creates a new list
+/// with the same elements, which are themselves still shared.
+///
+/// Lists are equal if they have the same length and their elements are
+/// equal (see `ops::equals`), and a list may contain itself.
+#[derive(Clone, Default)]
 pub struct List {
-    pub elements: Vec<Object>,
+    elements: Rc<RefCell<Vec<Object>>>,
+}
+
+impl heap::Trace for Vec<Object> {
+    fn trace(&self, visit: &mut dyn FnMut(Rc<dyn heap::Node>)) {
+        for element in self {
+            heap::trace_object(element, visit);
+        }
+    }
+
+    fn clear(&mut self) {
+        std::mem::take(self);
+    }
+
+    fn size(&self) -> usize {
+        self.capacity() * std::mem::size_of::<Object>()
+    }
+}
+
+impl List {
+    pub fn new(elements: Vec<Object>) -> Self {
+        Self {
+            elements: heap::track(elements),
+        }
+    }
+
+    /// Borrows the elements. Panics if the list is being modified.
+    pub fn borrow(&self) -> Ref<'_, Vec<Object>> {
+        self.elements.borrow()
+    }
+
+    /// Borrows the elements mutably. Panics if the list is borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, Vec<Object>> {
+        self.elements.borrow_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.borrow().is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Object> {
+        self.borrow().get(index).cloned()
+    }
+
+    /// Returns a copy of the elements.
+    pub fn to_vec(&self) -> Vec<Object> {
+        self.borrow().clone()
+    }
+
+    pub fn push(&self, value: Object) {
+        self.borrow_mut().push(value);
+    }
+
+    /// Creates a new list with the same elements.
+    pub fn copy(&self) -> Self {
+        Self::new(self.to_vec())
+    }
+
+    /// Whether both lists are the same list.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.elements, &other.elements)
+    }
+
+    /// The list's address, which identifies it while it's alive.
+    pub fn id(&self) -> *const () {
+        Rc::as_ptr(&self.elements) as *const ()
+    }
+
+    pub(crate) fn node(&self) -> Rc<dyn heap::Node> {
+        self.elements.clone()
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || ops::equals_once(self.id(), other.id(), || *self.borrow() == *other.borrow())
+    }
 }
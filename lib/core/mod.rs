@@ -1,25 +1,34 @@
-macro_rules! impl_into {
-    ($from:ty => $to:ty, |$var:ident| $code:block) => {
-        impl TryInto<$to> for $from {
-            type Error = ();
-            fn try_into($var: $from) -> Result<$to, Self::Error> {
-                $code
-            }
-        }
-    };
-}
-
 pub mod object;
 pub use object::Object;
 
+pub mod ops;
+
+pub mod bigint;
 pub mod bool;
+pub mod convert;
+pub mod decimal;
+pub mod dict;
 pub mod float;
+pub mod function;
+pub mod heap;
 pub mod int;
 pub mod list;
+pub mod none;
+pub mod range;
+mod repr;
 pub mod str;
+pub mod structs;
 
 pub use self::bool::Bool;
 pub use self::str::Str;
+pub use bigint::BigInt;
+pub use convert::{ConversionError, ConversionErrorKind};
+pub use decimal::{Decimal, DecimalContext, Rounding};
+pub use dict::{Dict, Key};
 pub use float::Float;
-pub use int::Int;
+pub use function::Function;
+pub use int::{Int, Overflow};
 pub use list::List;
+pub use none::NoneType;
+pub use range::Range;
+pub use structs::{Struct, StructType};
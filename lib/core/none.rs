@@ -0,0 +1,12 @@
+use crate::core::Object;
+
+/// The type of `none`, the absence of a value, which is represented by the
+/// `Object::None` variant.
+#[derive(Clone, PartialEq, Eq)]
+pub struct NoneType;
+
+impl From<NoneType> for Object {
+    fn from(_: NoneType) -> Object {
+        Object::None
+    }
+}
@@ -1,25 +1,89 @@
-use crate::core::{Bool, Float, Int, List, Str};
+use std::rc::Rc;
 
+use crate::core::{
+    ops, Bool, Decimal, Dict, Float, Function, Int, List, Range, Str, Struct, StructType,
+};
+
+#[derive(Clone)]
 pub enum Object {
     Bool(Bool),
+    Decimal(Decimal),
+    Dict(Dict),
     Float(Float),
+    Function(Rc<Function>),
     List(List),
+    Range(Range),
     Str(Str),
+    Struct(Struct),
+    /// A type declared with `struct`, which creates instances when called.
+    StructType(Rc<StructType>),
     Int(Int),
+    /// The absence of a value, e.g. the result of a function which finishes
+    /// without returning anything.
+    None,
+}
+
+impl Object {
+    /// Returns the name of the object's type, as shown to the user.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Bool(_) => "bool",
+            Object::Decimal(_) => "decimal",
+            Object::Dict(_) => "dict",
+            Object::Float(_) => "float",
+            Object::Function(_) => "function",
+            Object::List(_) => "list",
+            Object::Range(_) => "range",
+            Object::Str(_) => "str",
+            Object::Struct(_) => "struct",
+            Object::StructType(_) => "type",
+            Object::Int(_) => "int",
+            Object::None => "none",
+        }
+    }
+
+    /// Whether the object counts as true when used as a condition.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::Bool(bool) => bool.value,
+            Object::Decimal(decimal) => !decimal.is_zero(),
+            Object::Dict(dict) => !dict.is_empty(),
+            Object::Float(float) => float.value != 0.0,
+            Object::Function(_) => true,
+            Object::List(list) => !list.is_empty(),
+            Object::Range(range) => !range.is_empty(),
+            Object::Str(str) => !str.value.is_empty(),
+            Object::Struct(_) | Object::StructType(_) => true,
+            Object::Int(int) => !int.is_zero(),
+            Object::None => false,
+        }
+    }
 }
 
 macro_rules! impl_into_obj {
     ($ty:ident) => {
-        impl Into<Object> for $ty {
-            fn into(self) -> Object {
-                Object::$ty(self)
+        impl From<$ty> for Object {
+            fn from(value: $ty) -> Object {
+                Object::$ty(value)
             }
         }
     };
 }
 
 impl_into_obj!(Bool);
+impl_into_obj!(Decimal);
+impl_into_obj!(Dict);
 impl_into_obj!(Float);
 impl_into_obj!(Int);
 impl_into_obj!(List);
+impl_into_obj!(Range);
 impl_into_obj!(Str);
+
+/// Objects are equal if `==` says they are (see `ops::equals`), so an int
+/// equals a float with the same value, and containers are equal if their
+/// elements are.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        ops::equals(self, other)
+    }
+}
@@ -0,0 +1,620 @@
+//! The semantics of SAP's operators, shared by everything which needs to
+//! evaluate them (e.g. the interpreter).
+
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
+
+use crate::{
+    core::{BigInt, Bool, Decimal, DecimalContext, Float, Int, Key, List, Object, Overflow, Str},
+    errors::{ErrorType, RuntimeError},
+    parser::ast::Operator,
+};
+
+fn unsupported_operands(op: &Operator, lhs: &Object, rhs: &Object) -> RuntimeError {
+    RuntimeError::new(
+        ErrorType::TypeError,
+        &format!(
+            "Unsupported operand types for '{}': '{}' and '{}'",
+            op,
+            lhs.type_name(),
+            rhs.type_name()
+        ),
+    )
+}
+
+/// Returns the result of integer arithmetic which was worked out as a
+/// `BigInt`, raising an error if it doesn't fit in 64 bits and promotion is
+/// turned off.
+fn promote(op: &Operator, value: BigInt, overflow: Overflow) -> Result<Object, RuntimeError> {
+    match Int::from_big(value) {
+        Int::Big(_) if overflow == Overflow::Error => Err(RuntimeError::new(
+            ErrorType::OverflowError,
+            &format!("Integer overflow in '{}'", op),
+        )),
+        int => Ok(int.into()),
+    }
+}
+
+/// The parts of arithmetic which the host can configure (see
+/// `Interpreter::set_overflow` and `Interpreter::set_decimal_context`).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Arithmetic {
+    /// What happens when integer arithmetic doesn't fit in 64 bits.
+    pub overflow: Overflow,
+    /// How decimal division is rounded.
+    pub decimal: DecimalContext,
+}
+
+/// Applies a binary operator to two objects. Note that `and` and `or` are
+/// evaluated eagerly here; short circuiting is the caller's responsibility.
+pub fn binary_op(
+    op: &Operator,
+    lhs: &Object,
+    rhs: &Object,
+    settings: &Arithmetic,
+) -> Result<Object, RuntimeError> {
+    let result: Object = match op {
+        Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Mod => {
+            return arithmetic(op, lhs, rhs, settings)
+        }
+        Operator::Eq => Bool {
+            value: equals(lhs, rhs),
+        }
+        .into(),
+        Operator::NotEq => Bool {
+            value: !equals(lhs, rhs),
+        }
+        .into(),
+        Operator::Less | Operator::LessEq | Operator::More | Operator::MoreEq => {
+            let ordering = compare(lhs, rhs).ok_or_else(|| unsupported_operands(op, lhs, rhs))?;
+            let value = match op {
+                Operator::Less => ordering == Some(Ordering::Less),
+                Operator::LessEq => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                Operator::More => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            };
+            Bool { value }.into()
+        }
+        Operator::And => Bool {
+            value: lhs.is_truthy() && rhs.is_truthy(),
+        }
+        .into(),
+        Operator::Or => Bool {
+            value: lhs.is_truthy() || rhs.is_truthy(),
+        }
+        .into(),
+        Operator::Not => return Err(unsupported_operands(op, lhs, rhs)),
+    };
+    Ok(result)
+}
+
+/// Applies a unary operator (`-` or `not`) to an object.
+pub fn unary_op(
+    op: &Operator,
+    child: &Object,
+    settings: &Arithmetic,
+) -> Result<Object, RuntimeError> {
+    match (op, child) {
+        (Operator::Not, _) => Ok(Bool {
+            value: !child.is_truthy(),
+        }
+        .into()),
+        (Operator::Sub, Object::Int(Int::Small(value))) if *value != i64::MIN => {
+            Ok(Int::new(-value).into())
+        }
+        (Operator::Sub, Object::Int(int)) => promote(op, int.to_big().neg(), settings.overflow),
+        (Operator::Sub, Object::Float(float)) => Ok(Float {
+            value: -float.value,
+        }
+        .into()),
+        (Operator::Sub, Object::Decimal(decimal)) => Ok(decimal.neg().into()),
+        _ => Err(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!(
+                "Unsupported operand type for unary '{}': '{}'",
+                op,
+                child.type_name()
+            ),
+        )),
+    }
+}
+
+/// Whether two objects are equal. Objects of different types are never
+/// equal, except for numbers (ints, floats and decimals) which are compared
+/// numerically, so
+/// `none` is only equal to itself. Functions and struct types are only equal
+/// to themselves, and structs are equal if they have the same type and equal
+/// fields.
+pub fn equals(lhs: &Object, rhs: &Object) -> bool {
+    match (lhs, rhs) {
+        (Object::Int(a), Object::Int(b)) => a == b,
+        (Object::Float(a), Object::Float(b)) => a.value == b.value,
+        (Object::Int(a), Object::Float(b)) | (Object::Float(b), Object::Int(a)) => {
+            a.to_f64() == b.value
+        }
+        (Object::Decimal(_), Object::Decimal(_) | Object::Int(_))
+        | (Object::Int(_), Object::Decimal(_)) => as_decimal(lhs) == as_decimal(rhs),
+        (Object::Decimal(a), Object::Float(b)) | (Object::Float(b), Object::Decimal(a)) => {
+            a.to_f64() == b.value
+        }
+        (Object::Str(a), Object::Str(b)) => a.value == b.value,
+        (Object::Bool(a), Object::Bool(b)) => a.value == b.value,
+        (Object::List(a), Object::List(b)) => a == b,
+        (Object::Dict(a), Object::Dict(b)) => a == b,
+        (Object::Range(a), Object::Range(b)) => a == b,
+        (Object::Function(a), Object::Function(b)) => Rc::ptr_eq(a, b),
+        (Object::StructType(a), Object::StructType(b)) => Rc::ptr_eq(a, b),
+        (Object::Struct(a), Object::Struct(b)) => a == b,
+        (Object::None, Object::None) => true,
+        _ => false,
+    }
+}
+
+thread_local! {
+    /// The pairs of containers currently being compared by `equals_once`.
+    static COMPARING: RefCell<Vec<(*const (), *const ())>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Compares the containers at addresses `a` and `b` with `eq`, unless they
+/// are already being compared further up, in which case they're assumed to
+/// be equal. This stops comparisons of containers which contain themselves
+/// from recursing forever.
+pub(crate) fn equals_once(a: *const (), b: *const (), eq: impl FnOnce() -> bool) -> bool {
+    let pair = (a, b);
+    let comparing = COMPARING.with(|comparing| comparing.borrow().contains(&pair));
+    if comparing {
+        return true;
+    }
+    COMPARING.with(|comparing| comparing.borrow_mut().push(pair));
+    let equal = eq();
+    COMPARING.with(|comparing| comparing.borrow_mut().pop());
+    equal
+}
+
+fn not_subscriptable(container: &Object) -> RuntimeError {
+    RuntimeError::new(
+        ErrorType::TypeError,
+        &format!("'{}' object is not subscriptable", container.type_name()),
+    )
+}
+
+fn key_error(key: &Key) -> RuntimeError {
+    RuntimeError::new(ErrorType::KeyError, &format!("Key {} not found", key))
+}
+
+/// Converts an index object into an `i64`, for indexing into a sequence of
+/// the given type. Ints too large for 64 bits are out of range of any
+/// sequence, so they're clamped.
+fn as_index(index: &Object, container: &str) -> Result<i64, RuntimeError> {
+    match index {
+        Object::Int(Int::Small(value)) => Ok(*value),
+        Object::Int(int) if int.is_negative() => Ok(i64::MIN),
+        Object::Int(_) => Ok(i64::MAX),
+        _ => Err(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!(
+                "{} indices must be integers, not '{}'",
+                container,
+                index.type_name()
+            ),
+        )),
+    }
+}
+
+/// Resolves a (possibly negative) index into a sequence of length `len`,
+/// counting negative indices from the end of the sequence.
+pub(crate) fn resolve_index(
+    index: &Object,
+    len: usize,
+    container: &str,
+) -> Result<usize, RuntimeError> {
+    // Widened as ranges can be longer than the largest i64
+    let position = as_index(index, container)? as i128;
+    let resolved = if position < 0 {
+        position + len as i128
+    } else {
+        position
+    };
+    if resolved < 0 || resolved >= len as i128 {
+        return Err(RuntimeError::new(
+            ErrorType::IndexError,
+            &format!(
+                "Index {} is out of range for {} of length {}",
+                index, container, len
+            ),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Returns `container[index]`. Lists and strings are indexed by position
+/// (with negative positions counting from the end), and strings by
+/// character.
+pub fn get_index(container: &Object, index: &Object) -> Result<Object, RuntimeError> {
+    match container {
+        Object::Dict(dict) => {
+            let key = Key::from_object(index)?;
+            dict.get(&key).ok_or_else(|| key_error(&key))
+        }
+        Object::List(list) => {
+            let elements = list.borrow();
+            let i = resolve_index(index, elements.len(), "list")?;
+            Ok(elements[i].clone())
+        }
+        Object::Range(range) => {
+            let i = resolve_index(index, range.len(), "range")?;
+            Ok(Int::new(range.get(i).unwrap_or_default()).into())
+        }
+        Object::Str(str) => {
+            let len = str.value.chars().count();
+            let i = resolve_index(index, len, "str")?;
+            Ok(Str {
+                value: str
+                    .value
+                    .chars()
+                    .nth(i)
+                    .map(String::from)
+                    .unwrap_or_default(),
+            }
+            .into())
+        }
+        _ => Err(not_subscriptable(container)),
+    }
+}
+
+/// Performs `container[index] = value`, modifying the container in place.
+pub fn set_index(container: &Object, index: &Object, value: Object) -> Result<(), RuntimeError> {
+    match container {
+        Object::Dict(dict) => {
+            dict.insert(Key::from_object(index)?, value);
+            Ok(())
+        }
+        Object::List(list) => {
+            let mut elements = list.borrow_mut();
+            let i = resolve_index(index, elements.len(), "list")?;
+            elements[i] = value;
+            Ok(())
+        }
+        _ => Err(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!(
+                "'{}' object does not support index assignment",
+                container.type_name()
+            ),
+        )),
+    }
+}
+
+fn no_field(object: &Object, field: &str) -> RuntimeError {
+    let message = match object {
+        Object::Struct(instance) => format!("'{}' has no field '{}'", instance.ty.name, field),
+        _ => format!("'{}' object has no field '{}'", object.type_name(), field),
+    };
+    RuntimeError::new(ErrorType::AttributeError, &message)
+}
+
+/// Returns `object.field`.
+pub fn get_field(object: &Object, field: &str) -> Result<Object, RuntimeError> {
+    match object {
+        Object::Struct(instance) => instance.get(field),
+        _ => None,
+    }
+    .ok_or_else(|| no_field(object, field))
+}
+
+/// Performs `object.field = value`, modifying the object in place. Only
+/// fields declared by the struct's type can be assigned to.
+pub fn set_field(object: &Object, field: &str, value: Object) -> Result<(), RuntimeError> {
+    match object {
+        Object::Struct(instance) => instance.set(field, value),
+        _ => None,
+    }
+    .ok_or_else(|| no_field(object, field))
+}
+
+/// Returns the positions selected by the slice `[start:stop:step]` of a
+/// sequence of length `len`, following the same rules as Python: negative
+/// bounds count from the end, and out of range bounds are clamped.
+fn slice_positions(start: Option<i64>, stop: Option<i64>, step: i64, len: usize) -> Vec<usize> {
+    let len = len as i64;
+    let adjust = |bound: i64| -> i64 {
+        if bound < 0 {
+            (bound + len).max(if step < 0 { -1 } else { 0 })
+        } else if bound >= len {
+            if step < 0 {
+                len - 1
+            } else {
+                len
+            }
+        } else {
+            bound
+        }
+    };
+    let start = start.map_or(if step < 0 { len - 1 } else { 0 }, adjust);
+    let stop = stop.map_or(if step < 0 { -1 } else { len }, adjust);
+
+    let mut positions = Vec::new();
+    let mut i = Some(start);
+    // A step which would overflow goes past either end of the container
+    while let Some(position) = i.filter(|&i| (step > 0 && i < stop) || (step < 0 && i > stop)) {
+        positions.push(position as usize);
+        i = position.checked_add(step);
+    }
+    positions
+}
+
+/// Returns `container[start:stop:step]`, where any of the bounds may be
+/// omitted.
+pub fn get_slice(
+    container: &Object,
+    start: Option<&Object>,
+    stop: Option<&Object>,
+    step: Option<&Object>,
+) -> Result<Object, RuntimeError> {
+    let bound = |bound: Option<&Object>| -> Result<Option<i64>, RuntimeError> {
+        match bound {
+            None | Some(Object::None) => Ok(None),
+            Some(bound) => as_index(bound, container.type_name()).map(Some),
+        }
+    };
+    let (start, stop, step) = (bound(start)?, bound(stop)?, bound(step)?.unwrap_or(1));
+    if step == 0 {
+        return Err(RuntimeError::new(
+            ErrorType::ValueError,
+            "Slice step cannot be zero",
+        ));
+    }
+
+    match container {
+        Object::List(list) => {
+            let elements = list.borrow();
+            let positions = slice_positions(start, stop, step, elements.len());
+            Ok(List::new(positions.into_iter().map(|i| elements[i].clone()).collect()).into())
+        }
+        Object::Str(str) => {
+            let chars: Vec<char> = str.value.chars().collect();
+            let positions = slice_positions(start, stop, step, chars.len());
+            Ok(Str {
+                value: positions.into_iter().map(|i| chars[i]).collect(),
+            }
+            .into())
+        }
+        _ => Err(not_subscriptable(container)),
+    }
+}
+
+/// Returns the objects produced by iterating over `object`: the elements of
+/// a list, the characters of a string, the keys of a dict, or the ints in a
+/// range. The elements of lists and dicts are copied first, so the loop body
+/// can modify them without affecting the iteration.
+pub fn iterate(object: &Object) -> Result<Box<dyn Iterator<Item = Object>>, RuntimeError> {
+    match object {
+        Object::List(list) => Ok(Box::new(list.to_vec().into_iter())),
+        Object::Str(str) => {
+            let chars: Vec<char> = str.value.chars().collect();
+            Ok(Box::new(chars.into_iter().map(|c| {
+                Str {
+                    value: c.to_string(),
+                }
+                .into()
+            })))
+        }
+        Object::Dict(dict) => {
+            let keys: Vec<Object> = dict.keys().iter().map(Key::to_object).collect();
+            Ok(Box::new(keys.into_iter()))
+        }
+        Object::Range(range) => Ok(Box::new(range.iter().map(|i| Int::new(i).into()))),
+        _ => Err(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!("'{}' object is not iterable", object.type_name()),
+        )),
+    }
+}
+
+/// Orders two objects. Returns `None` if the objects can't be ordered at all,
+/// and `Some(None)` if they can but happen to be unordered (i.e. NaN).
+pub fn compare(lhs: &Object, rhs: &Object) -> Option<Option<Ordering>> {
+    match (lhs, rhs) {
+        (Object::Int(a), Object::Int(b)) => Some(Some(a.cmp(b))),
+        (Object::Str(a), Object::Str(b)) => Some(a.value.partial_cmp(&b.value)),
+        (Object::Decimal(_), Object::Decimal(_) | Object::Int(_))
+        | (Object::Int(_), Object::Decimal(_)) => {
+            Some(Some(as_decimal(lhs)?.cmp(&as_decimal(rhs)?)))
+        }
+        (
+            Object::Int(_) | Object::Float(_) | Object::Decimal(_),
+            Object::Int(_) | Object::Float(_) | Object::Decimal(_),
+        ) => Some(as_float(lhs)?.partial_cmp(&as_float(rhs)?)),
+        _ => None,
+    }
+}
+
+fn as_float(object: &Object) -> Option<f64> {
+    match object {
+        Object::Int(int) => Some(int.to_f64()),
+        Object::Float(float) => Some(float.value),
+        Object::Decimal(decimal) => Some(decimal.to_f64()),
+        _ => None,
+    }
+}
+
+/// Converts an object into a decimal, if it can be without losing
+/// precision.
+fn as_decimal(object: &Object) -> Option<Decimal> {
+    match object {
+        Object::Int(int) => Some(Decimal::from_int(int)),
+        Object::Decimal(decimal) => Some(decimal.clone()),
+        _ => None,
+    }
+}
+
+/// The longest string (in bytes) or list which repeating a sequence may
+/// create.
+pub const MAX_REPEAT_LEN: usize = 1 << 28;
+
+/// Converts the int a sequence of length `len` is multiplied by into the
+/// number of times to repeat it, where negative ints repeat it no times.
+/// Raises an error if the result would be longer than `MAX_REPEAT_LEN`.
+fn repeat_count(times: &Int, len: usize) -> Result<usize, RuntimeError> {
+    let count = match times {
+        Int::Small(value) => usize::try_from((*value).max(0)).ok(),
+        Int::Big(_) if times.is_negative() => Some(0),
+        Int::Big(_) => None,
+    };
+    if len == 0 {
+        return Ok(0);
+    }
+    match count {
+        Some(count) if len.checked_mul(count).is_some_and(|n| n <= MAX_REPEAT_LEN) => Ok(count),
+        _ => Err(RuntimeError::new(
+            ErrorType::OverflowError,
+            &format!(
+                "Cannot repeat a sequence of length {} {} times, as the result would be too long",
+                len, times
+            ),
+        )),
+    }
+}
+
+fn repeat<T: Clone>(items: &[T], times: usize) -> Vec<T> {
+    let mut repeated = Vec::with_capacity(items.len() * times);
+    for _ in 0..times {
+        repeated.extend_from_slice(items);
+    }
+    repeated
+}
+
+fn arithmetic(
+    op: &Operator,
+    lhs: &Object,
+    rhs: &Object,
+    settings: &Arithmetic,
+) -> Result<Object, RuntimeError> {
+    if matches!(lhs, Object::None) || matches!(rhs, Object::None) {
+        return Err(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!(
+                "Cannot use 'none' with '{}', as it has no value (is it the result of a function which doesn't return anything?)",
+                op
+            ),
+        ));
+    }
+    match (op, lhs, rhs) {
+        (_, Object::Int(a), Object::Int(b)) => int_arithmetic(op, a, b, settings.overflow),
+        // Decimals can only be combined with ints, as mixing them with floats
+        // would lose their exactness
+        (_, Object::Decimal(_), _) | (_, _, Object::Decimal(_)) => {
+            match (as_decimal(lhs), as_decimal(rhs)) {
+                (Some(a), Some(b)) => decimal_arithmetic(op, &a, &b, &settings.decimal),
+                _ => Err(unsupported_operands(op, lhs, rhs)),
+            }
+        }
+        (_, Object::Int(_) | Object::Float(_), Object::Int(_) | Object::Float(_)) => {
+            match (as_float(lhs), as_float(rhs)) {
+                (Some(a), Some(b)) => float_arithmetic(op, a, b),
+                _ => Err(unsupported_operands(op, lhs, rhs)),
+            }
+        }
+        (Operator::Add, Object::Str(a), Object::Str(b)) => Ok(Str {
+            value: format!("{}{}", a.value, b.value),
+        }
+        .into()),
+        (Operator::Mul, Object::Str(s), Object::Int(n))
+        | (Operator::Mul, Object::Int(n), Object::Str(s)) => Ok(Str {
+            value: s.value.repeat(repeat_count(n, s.value.len())?),
+        }
+        .into()),
+        // Both create a new list, whose elements are shared with the operands
+        (Operator::Add, Object::List(a), Object::List(b)) => {
+            let mut elements = a.to_vec();
+            elements.extend(b.borrow().iter().cloned());
+            Ok(List::new(elements).into())
+        }
+        (Operator::Mul, Object::List(list), Object::Int(n))
+        | (Operator::Mul, Object::Int(n), Object::List(list)) => {
+            let elements = list.to_vec();
+            let times = repeat_count(n, elements.len())?;
+            Ok(List::new(repeat(&elements, times)).into())
+        }
+        _ => Err(unsupported_operands(op, lhs, rhs)),
+    }
+}
+
+fn int_arithmetic(
+    op: &Operator,
+    a: &Int,
+    b: &Int,
+    overflow: Overflow,
+) -> Result<Object, RuntimeError> {
+    if let (Int::Small(a), Int::Small(b)) = (a, b) {
+        let value = match op {
+            Operator::Add => a.checked_add(*b),
+            Operator::Sub => a.checked_sub(*b),
+            Operator::Mul => a.checked_mul(*b),
+            Operator::Div => a.checked_div(*b),
+            _ => a.checked_rem(*b),
+        };
+        if let Some(value) = value {
+            return Ok(Int::new(value).into());
+        }
+    }
+    // The result doesn't fit in 64 bits (or the divisor is zero), so work it
+    // out exactly.
+    let (a, b) = (a.to_big(), b.to_big());
+    let value = match op {
+        Operator::Add => a.add(&b),
+        Operator::Sub => a.sub(&b),
+        Operator::Mul => a.mul(&b),
+        _ => {
+            let (quotient, remainder) = a.div_rem(&b).ok_or_else(|| {
+                RuntimeError::new(
+                    ErrorType::ZeroDivisionError,
+                    "Integer division or modulo by zero",
+                )
+            })?;
+            if matches!(op, Operator::Div) {
+                quotient
+            } else {
+                remainder
+            }
+        }
+    };
+    promote(op, value, overflow)
+}
+
+fn float_arithmetic(op: &Operator, a: f64, b: f64) -> Result<Object, RuntimeError> {
+    if b == 0.0 && matches!(op, Operator::Div | Operator::Mod) {
+        return Err(RuntimeError::new(
+            ErrorType::ZeroDivisionError,
+            "Float division or modulo by zero",
+        ));
+    }
+    let value = match op {
+        Operator::Add => a + b,
+        Operator::Sub => a - b,
+        Operator::Mul => a * b,
+        Operator::Div => a / b,
+        _ => a % b,
+    };
+    Ok(Float { value }.into())
+}
+
+fn decimal_arithmetic(
+    op: &Operator,
+    a: &Decimal,
+    b: &Decimal,
+    context: &DecimalContext,
+) -> Result<Object, RuntimeError> {
+    let value = match op {
+        Operator::Add => Some(a.add(b)),
+        Operator::Sub => Some(a.sub(b)),
+        Operator::Mul => Some(a.mul(b)),
+        Operator::Div => a.div(b, context),
+        _ => a.rem(b),
+    };
+    value.map(Object::from).ok_or_else(|| {
+        RuntimeError::new(
+            ErrorType::ZeroDivisionError,
+            "Decimal division or modulo by zero",
+        )
+    })
+}
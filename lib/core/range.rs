@@ -0,0 +1,87 @@
+use crate::errors::{ErrorType, RuntimeError};
+
+/// The ints from `start` up to (but not including) `stop`, counting in steps
+/// of `step`. The ints are produced as the range is iterated over rather
+/// than stored, so `range(1000000)` is as cheap to create as `range(1)`.
+#[derive(Clone)]
+pub struct Range {
+    pub start: i64,
+    pub stop: i64,
+    pub step: i64,
+}
+
+impl Range {
+    pub fn new(start: i64, stop: i64, step: i64) -> Result<Self, RuntimeError> {
+        if step == 0 {
+            return Err(RuntimeError::new(
+                ErrorType::ValueError,
+                "Range step cannot be zero",
+            ));
+        }
+        Ok(Self { start, stop, step })
+    }
+
+    /// The number of ints in the range.
+    pub fn len(&self) -> usize {
+        // Widened so that the distance between the bounds can't overflow
+        let (start, stop, step) = (self.start as i128, self.stop as i128, self.step as i128);
+        if (step > 0 && start < stop) || (step < 0 && start > stop) {
+            ((stop - start - step.signum()) / step + 1).min(usize::MAX as i128) as usize
+        } else {
+            0
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `i`th int in the range.
+    pub fn get(&self, i: usize) -> Option<i64> {
+        if i < self.len() {
+            Some((self.start as i128 + i as i128 * self.step as i128) as i64)
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> RangeIter {
+        RangeIter {
+            range: self.clone(),
+            next: 0,
+            len: self.len(),
+        }
+    }
+}
+
+/// Ranges are equal if they produce the same ints, so `range(0, 3, 2)` and
+/// `range(0, 4, 2)` are equal.
+impl PartialEq for Range {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.get(0) == other.get(0) && self.get(1) == other.get(1)
+    }
+}
+
+pub struct RangeIter {
+    range: Range,
+    next: usize,
+    len: usize,
+}
+
+impl Iterator for RangeIter {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.next >= self.len {
+            return None;
+        }
+        let value = self.range.get(self.next);
+        self.next += 1;
+        value
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.next;
+        (remaining, Some(remaining))
+    }
+}
@@ -0,0 +1,204 @@
+//! How objects are shown as text. Every object has two forms:
+//!
+//! - Its `Display` form is what the user is shown, e.g. by `print`. Strings
+//!   are shown as they are, without quotes.
+//! - Its `Debug` form, or repr, is how it would be written in a program.
+//!   Strings are quoted and escaped and decimals have their `d` suffix, so
+//!   `"1"`, `1` and `1d` can be told apart.
+//!
+//! Containers show their elements in repr form either way. A container which
+//! contains itself is shown as `[...]`, `{...}` or `Name(...)` where it
+//! appears inside itself.
+
+use std::fmt::{self, Debug, Display, Formatter};
+
+use crate::core::{Bool, Decimal, Dict, Float, Int, List, NoneType, Object, Range, Str, Struct};
+
+/// The addresses of the containers enclosing the object being written.
+type Seen = Vec<*const ()>;
+
+fn write_repr(object: &Object, f: &mut Formatter<'_>, seen: &mut Seen) -> fmt::Result {
+    match object {
+        Object::Bool(bool) => Debug::fmt(bool, f),
+        Object::Decimal(decimal) => Debug::fmt(decimal, f),
+        Object::Dict(dict) => write_dict(dict, f, seen),
+        Object::Float(float) => Debug::fmt(float, f),
+        Object::Function(function) => Debug::fmt(function, f),
+        Object::List(list) => write_list(list, f, seen),
+        Object::Range(range) => Debug::fmt(range, f),
+        Object::Str(str) => Debug::fmt(str, f),
+        Object::Struct(instance) => write_struct(instance, f, seen),
+        Object::StructType(ty) => Debug::fmt(ty, f),
+        Object::Int(int) => Debug::fmt(int, f),
+        Object::None => Debug::fmt(&NoneType, f),
+    }
+}
+
+/// Writes the elements of a container separated by commas, with `write`
+/// writing each one.
+fn write_separated<T>(
+    items: impl IntoIterator<Item = T>,
+    f: &mut Formatter<'_>,
+    mut write: impl FnMut(T, &mut Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write(item, f)?;
+    }
+    Ok(())
+}
+
+/// Writes a container between `open` and `close` with `write`, or with
+/// `...` in between if it's already being written further up.
+fn write_container(
+    address: *const (),
+    open: &str,
+    close: &str,
+    f: &mut Formatter<'_>,
+    seen: &mut Seen,
+    write: impl FnOnce(&mut Formatter<'_>, &mut Seen) -> fmt::Result,
+) -> fmt::Result {
+    if seen.contains(&address) {
+        return write!(f, "{}...{}", open, close);
+    }
+    seen.push(address);
+    write!(f, "{}", open)?;
+    write(f, seen)?;
+    seen.pop();
+    write!(f, "{}", close)
+}
+
+fn write_list(list: &List, f: &mut Formatter<'_>, seen: &mut Seen) -> fmt::Result {
+    write_container(list.id(), "[", "]", f, seen, |f, seen| {
+        write_separated(list.to_vec(), f, |element, f| write_repr(&element, f, seen))
+    })
+}
+
+fn write_dict(dict: &Dict, f: &mut Formatter<'_>, seen: &mut Seen) -> fmt::Result {
+    write_container(dict.id(), "{", "}", f, seen, |f, seen| {
+        write_separated(dict.entries(), f, |(key, value), f| {
+            write!(f, "{}: ", key)?;
+            write_repr(&value, f, seen)
+        })
+    })
+}
+
+fn write_struct(instance: &Struct, f: &mut Formatter<'_>, seen: &mut Seen) -> fmt::Result {
+    let open = format!("{}(", instance.ty.name);
+    write_container(instance.id(), &open, ")", f, seen, |f, seen| {
+        let fields = instance.ty.fields.iter().zip(instance.values());
+        write_separated(fields, f, |(field, value), f| {
+            write!(f, "{}=", field)?;
+            write_repr(&value, f, seen)
+        })
+    })
+}
+
+impl Object {
+    /// Returns the object's repr, the way it would be written in a program.
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl Display for Object {
+    /// Formats the object the way it is shown to the user.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Object::Str(str) => Display::fmt(str, f),
+            Object::Decimal(decimal) => Display::fmt(decimal, f),
+            _ => Debug::fmt(self, f),
+        }
+    }
+}
+
+impl Debug for Object {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_repr(self, f, &mut Vec::new())
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Debug for Str {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+impl Debug for Int {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Debug for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d", self)
+    }
+}
+
+impl Display for Float {
+    /// Floats always have a decimal point (or are `inf` or `NaN`), so they
+    /// can be told apart from ints.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+/// The remaining types are shown the same way in both forms.
+macro_rules! debug_as_display {
+    ($($ty:ty),*) => {
+        $(
+            impl Debug for $ty {
+                fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                    Display::fmt(self, f)
+                }
+            }
+        )*
+    };
+}
+
+debug_as_display!(Float, Bool, NoneType, Range, List, Dict, Struct);
+
+impl Display for Bool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Display for NoneType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "none")
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "range({}, {}, {})", self.start, self.stop, self.step)
+    }
+}
+
+impl Display for List {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_list(self, f, &mut Vec::new())
+    }
+}
+
+impl Display for Dict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_dict(self, f, &mut Vec::new())
+    }
+}
+
+impl Display for Struct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_struct(self, f, &mut Vec::new())
+    }
+}
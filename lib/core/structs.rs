@@ -0,0 +1,116 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::core::{heap, ops, Function, Object};
+
+/// A type declared with `struct`. Calling it creates an instance, taking the
+/// value of each field as an argument.
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+    /// The type's methods by name, which expect the instance they are
+    /// called on to be bound to `self`.
+    pub methods: HashMap<String, Rc<Function>>,
+}
+
+impl StructType {
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field == name)
+    }
+
+    /// The type's name and fields as shown in error messages, e.g.
+    /// `Point(x, y)`.
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.fields.join(", "))
+    }
+}
+
+impl heap::Node for StructType {
+    fn trace_node(&self, visit: &mut dyn FnMut(Rc<dyn heap::Node>)) -> bool {
+        for method in self.methods.values() {
+            visit(method.clone());
+        }
+        true
+    }
+
+    fn clear_node(&self) {}
+
+    fn node_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+// Methods capture the scope the type was declared in, which may contain the
+// type itself, so only the name is shown.
+impl std::fmt::Debug for StructType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<struct {}>", self.name)
+    }
+}
+
+/// An instance of a `StructType`. Like lists, structs are shared references,
+/// so assigning one to a variable or passing it to a function doesn't copy
+/// it, and a method which sets a field of `self` changes the instance it was
+/// called on (see `List`).
+#[derive(Clone)]
+pub struct Struct {
+    pub ty: Rc<StructType>,
+    /// The value of each of the type's fields, in the order they were
+    /// declared.
+    values: Rc<RefCell<Vec<Object>>>,
+}
+
+impl Struct {
+    pub fn new(ty: Rc<StructType>, values: Vec<Object>) -> Self {
+        Self {
+            ty,
+            values: heap::track(values),
+        }
+    }
+
+    pub fn get(&self, field: &str) -> Option<Object> {
+        self.values
+            .borrow()
+            .get(self.ty.field_index(field)?)
+            .cloned()
+    }
+
+    /// Sets the value of a field, returning `None` if the type has no such
+    /// field.
+    pub fn set(&self, field: &str, value: Object) -> Option<()> {
+        let index = self.ty.field_index(field)?;
+        self.values.borrow_mut()[index] = value;
+        Some(())
+    }
+
+    /// Returns a copy of the value of each field, in the order they were
+    /// declared.
+    pub fn values(&self) -> Vec<Object> {
+        self.values.borrow().clone()
+    }
+
+    /// The instance's address, which identifies it while it's alive.
+    pub fn id(&self) -> *const () {
+        Rc::as_ptr(&self.values) as *const ()
+    }
+
+    pub(crate) fn node(&self) -> Rc<dyn heap::Node> {
+        self.values.clone()
+    }
+}
+
+/// Structs are equal if they have the same type and equal fields.
+impl PartialEq for Struct {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.ty, &other.ty)
+            && (Rc::ptr_eq(&self.values, &other.values)
+                || ops::equals_once(self.id(), other.id(), || {
+                    *self.values.borrow() == *other.values.borrow()
+                }))
+    }
+}
+
+impl From<Struct> for Object {
+    fn from(value: Struct) -> Object {
+        Object::Struct(value)
+    }
+}
@@ -0,0 +1,214 @@
+//! Documentation generation from `///` doc comments.
+//!
+//! Modules are parsed, the doc comments attached to their top level
+//! `FunctionDecl` nodes are collected, and any `Import`ed modules are followed
+//! so that the generated reference can link between them. Modules are named by
+//! their path relative to the directory containing all of them, without the
+//! extension, e.g. `net/http`, so that modules in different directories which
+//! share a file name are kept apart.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::errors::Error;
+use crate::lexer::Span;
+use crate::parser::{
+    ast::{Node, Param},
+    parse,
+};
+
+pub mod render;
+pub use render::Format;
+
+/// The file extension of SAP source files.
+pub const EXTENSION: &str = "sap";
+
+pub struct FunctionDoc {
+    pub name: String,
+    /// The parameters as written in the source, e.g. `port: int = 80` or
+    /// `*rest`.
+    pub args: Vec<String>,
+    /// The return type annotation as written in the source, if any.
+    pub returns: Option<String>,
+    pub doc: Option<String>,
+}
+
+impl FunctionDoc {
+    /// Returns the function's signature as it would be written in source code.
+    pub fn signature(&self) -> String {
+        let signature = format!("fn {}({})", self.name, self.args.join(", "));
+        match &self.returns {
+            Some(returns) => format!("{} -> {}", signature, returns),
+            None => signature,
+        }
+    }
+}
+
+pub struct ModuleDoc {
+    /// The module's path relative to the other modules, using `/` as the
+    /// separator, e.g. `net/http`.
+    pub name: String,
+    pub imports: Vec<String>,
+    pub functions: Vec<FunctionDoc>,
+}
+
+pub enum DocError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: String,
+        error: Box<Error>,
+    },
+}
+
+/// Collects the documentation of a single module from its source code.
+pub fn collect_module(name: &str, src: &str) -> Result<ModuleDoc, Error> {
+    let statements = match parse(src)? {
+        Node::Program { statements, .. } => statements,
+        _ => Vec::new(),
+    };
+
+    let mut module = ModuleDoc {
+        name: name.to_string(),
+        imports: Vec::new(),
+        functions: Vec::new(),
+    };
+    for statement in statements {
+        match statement {
+            Node::Import { path, .. } => module.imports.push(path),
+            Node::FunctionDecl {
+                name,
+                params,
+                return_type,
+                doc,
+                ..
+            } => module.functions.push(FunctionDoc {
+                name,
+                args: params
+                    .iter()
+                    .map(|param| param_source(src, param))
+                    .collect(),
+                returns: return_type.map(|ty| source_text(src, ty.span())),
+                doc,
+            }),
+            _ => {}
+        }
+    }
+    Ok(module)
+}
+
+fn param_source(src: &str, param: &Param) -> String {
+    let mut source = if param.variadic {
+        format!("*{}", param.name)
+    } else {
+        param.name.clone()
+    };
+    if let Some(ty) = &param.ty {
+        source = format!("{}: {}", source, source_text(src, ty.span()));
+    }
+    match &param.default {
+        Some(default) => format!("{} = {}", source, source_text(src, default.span())),
+        None => source,
+    }
+}
+
+/// Returns the source code covered by `span`, which counts characters.
+fn source_text(src: &str, span: &Span) -> String {
+    src.chars()
+        .skip(span.start)
+        .take(span.end.saturating_sub(span.start))
+        .collect()
+}
+
+/// Returns the path an `import` statement in the module at `from` refers to.
+pub fn resolve_import(from: &Path, import: &str) -> PathBuf {
+    from.parent()
+        .unwrap_or(Path::new(""))
+        .join(import)
+        .with_extension(EXTENSION)
+}
+
+/// Returns the name of the module an `import` statement in the module named
+/// `from` refers to, which is in the same directory.
+pub fn import_name(from: &str, import: &str) -> String {
+    match from.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, import),
+        None => import.to_string(),
+    }
+}
+
+/// Returns the name of the module at `path`, relative to the directory `root`.
+fn module_name(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path).with_extension("");
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Loads and documents the given modules along with every module they
+/// (transitively) import. Imports which cannot be found are left unresolved
+/// rather than treated as errors, so that documentation can still be produced
+/// for a partial library.
+pub fn load_modules(entries: &[PathBuf]) -> Result<Vec<ModuleDoc>, DocError> {
+    let mut modules = Vec::new();
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue: VecDeque<(PathBuf, bool)> = entries.iter().map(|p| (p.clone(), true)).collect();
+
+    while let Some((path, is_entry)) = queue.pop_front() {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        if !is_entry && !path.exists() {
+            continue;
+        }
+        // The same module may be reached through different paths, such as
+        // `./b.sap` and `b.sap`.
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+        if paths.contains(&path) {
+            continue;
+        }
+        let source = std::fs::read_to_string(&path).map_err(|error| DocError::Io {
+            path: path.clone(),
+            error,
+        })?;
+        // Named once every module is known, as the names depend on the
+        // directory containing all of them.
+        let module = match collect_module("", &source) {
+            Ok(module) => module,
+            Err(error) => {
+                return Err(DocError::Parse {
+                    path,
+                    source,
+                    error: Box::new(error),
+                })
+            }
+        };
+        for import in &module.imports {
+            queue.push_back((resolve_import(&path, import), false));
+        }
+        modules.push(module);
+        paths.push(path);
+    }
+
+    let mut root = paths
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    while !paths.iter().all(|path| path.starts_with(&root)) {
+        if !root.pop() {
+            break;
+        }
+    }
+    for (module, path) in modules.iter_mut().zip(&paths) {
+        module.name = module_name(&root, path);
+    }
+
+    Ok(modules)
+}
@@ -0,0 +1,212 @@
+use std::fmt::Write;
+
+use super::{import_name, FunctionDoc, ModuleDoc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "md" | "markdown" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    /// The file extension used for pages in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+/// Returns the path of the page documenting the given module, relative to
+/// the output directory. Modules in subdirectories get pages in matching
+/// subdirectories.
+pub fn page_name(module: &str, format: Format) -> String {
+    format!("{}.{}", module, format.extension())
+}
+
+/// Returns a link from the page documenting the module `module` to the index
+/// page, which is in the output directory.
+fn index_link(module: &str, format: Format) -> String {
+    format!(
+        "{}index.{}",
+        "../".repeat(module.matches('/').count()),
+        format.extension()
+    )
+}
+
+fn anchor(function: &FunctionDoc) -> String {
+    format!("fn-{}", function.name)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders the reference page for `module`. Imports are linked to the pages
+/// of the modules they refer to when those modules are part of `modules`.
+pub fn render_module(module: &ModuleDoc, modules: &[ModuleDoc], format: Format) -> String {
+    let import_link = |import: &str| {
+        // Imported modules are in the same directory, so their pages are too.
+        let name = import_name(&module.name, import);
+        modules
+            .iter()
+            .any(|m| m.name == name)
+            .then(|| page_name(import, format))
+    };
+    match format {
+        Format::Markdown => render_module_markdown(module, import_link),
+        Format::Html => render_module_html(module, import_link),
+    }
+}
+
+/// Renders a page linking to every documented module.
+pub fn render_index(modules: &[ModuleDoc], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Markdown => {
+            out.push_str("# Modules\n\n");
+            for module in modules {
+                let _ = writeln!(
+                    out,
+                    "- [{}]({})",
+                    module.name,
+                    page_name(&module.name, format)
+                );
+            }
+        }
+        Format::Html => {
+            out.push_str(&html_header("Modules"));
+            out.push_str("<h1>Modules</h1>\n<ul>\n");
+            for module in modules {
+                let _ = writeln!(
+                    out,
+                    "<li><a href=\"{}\">{}</a></li>",
+                    escape_html(&page_name(&module.name, format)),
+                    escape_html(&module.name)
+                );
+            }
+            out.push_str("</ul>\n");
+            out.push_str(HTML_FOOTER);
+        }
+    }
+    out
+}
+
+fn render_module_markdown(
+    module: &ModuleDoc,
+    import_link: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "[Modules]({})\n",
+        index_link(&module.name, Format::Markdown)
+    );
+    let _ = writeln!(out, "# Module `{}`\n", module.name);
+
+    if !module.imports.is_empty() {
+        out.push_str("## Imports\n\n");
+        for import in &module.imports {
+            if let Some(href) = import_link(import) {
+                let _ = writeln!(out, "- [`{}`]({})", import, href);
+            } else {
+                let _ = writeln!(out, "- `{}`", import);
+            }
+        }
+        out.push('\n');
+    }
+
+    if !module.functions.is_empty() {
+        out.push_str("## Functions\n\n");
+        for function in &module.functions {
+            let _ = writeln!(out, "- [`{}`](#{})", function.name, anchor(function));
+        }
+        out.push('\n');
+
+        for function in &module.functions {
+            let _ = writeln!(out, "<a id=\"{}\"></a>", anchor(function));
+            let _ = writeln!(out, "### `{}`\n", function.signature());
+            if let Some(doc) = &function.doc {
+                let _ = writeln!(out, "{}\n", doc);
+            }
+        }
+    }
+
+    out
+}
+
+fn render_module_html(module: &ModuleDoc, import_link: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = html_header(&module.name);
+    let _ = writeln!(
+        out,
+        "<p><a href=\"{}\">Modules</a></p>\n<h1>Module <code>{}</code></h1>",
+        escape_html(&index_link(&module.name, Format::Html)),
+        escape_html(&module.name)
+    );
+
+    if !module.imports.is_empty() {
+        out.push_str("<h2>Imports</h2>\n<ul>\n");
+        for import in &module.imports {
+            if let Some(href) = import_link(import) {
+                let _ = writeln!(
+                    out,
+                    "<li><a href=\"{}\"><code>{}</code></a></li>",
+                    escape_html(&href),
+                    escape_html(import)
+                );
+            } else {
+                let _ = writeln!(out, "<li><code>{}</code></li>", escape_html(import));
+            }
+        }
+        out.push_str("</ul>\n");
+    }
+
+    if !module.functions.is_empty() {
+        out.push_str("<h2>Functions</h2>\n");
+        for function in &module.functions {
+            let _ = writeln!(
+                out,
+                "<h3 id=\"{}\"><code>{}</code></h3>",
+                anchor(function),
+                escape_html(&function.signature())
+            );
+            if let Some(doc) = &function.doc {
+                for paragraph in doc.split("\n\n") {
+                    let _ = writeln!(out, "<p>{}</p>", escape_html(paragraph));
+                }
+            }
+        }
+    }
+
+    out.push_str(HTML_FOOTER);
+    out
+}
+
+fn html_header(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n",
+        escape_html(title)
+    )
+}
+
+const HTML_FOOTER: &str = "</body>\n</html>\n";
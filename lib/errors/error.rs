@@ -1,4 +1,5 @@
-use crate::lexer::token::Token;
+use crate::lexer::token::{Token, TokenKind};
+use crate::lexer::Span;
 
 use crate::colours::*;
 
@@ -6,15 +7,43 @@ use crate::colours::*;
 pub enum ErrorType {
     NameError,
     SyntaxError,
+    TypeError,
+    ZeroDivisionError,
+    KeyError,
+    IndexError,
+    ValueError,
+    AttributeError,
+    OverflowError,
+    RecursionError,
+    /// An `assert` whose condition was false.
+    AssertionError,
+    /// `input` was called after the end of the input was reached.
+    EOFError,
+    /// Reading input or writing output failed.
+    IOError,
+    /// A lint whose rule is configured as an error.
+    LintError,
     Runtime(String), // User defined errors?
 }
 
-impl ToString for ErrorType {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ErrorType::NameError => "NameError".to_string(),
-            ErrorType::SyntaxError => "SyntaxError".to_string(),
-            ErrorType::Runtime(msg) => format!("RuntimeError: {}", msg),
+            ErrorType::NameError => write!(f, "NameError"),
+            ErrorType::SyntaxError => write!(f, "SyntaxError"),
+            ErrorType::TypeError => write!(f, "TypeError"),
+            ErrorType::ZeroDivisionError => write!(f, "ZeroDivisionError"),
+            ErrorType::KeyError => write!(f, "KeyError"),
+            ErrorType::IndexError => write!(f, "IndexError"),
+            ErrorType::ValueError => write!(f, "ValueError"),
+            ErrorType::AttributeError => write!(f, "AttributeError"),
+            ErrorType::OverflowError => write!(f, "OverflowError"),
+            ErrorType::RecursionError => write!(f, "RecursionError"),
+            ErrorType::AssertionError => write!(f, "AssertionError"),
+            ErrorType::EOFError => write!(f, "EOFError"),
+            ErrorType::IOError => write!(f, "IOError"),
+            ErrorType::LintError => write!(f, "LintError"),
+            ErrorType::Runtime(msg) => write!(f, "RuntimeError: {}", msg),
         }
     }
 }
@@ -26,17 +55,88 @@ pub struct Error {
     pub token: Token,
 }
 
+impl Error {
+    /// Constructs an error which refers to a region of the source code, such
+    /// as an AST node, rather than a single token.
+    pub fn new(error_type: ErrorType, message: &str, span: Span) -> Self {
+        Self {
+            message: message.to_string(),
+            error_type,
+            token: Token {
+                kind: TokenKind::Unknown,
+                span,
+            },
+        }
+    }
+}
+
+/// An error raised while operating on values, before it has been given a
+/// location in the source code.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub error_type: ErrorType,
+    pub message: String,
+}
+
+impl RuntimeError {
+    pub fn new(error_type: ErrorType, message: &str) -> Self {
+        Self {
+            error_type,
+            message: message.to_string(),
+        }
+    }
+
+    /// Attaches the location the error was raised at.
+    pub fn at(self, span: &Span) -> Error {
+        Error::new(self.error_type, &self.message, span.clone())
+    }
+}
+
+/// A likely mistake in a program which doesn't stop it from running, such as
+/// a `match` statement which may not handle every value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Warning {
+    pub fn new(message: &str, span: Span) -> Self {
+        Self {
+            message: message.to_string(),
+            span,
+        }
+    }
+}
+
 // TODO: Add a file buffer system
 pub fn handle_error(error: Error, src_buffer: &str, file_name: &str) {
-    let (line_num, col_num) = find_line_column(src_buffer, error.token.span.start);
+    print_source_location(&error.token.span, src_buffer, file_name);
+    println!(
+        "{colour_red}{style_bold} {} {colour_reset}:: {}{colour_reset}{style_reset}",
+        error.error_type, error.message
+    );
+}
+
+pub fn handle_warning(warning: &Warning, src_buffer: &str, file_name: &str) {
+    print_source_location(&warning.span, src_buffer, file_name);
+    println!(
+        "{colour_yellow}{style_bold} Warning {colour_reset}:: {}{colour_reset}{style_reset}",
+        warning.message
+    );
+}
+
+/// Prints the lines leading up to `span`, with the span highlighted.
+fn print_source_location(span: &Span, src_buffer: &str, file_name: &str) {
+    let (line_num, col_num) = find_line_column(src_buffer, span.start);
     let lines = get_context_lines(src_buffer, line_num);
     let mut lines_enum = lines.iter().enumerate().peekable();
-    let span_len = error.token.span.end - error.token.span.start;
+    let span_len = span.end.saturating_sub(span.start);
 
     let left_pad = line_num.to_string().len() + 1;
     let blank_pad = " ".repeat(left_pad);
 
-    println!("");
+    println!();
     println!(
         "{colour_cyan}{style_bold}{}--> {colour_reset}{file_name}:{line_num}:{col_num}",
         blank_pad
@@ -45,9 +145,14 @@ pub fn handle_error(error: Error, src_buffer: &str, file_name: &str) {
     while let Some((i, line)) = lines_enum.next() {
         let line_num = line_num.saturating_sub(4) + i + 1;
         if lines_enum.peek().is_none() {
-            let before_highlight = &line[0..col_num];
-            let highlight = &line[col_num..col_num + span_len + 1];
-            let after_highlight = &line[col_num + span_len + 1..];
+            // Spans are measured in characters, and may run past the end of
+            // the line (e.g. an unexpected end of file)
+            let chars: Vec<char> = line.chars().collect();
+            let highlight_start = col_num.min(chars.len());
+            let highlight_end = (col_num + span_len).min(chars.len());
+            let before_highlight: String = chars[..highlight_start].iter().collect();
+            let highlight: String = chars[highlight_start..highlight_end].iter().collect();
+            let after_highlight: String = chars[highlight_end..].iter().collect();
             println!(
                 "{colour_cyan}{style_bold}{line_num:>left_pad$} | {colour_reset}{style_reset}{before_highlight}{colour_green}{style_bold}{highlight}{colour_reset}{after_highlight}",
                 line_num = line_num,
@@ -71,12 +176,7 @@ pub fn handle_error(error: Error, src_buffer: &str, file_name: &str) {
             " ".repeat(col_num)
         );
     }
-    println!("");
-    println!(
-        "{colour_red}{style_bold} {} {colour_reset}:: {}{colour_reset}{style_reset}",
-        error.error_type.to_string(),
-        error.message
-    );
+    println!();
 }
 
 /// Returns the line and column number of the given index.
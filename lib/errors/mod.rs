@@ -1,3 +1,5 @@
 pub mod error;
 pub use error::Error;
 pub use error::ErrorType;
+pub use error::RuntimeError;
+pub use error::Warning;
@@ -0,0 +1,616 @@
+//! Functions provided by the interpreter rather than defined in SAP code.
+//! A function defined by the program with the same name as a builtin takes
+//! precedence over it.
+
+use std::{
+    cmp::Ordering,
+    io::{BufRead, BufReader, Write},
+};
+
+use crate::{
+    core::{ops, BigInt, Decimal, Float, Int, List, Object, Range, Str},
+    errors::{Error, ErrorType, RuntimeError},
+    lexer::Span,
+    parser::ast::Operator,
+};
+
+/// A function provided by the interpreter.
+pub struct Builtin {
+    pub name: &'static str,
+    /// The names of the keyword arguments it takes.
+    pub keywords: &'static [&'static str],
+    function: fn(&mut Call) -> Result<Object, Error>,
+}
+
+impl Builtin {
+    const fn new(
+        name: &'static str,
+        keywords: &'static [&'static str],
+        function: fn(&mut Call) -> Result<Object, Error>,
+    ) -> Self {
+        Self {
+            name,
+            keywords,
+            function,
+        }
+    }
+
+    pub(crate) fn call(&self, call: &mut Call) -> Result<Object, Error> {
+        (self.function)(call)
+    }
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin::new("print", &["sep", "end"], print),
+    Builtin::new("input", &[], input),
+    Builtin::new("len", &[], len),
+    Builtin::new("type", &[], type_of),
+    Builtin::new("abs", &[], abs),
+    Builtin::new("min", &[], min),
+    Builtin::new("max", &[], max),
+    Builtin::new("sum", &[], sum),
+    Builtin::new("round", &[], round),
+    Builtin::new("assert", &[], assert),
+    Builtin::new("range", &[], range),
+    Builtin::new("int", &[], int),
+    Builtin::new("float", &[], float),
+    Builtin::new("str", &[], str),
+    Builtin::new("bool", &[], bool),
+    Builtin::new("list", &[], list),
+];
+
+/// Returns the builtin function with the given name.
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Where `print` writes to and `input` reads from. See
+/// `Interpreter::set_output` and `Interpreter::set_input`.
+pub struct Io {
+    pub output: Box<dyn Write>,
+    pub input: Box<dyn BufRead>,
+}
+
+/// The process's standard output and input.
+impl Default for Io {
+    fn default() -> Self {
+        Self {
+            output: Box::new(std::io::stdout()),
+            input: Box::new(BufReader::new(std::io::stdin())),
+        }
+    }
+}
+
+/// A call to a builtin: the arguments it was given, along with where they
+/// were written, and the parts of the interpreter it may use.
+pub struct Call<'a> {
+    pub(crate) name: &'static str,
+    pub(crate) args: Vec<(Object, &'a Span)>,
+    pub(crate) kwargs: Vec<(&'a str, Object, &'a Span)>,
+    pub(crate) span: &'a Span,
+    pub(crate) io: &'a mut Io,
+    pub(crate) arithmetic: &'a ops::Arithmetic,
+}
+
+impl Call<'_> {
+    /// Checks that between `min` and `max` positional arguments were given.
+    fn expect_args(&self, min: usize, max: usize) -> Result<(), Error> {
+        if (min..=max).contains(&self.args.len()) {
+            return Ok(());
+        }
+        let expected = if min == max {
+            min.to_string()
+        } else if max == usize::MAX {
+            format!("at least {}", min)
+        } else {
+            format!("{} to {}", min, max)
+        };
+        Err(self.error(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!(
+                "{}() takes {} arguments but {} were given",
+                self.name,
+                expected,
+                self.args.len()
+            ),
+        )))
+    }
+
+    fn arg(&self, i: usize) -> &Object {
+        &self.args[i].0
+    }
+
+    /// Attaches the location of the whole call to an error.
+    fn error(&self, error: RuntimeError) -> Error {
+        error.at(self.span)
+    }
+
+    /// Attaches the location of the `i`th argument to an error.
+    fn arg_error(&self, i: usize, error: RuntimeError) -> Error {
+        error.at(self.args[i].1)
+    }
+
+    /// An error for the `i`th argument having the wrong type, where
+    /// `expected` describes the types it may have.
+    fn expected(&self, i: usize, expected: &str) -> Error {
+        self.arg_error(
+            i,
+            RuntimeError::new(
+                ErrorType::TypeError,
+                &format!(
+                    "{}() argument {} must be {}, not '{}'",
+                    self.name,
+                    i + 1,
+                    expected,
+                    self.arg(i).type_name()
+                ),
+            ),
+        )
+    }
+
+    /// Returns the string given as the keyword argument `name`, or `default`
+    /// if it wasn't given or was `none`.
+    fn str_keyword(&self, name: &str, default: &str) -> Result<String, Error> {
+        match self.kwargs.iter().find(|(keyword, ..)| *keyword == name) {
+            Some((_, Object::Str(str), _)) => Ok(str.value.clone()),
+            Some((_, Object::None, _)) | None => Ok(default.to_string()),
+            Some((_, other, span)) => Err(RuntimeError::new(
+                ErrorType::TypeError,
+                &format!(
+                    "{}() argument '{}' must be a str, not '{}'",
+                    self.name,
+                    name,
+                    other.type_name()
+                ),
+            )
+            .at(span)),
+        }
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), Error> {
+        let output = &mut self.io.output;
+        output
+            .write_all(text.as_bytes())
+            .and_then(|_| output.flush())
+            .map_err(|error| {
+                RuntimeError::new(
+                    ErrorType::IOError,
+                    &format!("Cannot write output: {}", error),
+                )
+                .at(self.span)
+            })
+    }
+}
+
+/// `print(*values, sep=" ", end="\n")` writes the values to the output as
+/// they are shown to the user, separated by `sep` and followed by `end`.
+fn print(call: &mut Call) -> Result<Object, Error> {
+    let sep = call.str_keyword("sep", " ")?;
+    let end = call.str_keyword("end", "\n")?;
+    let values: Vec<String> = call
+        .args
+        .iter()
+        .map(|(value, _)| value.to_string())
+        .collect();
+    call.write(&(values.join(&sep) + &end))?;
+    Ok(Object::None)
+}
+
+/// `input()` or `input(prompt)` writes the prompt to the output, then reads
+/// a line from the input and returns it without its line ending.
+fn input(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(0, 1)?;
+    if let Some((prompt, _)) = call.args.first() {
+        let prompt = prompt.to_string();
+        call.write(&prompt)?;
+    }
+    let mut line = String::new();
+    let read = call.io.input.read_line(&mut line).map_err(|error| {
+        RuntimeError::new(ErrorType::IOError, &format!("Cannot read input: {}", error))
+            .at(call.span)
+    })?;
+    if read == 0 {
+        return Err(call.error(RuntimeError::new(
+            ErrorType::EOFError,
+            "input() reached the end of the input",
+        )));
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Str { value: line }.into())
+}
+
+/// `len(value)` returns the number of elements in a list, dict or range, or
+/// the number of characters in a string.
+fn len(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    let len = match call.arg(0) {
+        Object::List(list) => list.len(),
+        Object::Dict(dict) => dict.len(),
+        Object::Range(range) => range.len(),
+        Object::Str(str) => str.value.chars().count(),
+        _ => return Err(call.expected(0, "a str, list, dict or range")),
+    };
+    // Ranges between far apart bounds have more ints than fit in 64 bits
+    let len = match i64::try_from(len) {
+        Ok(len) => Int::new(len),
+        Err(_) => Int::from_big(BigInt::from_u64(len as u64)),
+    };
+    Ok(len.into())
+}
+
+/// `type(value)` returns the name of the value's type, as used in error
+/// messages, e.g. `"int"` or `"list"`.
+fn type_of(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    Ok(Str {
+        value: call.arg(0).type_name().to_string(),
+    }
+    .into())
+}
+
+/// `abs(number)` returns the number without its sign.
+fn abs(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    let number = call.arg(0);
+    match number {
+        Object::Int(int) if int.is_negative() => {
+            ops::unary_op(&Operator::Sub, number, call.arithmetic).map_err(|e| call.error(e))
+        }
+        Object::Decimal(decimal) if decimal.coefficient.is_negative() => Ok(decimal.neg().into()),
+        Object::Int(_) | Object::Decimal(_) => Ok(number.clone()),
+        Object::Float(float) => Ok(Float {
+            value: float.value.abs(),
+        }
+        .into()),
+        _ => Err(call.expected(0, "a number")),
+    }
+}
+
+/// `min(values)` or `min(a, b, ...)` returns the smallest of the values
+/// produced by iterating over a value, or of its arguments. The first of
+/// several equally small values is returned.
+fn min(call: &mut Call) -> Result<Object, Error> {
+    extreme(call, Operator::Less)
+}
+
+/// `max(values)` or `max(a, b, ...)` returns the largest of the values
+/// produced by iterating over a value, or of its arguments. The first of
+/// several equally large values is returned.
+fn max(call: &mut Call) -> Result<Object, Error> {
+    extreme(call, Operator::More)
+}
+
+/// Returns the value which `op` orders before all of the others.
+fn extreme(call: &mut Call, op: Operator) -> Result<Object, Error> {
+    call.expect_args(1, usize::MAX)?;
+    let mut values: Box<dyn Iterator<Item = Object>> = match &call.args[..] {
+        [(values, span)] => ops::iterate(values).map_err(|error| error.at(span))?,
+        args => Box::new(
+            args.iter()
+                .map(|(value, _)| value.clone())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        ),
+    };
+    let Some(mut extreme) = values.next() else {
+        return Err(call.arg_error(
+            0,
+            RuntimeError::new(
+                ErrorType::ValueError,
+                &format!("{}() was given no values", call.name),
+            ),
+        ));
+    };
+    for value in values {
+        let before = ops::binary_op(&op, &value, &extreme, call.arithmetic)
+            .map_err(|error| call.error(error))?;
+        if before.is_truthy() {
+            extreme = value;
+        }
+    }
+    Ok(extreme)
+}
+
+/// `sum(values)` or `sum(values, start)` adds the values produced by
+/// iterating over a value to `start`, which is `0` by default, in order.
+fn sum(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 2)?;
+    let values = ops::iterate(call.arg(0)).map_err(|error| call.arg_error(0, error))?;
+    let mut total = match call.args.get(1) {
+        Some((start, _)) => start.clone(),
+        None => Int::new(0).into(),
+    };
+    for value in values {
+        total = ops::binary_op(&Operator::Add, &total, &value, call.arithmetic)
+            .map_err(|error| call.error(error))?;
+    }
+    Ok(total)
+}
+
+/// `round(number)` rounds a number to the nearest int, and
+/// `round(number, digits)` rounds it to that many digits after the decimal
+/// point (or before it, if negative) keeping its type. Ties are rounded to
+/// the even neighbour, and floats are rounded as they are shown, so
+/// `round(2.675, 2)` is `2.68`.
+fn round(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 2)?;
+    let digits = match call.args.get(1) {
+        None => None,
+        Some((Object::Int(int), _)) => Some(int.to_i64().unwrap_or(if int.is_negative() {
+            i64::MIN
+        } else {
+            i64::MAX
+        })),
+        Some(_) => return Err(call.expected(1, "an int")),
+    };
+    let number = call.arg(0);
+    let decimal = match number {
+        Object::Int(int) => Decimal::from_int(int),
+        Object::Decimal(decimal) => decimal.clone(),
+        Object::Float(float) => match Decimal::from_f64(float.value) {
+            Some(decimal) => decimal,
+            // Infinities and NaN have no digits to round, nor an int value
+            None if digits.is_some() => return Ok(number.clone()),
+            None => {
+                return Err(call.arg_error(
+                    0,
+                    RuntimeError::new(
+                        ErrorType::ValueError,
+                        &format!("Cannot round {} to an int", number.repr()),
+                    ),
+                ))
+            }
+        },
+        _ => return Err(call.expected(0, "a number")),
+    };
+    let rounded = decimal.round(digits.unwrap_or(0));
+    Ok(match (number, digits) {
+        (Object::Float(_), Some(_)) => Float {
+            value: rounded.to_f64(),
+        }
+        .into(),
+        (Object::Decimal(_), Some(_)) => rounded.into(),
+        _ => rounded.to_int().into(),
+    })
+}
+
+/// `assert(condition)` or `assert(condition, message)` raises an
+/// `AssertionError` pointing at the condition if it's false.
+fn assert(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 2)?;
+    if call.arg(0).is_truthy() {
+        return Ok(Object::None);
+    }
+    let message = match call.args.get(1) {
+        Some((message, _)) => message.to_string(),
+        None => "Assertion failed".to_string(),
+    };
+    Err(call.arg_error(0, RuntimeError::new(ErrorType::AssertionError, &message)))
+}
+
+/// `int(value)` converts a value to an int (see `Object::to_int`).
+fn int(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    let int = call.arg(0).to_int();
+    Ok(int.map_err(|error| call.arg_error(0, error.into()))?.into())
+}
+
+/// `float(value)` converts a value to a float (see `Object::to_float`).
+fn float(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    let float = call.arg(0).to_float();
+    Ok(float
+        .map_err(|error| call.arg_error(0, error.into()))?
+        .into())
+}
+
+/// `str(value)` converts a value to the string it is shown as.
+fn str(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    Ok(call.arg(0).to_str().into())
+}
+
+/// `bool(value)` converts a value to whether it counts as true.
+fn bool(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    Ok(call.arg(0).to_bool().into())
+}
+
+/// `list(value)` creates a list of the values produced by iterating over a
+/// value (see `Object::to_list`).
+fn list(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 1)?;
+    let list = call.arg(0).to_list();
+    Ok(list
+        .map_err(|error| call.arg_error(0, error.into()))?
+        .into())
+}
+
+/// `range(stop)`, `range(start, stop)` or `range(start, stop, step)`
+fn range(call: &mut Call) -> Result<Object, Error> {
+    call.expect_args(1, 3)?;
+    let mut bounds = Vec::with_capacity(call.args.len());
+    for (i, (arg, _)) in call.args.iter().enumerate() {
+        match arg {
+            Object::Int(int) => bounds.push(int.to_i64().ok_or_else(|| {
+                call.arg_error(
+                    i,
+                    RuntimeError::new(
+                        ErrorType::OverflowError,
+                        &format!("range() argument {} is too large", int),
+                    ),
+                )
+            })?),
+            _ => {
+                return Err(call.arg_error(
+                    i,
+                    RuntimeError::new(
+                        ErrorType::TypeError,
+                        &format!(
+                            "range() arguments must be integers, not '{}'",
+                            arg.type_name()
+                        ),
+                    ),
+                ))
+            }
+        }
+    }
+    let (start, stop) = match bounds[..] {
+        [stop] => (0, stop),
+        _ => (bounds[0], bounds[1]),
+    };
+    let step = bounds.get(2).copied().unwrap_or(1);
+    let range = Range::new(start, stop, step).map_err(|error| call.error(error))?;
+    Ok(range.into())
+}
+
+/// A method provided by the interpreter, which is called with the object it
+/// was looked up on and the arguments it was given.
+pub type Method = fn(&List, &[Object]) -> Result<Object, RuntimeError>;
+
+/// Returns the builtin method `name` of `receiver`.
+pub fn lookup_method(receiver: &Object, name: &str) -> Option<(List, Method)> {
+    let Object::List(list) = receiver else {
+        return None;
+    };
+    let method: Method = match name {
+        "push" => push,
+        "pop" => pop,
+        "insert" => insert,
+        "remove" => remove,
+        "sort" => sort,
+        "reverse" => reverse,
+        "copy" => copy,
+        _ => return None,
+    };
+    Some((list.clone(), method))
+}
+
+/// Checks that a method was given between `min` and `max` arguments.
+fn expect_args(name: &str, args: &[Object], min: usize, max: usize) -> Result<(), RuntimeError> {
+    if (min..=max).contains(&args.len()) {
+        return Ok(());
+    }
+    let expected = if min == max {
+        min.to_string()
+    } else {
+        format!("{} to {}", min, max)
+    };
+    Err(RuntimeError::new(
+        ErrorType::TypeError,
+        &format!(
+            "list.{}() takes {} arguments but {} were given",
+            name,
+            expected,
+            args.len()
+        ),
+    ))
+}
+
+/// `list.push(value)` adds a value to the end of the list.
+fn push(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("push", args, 1, 1)?;
+    list.push(args[0].clone());
+    Ok(Object::None)
+}
+
+/// `list.pop()` or `list.pop(index)` removes and returns the value at the
+/// index, or the last value.
+fn pop(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("pop", args, 0, 1)?;
+    let mut elements = list.borrow_mut();
+    if elements.is_empty() {
+        return Err(RuntimeError::new(
+            ErrorType::IndexError,
+            "Cannot pop from an empty list",
+        ));
+    }
+    let i = match args.first() {
+        Some(index) => ops::resolve_index(index, elements.len(), "list")?,
+        None => elements.len() - 1,
+    };
+    Ok(elements.remove(i))
+}
+
+/// `list.insert(index, value)` inserts a value before the index. Indices
+/// past either end of the list insert at that end.
+fn insert(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("insert", args, 2, 2)?;
+    let mut elements = list.borrow_mut();
+    let len = elements.len() as i64;
+    let position = match &args[0] {
+        Object::Int(Int::Small(position)) => *position,
+        Object::Int(int) if int.is_negative() => i64::MIN,
+        Object::Int(_) => i64::MAX,
+        other => {
+            return Err(RuntimeError::new(
+                ErrorType::TypeError,
+                &format!("list indices must be integers, not '{}'", other.type_name()),
+            ))
+        }
+    };
+    let i = if position < 0 {
+        position.saturating_add(len).max(0)
+    } else {
+        position.min(len)
+    };
+    elements.insert(i as usize, args[1].clone());
+    Ok(Object::None)
+}
+
+/// `list.remove(value)` removes the first value equal to the given one.
+fn remove(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("remove", args, 1, 1)?;
+    let position = list.borrow().iter().position(|element| *element == args[0]);
+    match position {
+        Some(i) => {
+            list.borrow_mut().remove(i);
+            Ok(Object::None)
+        }
+        None => Err(RuntimeError::new(
+            ErrorType::ValueError,
+            &format!("{} is not in the list", args[0].repr()),
+        )),
+    }
+}
+
+/// `list.sort()` sorts the list in place, in ascending order. Equal values
+/// keep their order.
+fn sort(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("sort", args, 0, 0)?;
+    let mut elements = list.to_vec();
+    let mut incomparable = None;
+    elements.sort_by(|a, b| match ops::compare(a, b) {
+        Some(ordering) => ordering.unwrap_or(Ordering::Equal),
+        None => {
+            incomparable.get_or_insert((a.type_name(), b.type_name()));
+            Ordering::Equal
+        }
+    });
+    if let Some((a, b)) = incomparable {
+        return Err(RuntimeError::new(
+            ErrorType::TypeError,
+            &format!("Cannot sort a list containing '{}' and '{}'", a, b),
+        ));
+    }
+    *list.borrow_mut() = elements;
+    Ok(Object::None)
+}
+
+/// `list.reverse()` reverses the list in place.
+fn reverse(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("reverse", args, 0, 0)?;
+    list.borrow_mut().reverse();
+    Ok(Object::None)
+}
+
+/// `list.copy()` returns a new list with the same elements.
+fn copy(list: &List, args: &[Object]) -> Result<Object, RuntimeError> {
+    expect_args("copy", args, 0, 0)?;
+    Ok(list.copy().into())
+}
@@ -0,0 +1,82 @@
+use std::{cell::RefCell, collections::HashMap, mem::size_of, rc::Rc};
+
+use crate::core::{heap, Object};
+
+pub type EnvRef = Rc<RefCell<Environment>>;
+
+/// A scope mapping names to values, which falls back to its parent scope for
+/// names it doesn't define itself.
+#[derive(Default)]
+pub struct Environment {
+    members: HashMap<String, Object>,
+    parent: Option<EnvRef>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_enclosed(parent: EnvRef) -> Self {
+        Self {
+            parent: Some(parent),
+            ..Self::default()
+        }
+    }
+
+    pub fn into_ref(self) -> EnvRef {
+        heap::track(self)
+    }
+
+    /// Binds `name` to `value` in this scope, shadowing any binding of the
+    /// same name in a parent scope.
+    pub fn define(&mut self, name: &str, value: Object) {
+        self.members.insert(name.to_string(), value);
+    }
+
+    /// Updates the binding of `name` in the nearest scope which defines it,
+    /// returning false if no scope does.
+    pub fn assign(&mut self, name: &str, value: Object) -> bool {
+        if let Some(member) = self.members.get_mut(name) {
+            *member = value;
+            return true;
+        }
+        match &self.parent {
+            Some(parent) => parent.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+
+    /// Removes every binding in this scope.
+    pub fn clear(&mut self) {
+        self.members.clear();
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Object> {
+        match self.members.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().lookup(name),
+        }
+    }
+}
+
+impl heap::Trace for Environment {
+    fn trace(&self, visit: &mut dyn FnMut(Rc<dyn heap::Node>)) {
+        for value in self.members.values() {
+            heap::trace_object(value, visit);
+        }
+        if let Some(parent) = &self.parent {
+            visit(parent.clone());
+        }
+    }
+
+    fn clear(&mut self) {
+        self.members.clear();
+        self.parent = None;
+    }
+
+    fn size(&self) -> usize {
+        self.members.capacity() * size_of::<(String, Object)>()
+            + self.members.keys().map(String::capacity).sum::<usize>()
+    }
+}
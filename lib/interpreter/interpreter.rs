@@ -0,0 +1,935 @@
+use crate::{
+    core::{
+        heap::{self, Collection, GcSettings, GcStats},
+        ops, Bool, DecimalContext, Dict, Function, Key, List, Object, Overflow, Struct, StructType,
+    },
+    errors::{Error, ErrorType, RuntimeError},
+    interpreter::{
+        builtins::{self, Builtin, Call, Io},
+        EnvRef, Environment,
+    },
+    lexer::Span,
+    parser::ast::*,
+};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+/// The default for how deeply function calls may be nested before a
+/// `RecursionError` is raised.
+///
+/// Each nested call uses the host's stack, so the thread running the
+/// interpreter needs enough stack space for this many calls (see
+/// `STACK_PER_CALL`), otherwise the host will overflow its stack rather than
+/// raising an error. See `Interpreter::set_max_call_depth`.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// Roughly how much of the host's stack a single nested function call uses
+/// in a debug build, including evaluating its arguments and body.
+pub const STACK_PER_CALL: usize = 16 * 1024;
+
+/// How control leaves a statement.
+pub enum Flow {
+    /// Continue with the next statement.
+    Next,
+    /// Unwind to the enclosing function call, producing the given value.
+    Return(Object),
+    /// Leave the enclosing loop.
+    Break,
+    /// Skip to the next iteration of the enclosing loop.
+    Continue,
+}
+
+fn undefined_name(name: &str, span: &Span) -> Error {
+    Error::new(
+        ErrorType::NameError,
+        &format!("Name '{}' is not defined", name),
+        span.clone(),
+    )
+}
+
+/// Attaches a location to an error raised while indexing into an object.
+/// Out of range indices point at the index expression, while other errors
+/// point at the whole expression.
+fn index_error(error: RuntimeError, index: &Node, span: &Span) -> Error {
+    match error.error_type {
+        ErrorType::IndexError => error.at(index.span()),
+        _ => error.at(span),
+    }
+}
+
+/// Matches the arguments of a call to the parameters named `params`, first
+/// positional arguments in order, then keyword arguments by name. Returns the
+/// value given for each parameter along with any positional arguments left
+/// over, which is only allowed if `variadic` is set.
+fn match_arguments(
+    signature: &str,
+    params: &[&str],
+    variadic: bool,
+    args: Vec<Object>,
+    kwargs: Vec<(&str, Object)>,
+    span: &Span,
+) -> Result<(Vec<Option<Object>>, Vec<Object>), Error> {
+    if !variadic && args.len() > params.len() {
+        return Err(argument_error(
+            signature,
+            &format!(
+                "takes {} arguments but {} were given",
+                params.len(),
+                args.len()
+            ),
+            span,
+        ));
+    }
+
+    let mut values: Vec<Option<Object>> = vec![None; params.len()];
+    let mut args = args.into_iter();
+    for (value, arg) in values.iter_mut().zip(args.by_ref()) {
+        *value = Some(arg);
+    }
+    for (name, arg) in kwargs {
+        match params.iter().position(|param| *param == name) {
+            Some(i) if values[i].is_some() => {
+                return Err(argument_error(
+                    signature,
+                    &format!("got multiple values for argument '{}'", name),
+                    span,
+                ))
+            }
+            Some(i) => values[i] = Some(arg),
+            None => {
+                return Err(argument_error(
+                    signature,
+                    &format!("got an unexpected keyword argument '{}'", name),
+                    span,
+                ))
+            }
+        }
+    }
+    Ok((values, args.collect()))
+}
+
+fn argument_error(signature: &str, message: &str, span: &Span) -> Error {
+    Error::new(
+        ErrorType::TypeError,
+        &format!("{} {}", signature, message),
+        span.clone(),
+    )
+}
+
+/// Looks up the method `name` on a struct instance, returning a copy of the
+/// method with `self` bound to the instance.
+fn bind_method(receiver: &Object, name: &str) -> Option<Function> {
+    let Object::Struct(instance) = receiver else {
+        return None;
+    };
+    let method = instance.ty.methods.get(name)?;
+    let env = Environment::new_enclosed(method.env.clone()).into_ref();
+    env.borrow_mut().define("self", receiver.clone());
+    Some(Function {
+        name: method.name.clone(),
+        params: method.params.clone(),
+        body: method.body.clone(),
+        env,
+    })
+}
+
+pub struct Interpreter {
+    globals: EnvRef,
+    /// The scope statements are currently being executed in.
+    env: EnvRef,
+    /// The scopes of the callers of the function being executed, which are
+    /// returned to when it finishes.
+    frames: Vec<EnvRef>,
+    max_call_depth: usize,
+    arithmetic: ops::Arithmetic,
+    gc_settings: GcSettings,
+    gc_stats: GcStats,
+    io: Io,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Functions refer to the scope they were declared in, which refers back to
+/// them, so the global scope is emptied to free them along with everything
+/// else the program created. Objects which are still kept alive only by
+/// cycles between them are then freed too.
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        self.globals.borrow_mut().clear();
+        heap::collect(&[]);
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let globals = Environment::new().into_ref();
+        Self {
+            env: globals.clone(),
+            globals,
+            frames: Vec::new(),
+            max_call_depth: MAX_CALL_DEPTH,
+            arithmetic: ops::Arithmetic::default(),
+            gc_settings: GcSettings::default(),
+            gc_stats: GcStats::default(),
+            io: Io::default(),
+        }
+    }
+
+    /// Sets how deeply function calls may be nested before a
+    /// `RecursionError` is raised.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Sets what happens when integer arithmetic doesn't fit in 64 bits. By
+    /// default the result is promoted to a big int.
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.arithmetic.overflow = overflow;
+    }
+
+    /// Sets how many digits decimal division keeps after the decimal point,
+    /// and how it rounds the rest.
+    pub fn set_decimal_context(&mut self, context: DecimalContext) {
+        self.arithmetic.decimal = context;
+    }
+
+    /// Sets when garbage is collected. By default a collection runs after
+    /// every 1000 lists, dicts, struct instances and scopes are created.
+    pub fn set_gc_settings(&mut self, settings: GcSettings) {
+        self.gc_settings = settings;
+    }
+
+    /// Returns the totals of every garbage collection the interpreter has
+    /// run.
+    pub fn gc_stats(&self) -> GcStats {
+        self.gc_stats
+    }
+
+    /// Frees the objects which the program can no longer reach, but which
+    /// are kept alive by cycles between them. The roots of the collection are
+    /// the global scope and the scopes of the functions being called.
+    pub fn collect_garbage(&mut self) -> Collection {
+        let roots: Vec<*const ()> = std::iter::once(&self.globals)
+            .chain(&self.frames)
+            .chain(std::iter::once(&self.env))
+            .map(|env| Rc::as_ptr(env) as *const ())
+            .collect();
+        let collection = heap::collect(&roots);
+        self.gc_stats.record(collection);
+        collection
+    }
+
+    /// Sets where `print` writes to. By default it writes to the standard
+    /// output.
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.io.output = Box::new(output);
+    }
+
+    /// Sets where `input` reads from. By default it reads from the standard
+    /// input.
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.io.input = Box::new(input);
+    }
+
+    /// Executes a parsed program in the interpreter's global scope.
+    pub fn run(&mut self, program: &Node) -> Result<(), Error> {
+        self.exec(program)?;
+        Ok(())
+    }
+
+    /// Returns the value of a global variable.
+    pub fn get_variable(&self, name: &str) -> Option<Object> {
+        self.globals.borrow().lookup(name)
+    }
+
+    fn exec_statements(&mut self, statements: &[Node]) -> Result<Flow, Error> {
+        for statement in statements {
+            // Collections run between statements, when the values being
+            // worked on are either stored in a scope or no longer needed.
+            if self.gc_settings.enabled && heap::allocations() >= self.gc_settings.threshold {
+                self.collect_garbage();
+            }
+            match self.exec(statement)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec(&mut self, node: &Node) -> Result<Flow, Error> {
+        match node {
+            Node::Program { statements, .. } | Node::Block { statements, .. } => {
+                self.exec_statements(statements)
+            }
+            Node::Import { path, span } => Err(Error::new(
+                ErrorType::NameError,
+                &format!("Cannot import '{}': imports are not supported yet", path),
+                span.clone(),
+            )),
+            Node::VariableDecl { name, value, .. } => {
+                let value = self.eval(value)?;
+                self.env.borrow_mut().define(name, value);
+                Ok(Flow::Next)
+            }
+            Node::FunctionDecl {
+                name, params, body, ..
+            } => {
+                let function = Function {
+                    name: Some(name.clone()),
+                    params: params.clone(),
+                    body: body.clone(),
+                    env: self.env.clone(),
+                };
+                self.env.borrow_mut().define(name, function.into());
+                Ok(Flow::Next)
+            }
+            Node::StructDecl {
+                name,
+                fields,
+                methods,
+                ..
+            } => {
+                let ty = self.struct_type(name, fields, methods);
+                self.env
+                    .borrow_mut()
+                    .define(name, Object::StructType(Rc::new(ty)));
+                Ok(Flow::Next)
+            }
+            Node::Assign {
+                name,
+                op,
+                value,
+                span,
+            } => {
+                let mut value = self.eval(value)?;
+                if let Some(op) = op {
+                    let current = self
+                        .env
+                        .borrow()
+                        .lookup(name)
+                        .ok_or_else(|| undefined_name(name, span))?;
+                    value = ops::binary_op(op, &current, &value, &self.arithmetic)
+                        .map_err(|error| error.at(span))?;
+                }
+                if !self.env.borrow_mut().assign(name, value) {
+                    return Err(undefined_name(name, span));
+                }
+                Ok(Flow::Next)
+            }
+            Node::IndexAssign {
+                object,
+                index,
+                op,
+                value,
+                span,
+            } => {
+                let container = self.eval(object)?;
+                let index_value = self.eval(index)?;
+                let mut value = self.eval(value)?;
+                if let Some(op) = op {
+                    let current = ops::get_index(&container, &index_value)
+                        .map_err(|error| index_error(error, index, span))?;
+                    value = ops::binary_op(op, &current, &value, &self.arithmetic)
+                        .map_err(|error| error.at(span))?;
+                }
+                ops::set_index(&container, &index_value, value)
+                    .map_err(|error| index_error(error, index, span))?;
+                Ok(Flow::Next)
+            }
+            Node::FieldAssign {
+                object,
+                name,
+                op,
+                value,
+                span,
+            } => {
+                let instance = self.eval(object)?;
+                let mut value = self.eval(value)?;
+                if let Some(op) = op {
+                    let current =
+                        ops::get_field(&instance, name).map_err(|error| error.at(span))?;
+                    value = ops::binary_op(op, &current, &value, &self.arithmetic)
+                        .map_err(|error| error.at(span))?;
+                }
+                ops::set_field(&instance, name, value).map_err(|error| error.at(span))?;
+                Ok(Flow::Next)
+            }
+            Node::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.eval(value)?,
+                    None => Object::None,
+                };
+                Ok(Flow::Return(value))
+            }
+            Node::Selection {
+                if_conditionals,
+                else_conditional,
+                ..
+            } => {
+                for conditional in if_conditionals {
+                    if self.eval(&conditional.condition)?.is_truthy() {
+                        return self.exec(&conditional.body);
+                    }
+                }
+                match else_conditional {
+                    Some(body) => self.exec(body),
+                    None => Ok(Flow::Next),
+                }
+            }
+            Node::While { conditional, .. } => {
+                while self.eval(&conditional.condition)?.is_truthy() {
+                    match self.exec(&conditional.body)? {
+                        Flow::Next | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            Node::Match { subject, cases, .. } => {
+                let subject = self.eval(subject)?;
+                for case in cases {
+                    let mut bindings = Vec::new();
+                    if !self.match_pattern(&case.pattern, &subject, &mut bindings)? {
+                        continue;
+                    }
+                    // The guard sees the bindings in a scope of their own,
+                    // so a case whose guard fails leaves no trace.
+                    if let Some(guard) = &case.guard {
+                        let guard_env = Environment::new_enclosed(self.env.clone()).into_ref();
+                        for (name, value) in &bindings {
+                            guard_env.borrow_mut().define(name, value.clone());
+                        }
+                        let env = std::mem::replace(&mut self.env, guard_env);
+                        let guard = self.eval(guard);
+                        self.env = env;
+                        if !guard?.is_truthy() {
+                            continue;
+                        }
+                    }
+                    for (name, value) in bindings {
+                        self.env.borrow_mut().define(name, value);
+                    }
+                    return self.exec(&case.body);
+                }
+                Ok(Flow::Next)
+            }
+            Node::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                let iterable_value = self.eval(iterable)?;
+                let items =
+                    ops::iterate(&iterable_value).map_err(|error| error.at(iterable.span()))?;
+                for item in items {
+                    self.env.borrow_mut().define(variable, item);
+                    match self.exec(body)? {
+                        Flow::Next | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            Node::Break { .. } => Ok(Flow::Break),
+            Node::Continue { .. } => Ok(Flow::Continue),
+            _ => {
+                self.eval(node)?;
+                Ok(Flow::Next)
+            }
+        }
+    }
+
+    fn eval(&mut self, node: &Node) -> Result<Object, Error> {
+        match node {
+            Node::Literal { value, .. } => Ok(value.clone()),
+            Node::Identifier { name, span } => self
+                .env
+                .borrow()
+                .lookup(name)
+                .ok_or_else(|| undefined_name(name, span)),
+            Node::List { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.eval(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(List::new(elements).into())
+            }
+            Node::Dict { entries, .. } => {
+                let dict = Dict::new();
+                for (key, value) in entries {
+                    let key_value = self.eval(key)?;
+                    let key_value =
+                        Key::from_object(&key_value).map_err(|error| error.at(key.span()))?;
+                    let value = self.eval(value)?;
+                    dict.insert(key_value, value);
+                }
+                Ok(dict.into())
+            }
+            Node::Index {
+                object,
+                index,
+                span,
+            } => {
+                let object = self.eval(object)?;
+                let index_value = self.eval(index)?;
+                ops::get_index(&object, &index_value)
+                    .map_err(|error| index_error(error, index, span))
+            }
+            Node::Field { object, name, span } => {
+                let object = self.eval(object)?;
+                ops::get_field(&object, name).or_else(|error| match bind_method(&object, name) {
+                    Some(method) => Ok(method.into()),
+                    None => Err(error.at(span)),
+                })
+            }
+            Node::Slice {
+                object,
+                start,
+                stop,
+                step,
+                span,
+            } => {
+                let object = self.eval(object)?;
+                let mut bounds = Vec::new();
+                for bound in [start, stop, step] {
+                    bounds.push(match bound {
+                        Some(bound) => Some(self.eval(bound)?),
+                        None => None,
+                    });
+                }
+                ops::get_slice(
+                    &object,
+                    bounds[0].as_ref(),
+                    bounds[1].as_ref(),
+                    bounds[2].as_ref(),
+                )
+                .map_err(|error| error.at(span))
+            }
+            Node::UnaryOp { op, child, span } => {
+                let child = self.eval(child)?;
+                ops::unary_op(op, &child, &self.arithmetic).map_err(|error| error.at(span))
+            }
+            Node::BinaryOp { op, lhs, rhs, span } => {
+                let lhs = self.eval(lhs)?;
+                // `and` and `or` short circuit, so the right hand side may
+                // never be evaluated
+                match op {
+                    Operator::And if !lhs.is_truthy() => Ok(Bool { value: false }.into()),
+                    Operator::Or if lhs.is_truthy() => Ok(Bool { value: true }.into()),
+                    Operator::And | Operator::Or => Ok(Bool {
+                        value: self.eval(rhs)?.is_truthy(),
+                    }
+                    .into()),
+                    _ => {
+                        let rhs = self.eval(rhs)?;
+                        ops::binary_op(op, &lhs, &rhs, &self.arithmetic)
+                            .map_err(|error| error.at(span))
+                    }
+                }
+            }
+            Node::Lambda { params, body, .. } => Ok(Function {
+                name: None,
+                params: params.clone(),
+                body: body.clone(),
+                env: self.env.clone(),
+            }
+            .into()),
+            Node::FunctionCall {
+                function,
+                args,
+                kwargs,
+                span,
+            } => self.call_function(function, args, kwargs, span),
+            _ => Err(Error::new(
+                ErrorType::SyntaxError,
+                "Expected an expression",
+                node.span().clone(),
+            )),
+        }
+    }
+
+    /// Creates the type declared by a `struct` statement. Its methods capture
+    /// the current scope, like functions declared with `fn`.
+    fn struct_type(&self, name: &str, fields: &[String], methods: &[Node]) -> StructType {
+        let mut ty = StructType {
+            name: name.to_string(),
+            fields: fields.to_vec(),
+            methods: HashMap::new(),
+        };
+        for method in methods {
+            if let Node::FunctionDecl {
+                name: method_name,
+                params,
+                body,
+                ..
+            } = method
+            {
+                let function = Function {
+                    name: Some(format!("{}.{}", name, method_name)),
+                    params: params.clone(),
+                    body: body.clone(),
+                    env: self.env.clone(),
+                };
+                ty.methods.insert(method_name.clone(), Rc::new(function));
+            }
+        }
+        ty
+    }
+
+    /// Checks whether `value` matches `pattern`, collecting the values of any
+    /// names the pattern binds.
+    fn match_pattern<'a>(
+        &self,
+        pattern: &'a Pattern,
+        value: &Object,
+        bindings: &mut Vec<(&'a str, Object)>,
+    ) -> Result<bool, Error> {
+        match pattern {
+            Pattern::Wildcard { .. } => Ok(true),
+            Pattern::Binding { name, .. } => {
+                bindings.push((name, value.clone()));
+                Ok(true)
+            }
+            Pattern::Literal { value: literal, .. } => Ok(ops::equals(literal, value)),
+            Pattern::List { elements, .. } => {
+                let Object::List(list) = value else {
+                    return Ok(false);
+                };
+                let rest = elements
+                    .iter()
+                    .position(|element| matches!(element, Pattern::Rest { .. }));
+                let (before, after) = match rest {
+                    Some(i) => (&elements[..i], &elements[i + 1..]),
+                    None => (&elements[..], &[][..]),
+                };
+                let values = list.to_vec();
+                let fits = match rest {
+                    Some(_) => values.len() >= before.len() + after.len(),
+                    None => values.len() == before.len(),
+                };
+                if !fits {
+                    return Ok(false);
+                }
+                let rest_end = values.len() - after.len();
+                let pairs = before
+                    .iter()
+                    .zip(&values[..before.len()])
+                    .chain(after.iter().zip(&values[rest_end..]));
+                for (element, value) in pairs {
+                    if !self.match_pattern(element, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                if let Some(Pattern::Rest {
+                    name: Some(name), ..
+                }) = rest.map(|i| &elements[i])
+                {
+                    let elements = values[before.len()..rest_end].to_vec();
+                    bindings.push((name, List::new(elements).into()));
+                }
+                Ok(true)
+            }
+            // Only parsed as an element of a list pattern, which handles it.
+            Pattern::Rest { .. } => Ok(true),
+            Pattern::Struct {
+                name,
+                args,
+                kwargs,
+                span,
+            } => {
+                let ty = match self.env.borrow().lookup(name) {
+                    Some(Object::StructType(ty)) => ty,
+                    Some(other) => {
+                        return Err(Error::new(
+                            ErrorType::TypeError,
+                            &format!(
+                                "'{}' is not a struct type, it is a '{}'",
+                                name,
+                                other.type_name()
+                            ),
+                            span.clone(),
+                        ))
+                    }
+                    None => return Err(undefined_name(name, span)),
+                };
+                if args.len() > ty.fields.len() {
+                    return Err(Error::new(
+                        ErrorType::TypeError,
+                        &format!(
+                            "{} has {} fields but {} patterns were given",
+                            ty.signature(),
+                            ty.fields.len(),
+                            args.len()
+                        ),
+                        span.clone(),
+                    ));
+                }
+                let fields = ty.fields.iter().map(String::as_str).zip(args);
+                let kwargs = kwargs
+                    .iter()
+                    .map(|(field, pattern)| (field.as_str(), pattern));
+                let instance = match value {
+                    Object::Struct(instance) if Rc::ptr_eq(&instance.ty, &ty) => Some(instance),
+                    _ => None,
+                };
+                for (field, pattern) in fields.chain(kwargs) {
+                    // Unknown fields are reported even if the value isn't an
+                    // instance of the type.
+                    let index = ty.field_index(field).ok_or_else(|| {
+                        Error::new(
+                            ErrorType::AttributeError,
+                            &format!("'{}' has no field '{}'", ty.name, field),
+                            pattern.span().clone(),
+                        )
+                    })?;
+                    if let Some(instance) = instance {
+                        if !self.match_pattern(pattern, &instance.values()[index], bindings)? {
+                            return Ok(false);
+                        }
+                    }
+                }
+                Ok(instance.is_some())
+            }
+        }
+    }
+
+    fn call_function(
+        &mut self,
+        callee: &Node,
+        args: &[Node],
+        kwargs: &[(String, Node)],
+        span: &Span,
+    ) -> Result<Object, Error> {
+        // A builtin is only called if the program doesn't define its name.
+        if let Node::Identifier { name, .. } = callee {
+            let is_defined = self.env.borrow().lookup(name).is_some();
+            if let (Some(builtin), false) = (builtins::lookup(name), is_defined) {
+                return self.call_builtin(builtin, args, kwargs, span);
+            }
+        }
+        let callee_value = match callee {
+            Node::Field { object, name, span } => {
+                let receiver = self.eval(object)?;
+                match ops::get_field(&receiver, name) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        if let Some(method) = bind_method(&receiver, name) {
+                            return self.call_with(&method, args, kwargs, span);
+                        }
+                        if let Some((list, method)) = builtins::lookup_method(&receiver, name) {
+                            return self
+                                .call_builtin_method(&list, method, name, args, kwargs, span);
+                        }
+                        return Err(error.at(span));
+                    }
+                }
+            }
+            _ => self.eval(callee)?,
+        };
+        match callee_value {
+            Object::Function(function) => self.call_with(&function, args, kwargs, span),
+            Object::StructType(ty) => self.construct(ty, args, kwargs, span),
+            other => Err(Error::new(
+                ErrorType::TypeError,
+                &format!("'{}' object is not callable", other.type_name()),
+                callee.span().clone(),
+            )),
+        }
+    }
+
+    /// Evaluates the arguments of a call to a builtin function and calls it.
+    fn call_builtin(
+        &mut self,
+        builtin: &Builtin,
+        args: &[Node],
+        kwargs: &[(String, Node)],
+        span: &Span,
+    ) -> Result<Object, Error> {
+        for (name, _) in kwargs {
+            if builtin.keywords.contains(&name.as_str()) {
+                continue;
+            }
+            let message = if builtin.keywords.is_empty() {
+                format!("{}() does not take keyword arguments", builtin.name)
+            } else {
+                format!(
+                    "{}() got an unexpected keyword argument '{}'",
+                    builtin.name, name
+                )
+            };
+            return Err(Error::new(ErrorType::TypeError, &message, span.clone()));
+        }
+        let values = self.eval_args(args)?;
+        let keyword_values = self.eval_kwargs(kwargs)?;
+        let mut call = Call {
+            name: builtin.name,
+            args: values
+                .into_iter()
+                .zip(args.iter().map(Node::span))
+                .collect(),
+            kwargs: keyword_values
+                .into_iter()
+                .zip(kwargs)
+                .map(|((name, value), (_, node))| (name, value, node.span()))
+                .collect(),
+            span,
+            io: &mut self.io,
+            arithmetic: &self.arithmetic,
+        };
+        builtin.call(&mut call)
+    }
+
+    /// Calls a builtin method of `list`.
+    fn call_builtin_method(
+        &mut self,
+        list: &List,
+        method: builtins::Method,
+        name: &str,
+        args: &[Node],
+        kwargs: &[(String, Node)],
+        span: &Span,
+    ) -> Result<Object, Error> {
+        if !kwargs.is_empty() {
+            return Err(Error::new(
+                ErrorType::TypeError,
+                &format!("list.{}() does not take keyword arguments", name),
+                span.clone(),
+            ));
+        }
+        let args = self.eval_args(args)?;
+        method(list, &args).map_err(|error| error.at(span))
+    }
+
+    /// Creates an instance of `ty`, taking the value of each field from the
+    /// arguments.
+    fn construct(
+        &mut self,
+        ty: Rc<StructType>,
+        args: &[Node],
+        kwargs: &[(String, Node)],
+        span: &Span,
+    ) -> Result<Object, Error> {
+        let args = self.eval_args(args)?;
+        let kwargs = self.eval_kwargs(kwargs)?;
+        let fields: Vec<&str> = ty.fields.iter().map(String::as_str).collect();
+        let signature = ty.signature();
+        let (values, _) = match_arguments(&signature, &fields, false, args, kwargs, span)?;
+        let values = fields
+            .iter()
+            .zip(values)
+            .map(|(field, value)| {
+                value.ok_or_else(|| {
+                    argument_error(
+                        &signature,
+                        &format!("is missing the argument '{}'", field),
+                        span,
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Struct::new(ty, values).into())
+    }
+
+    /// Evaluates the arguments of a call and runs `function`'s body with them.
+    fn call_with(
+        &mut self,
+        function: &Function,
+        args: &[Node],
+        kwargs: &[(String, Node)],
+        span: &Span,
+    ) -> Result<Object, Error> {
+        let args = self.eval_args(args)?;
+        let kwargs = self.eval_kwargs(kwargs)?;
+        if self.frames.len() >= self.max_call_depth {
+            return Err(Error::new(
+                ErrorType::RecursionError,
+                "Maximum recursion depth exceeded",
+                span.clone(),
+            ));
+        }
+
+        // Defaults are evaluated in the new scope, so that they can refer to
+        // earlier parameters.
+        let call_env = Environment::new_enclosed(function.env.clone()).into_ref();
+        let caller_env = std::mem::replace(&mut self.env, call_env);
+        self.frames.push(caller_env);
+        let result = self
+            .bind_arguments(function, args, kwargs, span)
+            .and_then(|()| self.exec(&function.body));
+        self.env = self.frames.pop().unwrap_or_else(|| self.globals.clone());
+
+        match result? {
+            Flow::Return(value) => Ok(value),
+            // `break` and `continue` can't appear outside of a loop, see
+            // `Parser::parse_loop_control`.
+            Flow::Next | Flow::Break | Flow::Continue => Ok(Object::None),
+        }
+    }
+
+    fn eval_args(&mut self, args: &[Node]) -> Result<Vec<Object>, Error> {
+        args.iter().map(|arg| self.eval(arg)).collect()
+    }
+
+    fn eval_kwargs<'a>(
+        &mut self,
+        kwargs: &'a [(String, Node)],
+    ) -> Result<Vec<(&'a str, Object)>, Error> {
+        kwargs
+            .iter()
+            .map(|(name, value)| Ok((name.as_str(), self.eval(value)?)))
+            .collect()
+    }
+
+    /// Defines `function`'s parameters in the current scope, matching
+    /// positional arguments in order, then keyword arguments by name, and
+    /// falling back to the parameters' defaults.
+    fn bind_arguments(
+        &mut self,
+        function: &Function,
+        args: Vec<Object>,
+        kwargs: Vec<(&str, Object)>,
+        span: &Span,
+    ) -> Result<(), Error> {
+        let (params, rest) = match function.params.split_last() {
+            Some((last, params)) if last.variadic => (params, Some(last)),
+            _ => (&function.params[..], None),
+        };
+        let signature = function.signature();
+        let names: Vec<&str> = params.iter().map(|param| param.name.as_str()).collect();
+        let (values, rest_values) =
+            match_arguments(&signature, &names, rest.is_some(), args, kwargs, span)?;
+
+        for (param, value) in params.iter().zip(values) {
+            let value = match (value, &param.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.eval(default)?,
+                (None, None) => {
+                    return Err(argument_error(
+                        &signature,
+                        &format!("is missing the argument '{}'", param.name),
+                        span,
+                    ))
+                }
+            };
+            self.env.borrow_mut().define(&param.name, value);
+        }
+        if let Some(rest) = rest {
+            let elements = rest_values;
+            self.env
+                .borrow_mut()
+                .define(&rest.name, List::new(elements).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+pub mod builtins;
+pub mod environment;
+pub use environment::{EnvRef, Environment};
+#[allow(clippy::module_inception)]
+pub mod interpreter;
+pub use interpreter::Interpreter;
@@ -1,6 +1,8 @@
+use std::collections::VecDeque;
 use std::str::Chars;
 
 use crate::{
+    core::BigInt,
     errors::{Error, ErrorType},
     lexer::token::*,
 };
@@ -9,10 +11,42 @@ use super::Span;
 
 type EncountredNewline = bool;
 
+/// A `// sap:allow(rule, ...)` comment, which stops the named lint rules from
+/// being reported on its line, or on the next line if the comment is on a
+/// line of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suppression {
+    pub rules: Vec<String>,
+    pub span: Span,
+}
+
+impl Suppression {
+    /// Parses the text of a line comment, after the `//`.
+    fn parse(comment: &str, span: Span) -> Option<Self> {
+        let rules = comment
+            .trim()
+            .strip_prefix("sap:allow(")?
+            .strip_suffix(')')?;
+        Some(Self {
+            rules: rules
+                .split(',')
+                .map(|rule| rule.trim().to_string())
+                .filter(|rule| !rule.is_empty())
+                .collect(),
+            span,
+        })
+    }
+}
+
 pub struct Lexer<'source> {
     input: Chars<'source>,
     cur: char,
     cur_idx: usize,
+    /// Tokens which have been lexed ahead of time by `peek_nth`.
+    lookahead: VecDeque<Result<Token, Error>>,
+    /// Set once the `Eof` token has been yielded by the iterator.
+    finished: bool,
+    suppressions: Vec<Suppression>,
 }
 
 impl<'source> Lexer<'source> {
@@ -23,9 +57,17 @@ impl<'source> Lexer<'source> {
             input,
             cur,
             cur_idx: 0,
+            lookahead: VecDeque::new(),
+            finished: false,
+            suppressions: Vec::new(),
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'source str) -> Self {
+        Self::new(input.chars())
+    }
+
     fn construct_error(&self, msg: &str, token: Token) -> Result<Token, Error> {
         Err(Error {
             message: msg.to_string(),
@@ -35,6 +77,11 @@ impl<'source> Lexer<'source> {
     }
 
     fn advance(&mut self) {
+        // Never move past the end of the input
+        if self.cur == '\0' {
+            return;
+        }
+
         let mut steps = 1;
 
         if self.cur == '\r' {
@@ -54,6 +101,21 @@ impl<'source> Lexer<'source> {
         self.input.clone().next().unwrap_or('\0')
     }
 
+    /// Returns the character `n` positions after the one following `cur`,
+    /// so `peek_char(0)` is equivalent to `peek()`.
+    fn peek_char(&self, n: usize) -> char {
+        self.input.clone().nth(n).unwrap_or('\0')
+    }
+
+    /// Whether the lexer is at the start of a `///` doc comment. Comments
+    /// starting with four or more slashes are ordinary comments.
+    fn at_doc_comment(&self) -> bool {
+        self.cur == '/'
+            && self.peek() == '/'
+            && self.peek_char(1) == '/'
+            && self.peek_char(2) != '/'
+    }
+
     fn skip_whitespace(&mut self) {
         while self.cur == ' ' {
             self.advance();
@@ -61,15 +123,43 @@ impl<'source> Lexer<'source> {
     }
 
     fn skip_comment(&mut self) {
+        let start = self.cur_idx;
+        let mut comment = String::new();
         // Consume the comment
+        // The newline itself is left for `skip_garbage`, so that a comment at
+        // the end of a line still produces a separator.
         while self.cur != '\n' && self.cur != '\r' {
+            comment.push(self.cur);
             self.advance();
             if self.cur == '\0' {
                 break;
             };
         }
-        // Consume the newline
-        self.advance();
+        let span = Span::new(start, self.cur_idx);
+        if let Some(suppression) = Suppression::parse(&comment[2..], span) {
+            self.suppressions.push(suppression);
+        }
+    }
+
+    /// The `// sap:allow(...)` comments the lexer has passed so far.
+    pub fn suppressions(&self) -> &[Suppression] {
+        &self.suppressions
+    }
+
+    fn read_doc_comment(&mut self) -> String {
+        // Consume the opening '///' and at most one space after it
+        for _ in 0..3 {
+            self.advance();
+        }
+        if self.cur == ' ' {
+            self.advance();
+        }
+        let mut doc = String::new();
+        while !matches!(self.cur, '\n' | '\r' | '\0') {
+            doc.push(self.cur);
+            self.advance();
+        }
+        doc
     }
 
     fn skip_multi_comment(&mut self) -> Result<(), Error> {
@@ -79,15 +169,31 @@ impl<'source> Lexer<'source> {
             self.advance();
             self.advance();
         } else {
-            panic!(
-                "Invalid multiline comment: {} - Multiline comments must start with '/*'",
-                self.cur
-            );
+            return Err(Error {
+                message: format!(
+                    "Invalid multiline comment: '{}' - Multiline comments must start with '/*'",
+                    self.cur
+                ),
+                error_type: ErrorType::SyntaxError,
+                token: Token {
+                    kind: TokenKind::Unknown,
+                    span: Span::new(start_pos, start_pos + 1),
+                },
+            });
         }
-        // Consume the comment
-        while !(self.cur == '*' && self.peek() == '/') {
+        // Consume the comment, keeping track of how deeply nested we are so
+        // that a region containing a block comment can itself be commented out
+        let mut depth = 1;
+        while depth > 0 {
+            if self.cur == '/' && self.peek() == '*' {
+                depth += 1;
+                self.advance();
+            } else if self.cur == '*' && self.peek() == '/' {
+                depth -= 1;
+                self.advance();
+            }
             self.advance();
-            if self.cur == '\0' {
+            if depth > 0 && self.cur == '\0' {
                 return Err(Error {
                     message: "Unexpected end of file while parsing multiline comment".to_string(),
                     error_type: ErrorType::SyntaxError,
@@ -98,9 +204,6 @@ impl<'source> Lexer<'source> {
                 });
             };
         }
-        // Consume the closing '*/'
-        self.advance();
-        self.advance();
 
         Ok(())
     }
@@ -110,7 +213,7 @@ impl<'source> Lexer<'source> {
         // count newlines, however it only needs to know if it encountered one,
         // not how many it encountered.
         let mut encountered_newline = false;
-        while matches!(self.cur, ' ' | '\n' | '\r' | '/') {
+        loop {
             match self.cur {
                 // Skip whitespace
                 ' ' => self.skip_whitespace(),
@@ -121,17 +224,19 @@ impl<'source> Lexer<'source> {
                 }
                 // Skip comments
                 '/' => match self.peek() {
+                    // Doc comments are kept as tokens
+                    '/' if self.at_doc_comment() => break,
                     '/' => self.skip_comment(),
                     '*' => self.skip_multi_comment()?,
                     _ => break,
                 },
-                _ => unreachable!(),
+                _ => break,
             }
         }
-        return Ok(encountered_newline);
+        Ok(encountered_newline)
     }
 
-    fn read_ident(&mut self) -> String {
+    fn read_ident(&mut self) -> Result<String, String> {
         let mut ident = String::new();
         // Check if the first character is a letter or an underscore
         if self.cur.is_alphabetic() || self.cur == '_' {
@@ -141,12 +246,12 @@ impl<'source> Lexer<'source> {
                 self.advance();
             }
         } else {
-            panic!(
-                "Invalid identifier: {} - Identifier must start with a letter or an underscore",
+            return Err(format!(
+                "Invalid identifier: '{}' - Identifier must start with a letter or an underscore",
                 self.cur
-            );
+            ));
         }
-        return ident;
+        Ok(ident)
     }
 
     fn read_string(&mut self) -> Result<String, String> {
@@ -155,32 +260,32 @@ impl<'source> Lexer<'source> {
         if self.cur == '"' {
             self.advance();
         } else {
-            panic!(
-                "Invalid string: {} - String must start with a double quote",
+            return Err(format!(
+                "Invalid string: '{}' - String must start with a double quote",
                 self.cur
-            );
+            ));
         }
         // Consume the string
         while self.cur != '"' {
-            string.push(self.cur);
-            self.advance();
             match self.cur {
                 '\n' | '\r' => return Err("Unexpected newline while parsing string".to_string()),
                 '\0' => return Err("Unexpected end of file while parsing string".to_string()),
                 _ => {}
             }
+            string.push(self.cur);
+            self.advance();
         }
         // Consume the closing '"'
         self.advance();
-        return Ok(string);
+        Ok(string)
     }
 
     fn read_integer(&mut self) -> Result<String, String> {
         let mut num = String::new();
-        if self.cur.is_numeric() {
+        if self.cur.is_ascii_digit() {
             num.push(self.cur);
             self.advance();
-            while self.cur.is_numeric() {
+            while self.cur.is_ascii_digit() {
                 num.push(self.cur);
                 self.advance();
             }
@@ -190,7 +295,7 @@ impl<'source> Lexer<'source> {
                 num, self.cur
             ));
         }
-        return Ok(num);
+        Ok(num)
     }
 
     fn read_number(&mut self) -> Result<String, String> {
@@ -200,11 +305,33 @@ impl<'source> Lexer<'source> {
             self.advance();
             num.push_str(self.read_integer()?.as_str());
         }
-        return Ok(num);
+        Ok(num)
     }
 
+    /// Returns the next token in the input, consuming it.
+    ///
+    /// Once the end of the input is reached, every subsequent call returns an
+    /// `Eof` token.
     pub fn get_next_token(&mut self) -> Result<Token, Error> {
-        while self.cur != '\0' {
+        match self.lookahead.pop_front() {
+            Some(token) => token,
+            None => self.lex_token(),
+        }
+    }
+
+    /// Returns a reference to the token `n` positions ahead of the current
+    /// one without consuming anything, so `peek_nth(0)` is the token the next
+    /// call to `get_next_token` will return.
+    pub fn peek_nth(&mut self, n: usize) -> &Result<Token, Error> {
+        while self.lookahead.len() <= n {
+            let token = self.lex_token();
+            self.lookahead.push_back(token);
+        }
+        &self.lookahead[n]
+    }
+
+    fn lex_token(&mut self) -> Result<Token, Error> {
+        if self.cur != '\0' {
             if self.skip_garbage()? {
                 return Ok(Token {
                     kind: TokenKind::Seperator,
@@ -212,19 +339,46 @@ impl<'source> Lexer<'source> {
                 });
             }
 
+            if self.cur == '\0' {
+                return Ok(Token {
+                    kind: TokenKind::Eof,
+                    span: Span::new(self.cur_idx, self.cur_idx),
+                });
+            }
+
             let start_idx = self.cur_idx;
 
             if self.cur.is_alphabetic() || self.cur == '_' {
-                let ident = self.read_ident();
+                let ident = match self.read_ident() {
+                    Ok(ident) => ident,
+                    Err(msg) => {
+                        return self.construct_error(
+                            msg.as_str(),
+                            Token {
+                                kind: TokenKind::Unknown,
+                                span: Span::new(start_idx, self.cur_idx),
+                            },
+                        )
+                    }
+                };
                 let kind = match ident.as_str() {
                     "import" => TokenKind::Import,
+                    "let" => TokenKind::Let,
+                    "const" => TokenKind::Const,
                     "fn" => TokenKind::Fn,
+                    "struct" => TokenKind::Struct,
                     "if" => TokenKind::If,
                     "elif" => TokenKind::Elif,
                     "else" => TokenKind::Else,
                     "then" => TokenKind::Then,
+                    "match" => TokenKind::Match,
+                    "case" => TokenKind::Case,
                     "while" => TokenKind::While,
+                    "for" => TokenKind::For,
+                    "in" => TokenKind::In,
                     "do" => TokenKind::Do,
+                    "break" => TokenKind::Break,
+                    "continue" => TokenKind::Continue,
                     "return" => TokenKind::Return,
                     "end" => TokenKind::End,
                     "and" => TokenKind::And,
@@ -232,13 +386,14 @@ impl<'source> Lexer<'source> {
                     "not" => TokenKind::Not,
                     "true" => TokenKind::Bool(true),
                     "false" => TokenKind::Bool(false),
+                    "none" => TokenKind::None,
                     _ => TokenKind::Ident(ident),
                 };
                 return Ok(Token {
                     kind,
                     span: Span::new(start_idx, self.cur_idx),
                 });
-            } else if self.cur.is_numeric() {
+            } else if self.cur.is_ascii_digit() {
                 let num = match self.read_number() {
                     Ok(num) => num,
                     Err(msg) => {
@@ -251,15 +406,53 @@ impl<'source> Lexer<'source> {
                         )
                     }
                 };
+                // A `d` suffix makes the number a decimal, as long as it
+                // isn't the start of an identifier
+                if self.cur == 'd' && !(self.peek().is_alphanumeric() || self.peek() == '_') {
+                    self.advance();
+                    return Ok(Token {
+                        kind: TokenKind::Decimal(num),
+                        span: Span::new(start_idx, self.cur_idx),
+                    });
+                }
                 let kind = if num.contains('.') {
-                    TokenKind::Float(num.parse().unwrap())
+                    match num.parse() {
+                        Ok(value) => TokenKind::Float(value),
+                        Err(_) => {
+                            return self.construct_error(
+                                "Invalid float literal",
+                                Token {
+                                    kind: TokenKind::Unknown,
+                                    span: Span::new(start_idx, self.cur_idx),
+                                },
+                            )
+                        }
+                    }
                 } else {
-                    TokenKind::Int(num.parse().unwrap())
+                    match (num.parse(), BigInt::parse(&num)) {
+                        (Ok(value), _) => TokenKind::Int(value),
+                        (Err(_), Some(value)) => TokenKind::BigInt(value),
+                        (Err(_), None) => {
+                            return self.construct_error(
+                                "Invalid integer literal",
+                                Token {
+                                    kind: TokenKind::Unknown,
+                                    span: Span::new(start_idx, self.cur_idx),
+                                },
+                            )
+                        }
+                    }
                 };
                 return Ok(Token {
                     kind,
                     span: Span::new(start_idx, self.cur_idx),
                 });
+            } else if self.at_doc_comment() {
+                let doc = self.read_doc_comment();
+                return Ok(Token {
+                    kind: TokenKind::DocComment(doc),
+                    span: Span::new(start_idx, self.cur_idx),
+                });
             } else if self.cur == '"' {
                 match self.read_string() {
                     Ok(string) => {
@@ -280,6 +473,22 @@ impl<'source> Lexer<'source> {
                 }
             } else {
                 let kind = match self.cur {
+                    // Compound assignment operators
+                    '*' | '/' | '+' | '-' | '%' if self.peek() == '=' => {
+                        let kind = match self.cur {
+                            '*' => TokenKind::MultAssign,
+                            '/' => TokenKind::DivAssign,
+                            '+' => TokenKind::PlusAssign,
+                            '-' => TokenKind::MinusAssign,
+                            _ => TokenKind::ModAssign,
+                        };
+                        self.advance();
+                        kind
+                    }
+                    '-' if self.peek() == '>' => {
+                        self.advance();
+                        TokenKind::Arrow
+                    }
                     // Single character symbols
                     '*' => TokenKind::Mult,
                     '/' => TokenKind::Div,
@@ -290,6 +499,10 @@ impl<'source> Lexer<'source> {
                     ')' => TokenKind::Rparen,
                     '[' => TokenKind::LBracket,
                     ']' => TokenKind::RBracket,
+                    '{' => TokenKind::LBrace,
+                    '}' => TokenKind::RBrace,
+                    ':' => TokenKind::Colon,
+                    '.' => TokenKind::Dot,
                     ',' => TokenKind::Comma,
                     ';' => TokenKind::Seperator,
                     // Two character symbols
@@ -320,23 +533,27 @@ impl<'source> Lexer<'source> {
                             TokenKind::NotEq
                         }
                         _ => {
+                            // Skip the '!' so that lexing can resume afterwards
+                            self.advance();
                             return self.construct_error(
                                 "Expected '=' after '!'",
                                 Token {
                                     kind: TokenKind::Unknown,
                                     span: Span::new(start_idx, start_idx + 2),
                                 },
-                            )
+                            );
                         }
                     },
                     _ => {
+                        // Skip the symbol so that lexing can resume afterwards
+                        self.advance();
                         return self.construct_error(
                             "Unexpected symbol",
                             Token {
                                 kind: TokenKind::Unknown,
                                 span: Span::new(start_idx, start_idx + 1),
                             },
-                        )
+                        );
                     }
                 };
                 self.advance();
@@ -353,3 +570,42 @@ impl<'source> Lexer<'source> {
         })
     }
 }
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, Error>;
+
+    /// Yields every token in the input, including the final `Eof` token,
+    /// after which the iterator is fused and only returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.get_next_token();
+        if matches!(
+            token,
+            Ok(Token {
+                kind: TokenKind::Eof,
+                ..
+            })
+        ) {
+            self.finished = true;
+        }
+        Some(token)
+    }
+}
+
+impl std::iter::FusedIterator for Lexer<'_> {}
+
+/// Lexes the entire input, collecting every token (ending with `Eof`) and
+/// every error encountered along the way.
+pub fn tokenize(input: &str) -> (Vec<Token>, Vec<Error>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for token in Lexer::from_str(input) {
+        match token {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+    (tokens, errors)
+}
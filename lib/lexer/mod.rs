@@ -1,5 +1,6 @@
 pub mod span;
 pub use span::Span;
+#[allow(clippy::module_inception)]
 pub mod lexer;
 pub mod token;
-pub use lexer::Lexer;
+pub use lexer::{tokenize, Lexer, Suppression};
@@ -1,6 +1,7 @@
 use super::Span;
+use crate::core::BigInt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
     // symbols
     Mult,
@@ -12,7 +13,17 @@ pub enum TokenKind {
     Rparen,
     LBracket,
     RBracket,
+    LBrace,
+    RBrace,
+    Colon,
+    Dot,
+    Arrow,
     Assign,
+    PlusAssign,
+    MinusAssign,
+    MultAssign,
+    DivAssign,
+    ModAssign,
     Eq,
     NotEq,
     Less,
@@ -22,13 +33,22 @@ pub enum TokenKind {
     Comma,
     // keywords
     Import,
+    Let,
+    Const,
     Fn,
+    Struct,
     If,
     Elif,
     Else,
     Then,
+    Match,
+    Case,
     While,
+    For,
+    In,
     Do,
+    Break,
+    Continue,
     Return,
     End,
     And,
@@ -37,16 +57,23 @@ pub enum TokenKind {
     // literals
     Ident(String),
     String(String),
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    /// An int literal which is too large to fit in 64 bits.
+    BigInt(BigInt),
+    Float(f64),
+    /// A number with a `d` suffix, e.g. `19.99d`, holding the number
+    /// without the suffix.
+    Decimal(String),
     Bool(bool),
+    None,
+    DocComment(String),
     // other
     Eof,
     Seperator,
     Unknown,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
@@ -60,3 +87,72 @@ impl Token {
         }
     }
 }
+
+impl std::fmt::Display for TokenKind {
+    /// Formats the token kind the way it would be written in source code,
+    /// for use in error messages.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Mult => write!(f, "'*'"),
+            TokenKind::Div => write!(f, "'/'"),
+            TokenKind::Plus => write!(f, "'+'"),
+            TokenKind::Minus => write!(f, "'-'"),
+            TokenKind::Mod => write!(f, "'%'"),
+            TokenKind::Lparen => write!(f, "'('"),
+            TokenKind::Rparen => write!(f, "')'"),
+            TokenKind::LBracket => write!(f, "'['"),
+            TokenKind::RBracket => write!(f, "']'"),
+            TokenKind::LBrace => write!(f, "'{{'"),
+            TokenKind::RBrace => write!(f, "'}}'"),
+            TokenKind::Colon => write!(f, "':'"),
+            TokenKind::Dot => write!(f, "'.'"),
+            TokenKind::Arrow => write!(f, "'->'"),
+            TokenKind::Assign => write!(f, "'='"),
+            TokenKind::PlusAssign => write!(f, "'+='"),
+            TokenKind::MinusAssign => write!(f, "'-='"),
+            TokenKind::MultAssign => write!(f, "'*='"),
+            TokenKind::DivAssign => write!(f, "'/='"),
+            TokenKind::ModAssign => write!(f, "'%='"),
+            TokenKind::Eq => write!(f, "'=='"),
+            TokenKind::NotEq => write!(f, "'!='"),
+            TokenKind::Less => write!(f, "'<'"),
+            TokenKind::LessEq => write!(f, "'<='"),
+            TokenKind::More => write!(f, "'>'"),
+            TokenKind::MoreEq => write!(f, "'>='"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::Import => write!(f, "'import'"),
+            TokenKind::Let => write!(f, "'let'"),
+            TokenKind::Const => write!(f, "'const'"),
+            TokenKind::Fn => write!(f, "'fn'"),
+            TokenKind::Struct => write!(f, "'struct'"),
+            TokenKind::If => write!(f, "'if'"),
+            TokenKind::Elif => write!(f, "'elif'"),
+            TokenKind::Else => write!(f, "'else'"),
+            TokenKind::Then => write!(f, "'then'"),
+            TokenKind::Match => write!(f, "'match'"),
+            TokenKind::Case => write!(f, "'case'"),
+            TokenKind::While => write!(f, "'while'"),
+            TokenKind::For => write!(f, "'for'"),
+            TokenKind::In => write!(f, "'in'"),
+            TokenKind::Do => write!(f, "'do'"),
+            TokenKind::Break => write!(f, "'break'"),
+            TokenKind::Continue => write!(f, "'continue'"),
+            TokenKind::Return => write!(f, "'return'"),
+            TokenKind::End => write!(f, "'end'"),
+            TokenKind::And => write!(f, "'and'"),
+            TokenKind::Or => write!(f, "'or'"),
+            TokenKind::Not => write!(f, "'not'"),
+            TokenKind::Ident(name) => write!(f, "identifier '{}'", name),
+            TokenKind::String(_) => write!(f, "string"),
+            TokenKind::Int(_) | TokenKind::BigInt(_) => write!(f, "integer"),
+            TokenKind::Float(_) => write!(f, "float"),
+            TokenKind::Decimal(_) => write!(f, "decimal"),
+            TokenKind::Bool(value) => write!(f, "'{}'", value),
+            TokenKind::None => write!(f, "'none'"),
+            TokenKind::DocComment(_) => write!(f, "doc comment"),
+            TokenKind::Eof => write!(f, "end of file"),
+            TokenKind::Seperator => write!(f, "newline or ';'"),
+            TokenKind::Unknown => write!(f, "unknown token"),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::lint::{Rule, Severity};
+
+/// The name of the project file lint settings are read from.
+pub const CONFIG_FILE: &str = "sap.toml";
+
+/// Lint settings, read from the `[lint]` section of a project file:
+///
+/// ```toml
+/// [lint]
+/// empty-block = "off"
+/// bool-comparison = "error"
+/// max-if-depth = 4
+/// ```
+///
+/// Each rule is set to `"off"`, `"warning"` or `"error"`, and rules which
+/// aren't mentioned are warnings.
+#[derive(Debug, Clone)]
+pub struct Config {
+    severities: HashMap<Rule, Severity>,
+    /// The number of `if` statements which can be nested inside each other
+    /// before `deep-nesting` is reported.
+    pub max_if_depth: usize,
+    /// The number of lines a function can span before `long-function` is
+    /// reported.
+    pub max_function_lines: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            severities: HashMap::new(),
+            max_if_depth: 3,
+            max_function_lines: 50,
+        }
+    }
+}
+
+impl Config {
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.severities
+            .get(&rule)
+            .copied()
+            .unwrap_or(Severity::Warning)
+    }
+
+    pub fn set_severity(&mut self, rule: Rule, severity: Severity) {
+        self.severities.insert(rule, severity);
+    }
+
+    /// Parses the contents of a project file. Sections other than `[lint]`
+    /// are ignored.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut in_lint_section = false;
+        for (i, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                in_lint_section = section.trim() == "lint";
+                continue;
+            }
+            if !in_lint_section {
+                continue;
+            }
+            config
+                .parse_setting(line)
+                .map_err(|message| format!("line {}: {}", i + 1, message))?;
+        }
+        Ok(config)
+    }
+
+    fn parse_setting(&mut self, line: &str) -> Result<(), String> {
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("expected 'name = value', found '{}'", line));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let limit = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("'{}' must be a whole number, not {}", key, value))
+        };
+        match key {
+            "max-if-depth" => self.max_if_depth = limit()?,
+            "max-function-lines" => self.max_function_lines = limit()?,
+            _ => {
+                let rule =
+                    Rule::from_name(key).ok_or_else(|| format!("unknown lint rule '{}'", key))?;
+                let name = value.trim_matches('"');
+                let severity = Severity::from_name(name).ok_or_else(|| {
+                    format!(
+                        "the severity of '{}' must be \"off\", \"warning\" or \"error\", not {}",
+                        key, value
+                    )
+                })?;
+                self.set_severity(rule, severity);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the settings from the project file at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let input = std::fs::read_to_string(path)
+            .map_err(|error| format!("could not read '{}': {}", path.display(), error))?;
+        Self::parse(&input).map_err(|message| format!("{}: {}", path.display(), message))
+    }
+}
+
+/// Finds the project file for a source file, which is the nearest
+/// `sap.toml` in the file's directory or one of its parents.
+pub fn find_config(file: &Path) -> Option<PathBuf> {
+    let file = file.canonicalize().ok()?;
+    file.ancestors()
+        .skip(1)
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
@@ -0,0 +1,152 @@
+//! Lints, which point out code that is likely to be a mistake or hard to
+//! read, but which isn't an error.
+//!
+//! Each lint comes from a named rule. Rules can be turned off or made into
+//! errors by a project file (see `Config`), and a `// sap:allow(rule)`
+//! comment stops a rule from being reported on the comment's line, or on the
+//! line after it if the comment is on a line of its own.
+
+use crate::{
+    lexer::{Lexer, Span, Suppression},
+    parser::ast::Node,
+};
+
+pub mod config;
+pub use config::{find_config, Config};
+mod rules;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// `if` statements nested inside too many other `if` statements.
+    DeepNesting,
+    /// Comparisons to `true` or `false` with `==` or `!=`.
+    BoolComparison,
+    /// `while` loops whose condition doesn't depend on any variables.
+    ConstantCondition,
+    /// Branches, loops, functions and `match` cases with nothing in them.
+    EmptyBlock,
+    /// Functions which span too many lines.
+    LongFunction,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::DeepNesting,
+        Rule::BoolComparison,
+        Rule::ConstantCondition,
+        Rule::EmptyBlock,
+        Rule::LongFunction,
+    ];
+
+    /// The name used to refer to the rule in project files and
+    /// `sap:allow` comments.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::DeepNesting => "deep-nesting",
+            Rule::BoolComparison => "bool-comparison",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::EmptyBlock => "empty-block",
+            Rule::LongFunction => "long-function",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Severity::Off),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+/// Checks a program against every rule which isn't turned off, returning the
+/// lints which aren't suppressed, in the order they appear in `src`.
+pub fn lint(program: &Node, src: &str, config: &Config) -> Vec<Lint> {
+    let lines = Lines::new(src);
+    let mut lints = rules::check(program, config, &lines);
+    lints.retain(|lint| lint.severity != Severity::Off);
+
+    let mut lexer = Lexer::from_str(src);
+    lexer.by_ref().for_each(drop);
+    let suppressions = lexer.suppressions();
+    lints.retain(|lint| {
+        let line = lines.line_of(lint.span.start);
+        !suppressions
+            .iter()
+            .any(|suppression| lines.suppresses(suppression, lint.rule, line))
+    });
+    lints.sort_by_key(|lint| lint.span.start);
+    lints
+}
+
+/// The positions of the lines of a source file, for rules and suppressions
+/// which work in lines.
+struct Lines {
+    /// The index of the first character of each line.
+    starts: Vec<usize>,
+    chars: Vec<char>,
+}
+
+impl Lines {
+    fn new(src: &str) -> Self {
+        let chars: Vec<char> = src.chars().collect();
+        let mut starts = vec![0];
+        starts.extend(
+            chars
+                .iter()
+                .enumerate()
+                .filter(|(_, &c)| c == '\n')
+                .map(|(i, _)| i + 1),
+        );
+        Self { starts, chars }
+    }
+
+    /// Returns the (zero based) line the character at `index` is on.
+    fn line_of(&self, index: usize) -> usize {
+        self.starts.partition_point(|&start| start <= index) - 1
+    }
+
+    /// Returns the number of lines `span` covers.
+    fn count(&self, span: &Span) -> usize {
+        self.line_of(span.end.saturating_sub(1).max(span.start)) - self.line_of(span.start) + 1
+    }
+
+    fn suppresses(&self, suppression: &Suppression, rule: Rule, line: usize) -> bool {
+        if !suppression.rules.iter().any(|name| name == rule.name()) {
+            return false;
+        }
+        let comment_line = self.line_of(suppression.span.start);
+        let own_line = self.chars[self.starts[comment_line]..suppression.span.start]
+            .iter()
+            .all(|c| c.is_whitespace());
+        line == comment_line || (own_line && line == comment_line + 1)
+    }
+}
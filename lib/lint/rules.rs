@@ -0,0 +1,352 @@
+use crate::{
+    core::Object,
+    lexer::Span,
+    lint::{Config, Lines, Lint, Rule},
+    parser::ast::*,
+};
+
+pub(super) fn check(program: &Node, config: &Config, lines: &Lines) -> Vec<Lint> {
+    let mut linter = Linter {
+        config,
+        lines,
+        lints: Vec::new(),
+        if_depth: 0,
+    };
+    linter.visit(program);
+    linter.lints
+}
+
+fn is_empty(body: &Node) -> bool {
+    matches!(body, Node::Block { statements, .. } if statements.is_empty())
+}
+
+/// Whether an expression only involves literals, so always has the same
+/// value.
+fn is_constant(node: &Node) -> bool {
+    match node {
+        Node::Literal { .. } => true,
+        Node::UnaryOp { child, .. } => is_constant(child),
+        Node::BinaryOp { lhs, rhs, .. } => is_constant(lhs) && is_constant(rhs),
+        _ => false,
+    }
+}
+
+/// Whether a loop body contains a `break` or `return` which leaves the loop.
+/// Those in nested loops and functions don't count.
+fn leaves_loop(node: &Node) -> bool {
+    match node {
+        Node::Break { .. } | Node::Return { .. } => true,
+        Node::Block { statements, .. } => statements.iter().any(leaves_loop),
+        Node::Selection {
+            if_conditionals,
+            else_conditional,
+            ..
+        } => {
+            if_conditionals
+                .iter()
+                .any(|conditional| leaves_loop(&conditional.body))
+                || else_conditional.as_deref().is_some_and(leaves_loop)
+        }
+        Node::Match { cases, .. } => cases.iter().any(|case| leaves_loop(&case.body)),
+        _ => false,
+    }
+}
+
+struct Linter<'a> {
+    config: &'a Config,
+    lines: &'a Lines,
+    lints: Vec<Lint>,
+    /// The number of `if` statements enclosing the node being visited,
+    /// within the current function.
+    if_depth: usize,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: Rule, message: &str, span: &Span) {
+        self.lints.push(Lint {
+            rule,
+            severity: self.config.severity(rule),
+            message: message.to_string(),
+            span: span.clone(),
+        });
+    }
+
+    fn visit_all(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.visit(node);
+        }
+    }
+
+    fn visit(&mut self, node: &Node) {
+        match node {
+            Node::Program { statements, .. } | Node::Block { statements, .. } => {
+                self.visit_all(statements)
+            }
+            Node::FunctionDecl {
+                name,
+                params,
+                body,
+                span,
+                ..
+            } => self.function(Some(name), params, body, span),
+            Node::Lambda {
+                params, body, span, ..
+            } => self.function(None, params, body, span),
+            Node::StructDecl { name, methods, .. } => {
+                for method in methods {
+                    if let Node::FunctionDecl {
+                        name: method_name,
+                        params,
+                        body,
+                        span,
+                        ..
+                    } = method
+                    {
+                        let name = format!("{}.{}", name, method_name);
+                        self.function(Some(&name), params, body, span);
+                    }
+                }
+            }
+            Node::Selection {
+                if_conditionals,
+                else_conditional,
+                span,
+            } => self.selection(if_conditionals, else_conditional.as_deref(), span),
+            Node::While { conditional, span } => {
+                self.while_condition(&conditional.condition, &conditional.body, span);
+                if is_empty(&conditional.body) {
+                    self.report(
+                        Rule::EmptyBlock,
+                        "The body of this 'while' loop is empty",
+                        span,
+                    );
+                }
+                self.visit(&conditional.condition);
+                self.visit(&conditional.body);
+            }
+            Node::For {
+                iterable,
+                body,
+                span,
+                ..
+            } => {
+                if is_empty(body) {
+                    self.report(
+                        Rule::EmptyBlock,
+                        "The body of this 'for' loop is empty",
+                        span,
+                    );
+                }
+                self.visit(iterable);
+                self.visit(body);
+            }
+            Node::Match { subject, cases, .. } => {
+                self.visit(subject);
+                for case in cases {
+                    if is_empty(&case.body) {
+                        self.report(
+                            Rule::EmptyBlock,
+                            "The body of this case is empty",
+                            case.pattern.span(),
+                        );
+                    }
+                    if let Some(guard) = &case.guard {
+                        self.visit(guard);
+                    }
+                    self.visit(&case.body);
+                }
+            }
+            Node::BinaryOp { op, lhs, rhs, span } => {
+                self.bool_comparison(op, lhs, rhs, span);
+                self.visit(lhs);
+                self.visit(rhs);
+            }
+            Node::VariableDecl { value, .. } | Node::Assign { value, .. } => self.visit(value),
+            Node::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.visit(value);
+                }
+            }
+            Node::FunctionCall {
+                function,
+                args,
+                kwargs,
+                ..
+            } => {
+                self.visit(function);
+                self.visit_all(args);
+                for (_, value) in kwargs {
+                    self.visit(value);
+                }
+            }
+            Node::List { elements, .. } => self.visit_all(elements),
+            Node::Dict { entries, .. } => {
+                for (key, value) in entries {
+                    self.visit(key);
+                    self.visit(value);
+                }
+            }
+            Node::Index { object, index, .. } => {
+                self.visit(object);
+                self.visit(index);
+            }
+            Node::Slice {
+                object,
+                start,
+                stop,
+                step,
+                ..
+            } => {
+                self.visit(object);
+                for bound in [start, stop, step].into_iter().flatten() {
+                    self.visit(bound);
+                }
+            }
+            Node::Field { object, .. } => self.visit(object),
+            Node::FieldAssign { object, value, .. } => {
+                self.visit(object);
+                self.visit(value);
+            }
+            Node::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.visit(object);
+                self.visit(index);
+                self.visit(value);
+            }
+            Node::UnaryOp { child, .. } => self.visit(child),
+            Node::Import { .. }
+            | Node::Break { .. }
+            | Node::Continue { .. }
+            | Node::Identifier { .. }
+            | Node::Literal { .. } => {}
+        }
+    }
+
+    /// Checks a function, which is anonymous if it has no `name`.
+    fn function(&mut self, name: Option<&str>, params: &[Param], body: &Node, span: &Span) {
+        let name = match name {
+            Some(name) => format!("'{}'", name),
+            None => "this function".to_string(),
+        };
+        let lines = self.lines.count(span);
+        if lines > self.config.max_function_lines {
+            let message = format!(
+                "The body of {} is {} lines long, more than the limit of {}",
+                name, lines, self.config.max_function_lines
+            );
+            self.report(Rule::LongFunction, &message, span);
+        }
+        if is_empty(body) {
+            self.report(
+                Rule::EmptyBlock,
+                &format!("The body of {} is empty", name),
+                span,
+            );
+        }
+        for default in params.iter().filter_map(|param| param.default.as_ref()) {
+            self.visit(default);
+        }
+        // Nesting is counted separately for each function.
+        let if_depth = std::mem::replace(&mut self.if_depth, 0);
+        self.visit(body);
+        self.if_depth = if_depth;
+    }
+
+    fn selection(
+        &mut self,
+        if_conditionals: &[Conditional],
+        else_conditional: Option<&Node>,
+        span: &Span,
+    ) {
+        self.if_depth += 1;
+        // Only the outermost `if` which is too deep is reported.
+        if self.if_depth == self.config.max_if_depth + 1 {
+            self.report(
+                Rule::DeepNesting,
+                &format!(
+                    "'if' statements are nested {} deep here, more than the limit of {}",
+                    self.if_depth, self.config.max_if_depth
+                ),
+                span,
+            );
+        }
+        for (i, conditional) in if_conditionals.iter().enumerate() {
+            if is_empty(&conditional.body) {
+                let branch = if i == 0 { "if" } else { "elif" };
+                self.report(
+                    Rule::EmptyBlock,
+                    &format!("The '{}' branch of this 'if' is empty", branch),
+                    span,
+                );
+            }
+            self.visit(&conditional.condition);
+            self.visit(&conditional.body);
+        }
+        if let Some(body) = else_conditional {
+            if is_empty(body) {
+                self.report(
+                    Rule::EmptyBlock,
+                    "The 'else' branch of this 'if' is empty",
+                    span,
+                );
+            }
+            self.visit(body);
+        }
+        self.if_depth -= 1;
+    }
+
+    fn while_condition(&mut self, condition: &Node, body: &Node, span: &Span) {
+        let message = match condition {
+            // `while true do ... end` is fine as long as something ends it.
+            Node::Literal { value, .. } if value.is_truthy() && leaves_loop(body) => return,
+            Node::Literal { value, .. } if value.is_truthy() => {
+                "The condition of this 'while' loop is always true, and nothing in it leaves the loop, so it never ends"
+            }
+            Node::Literal { .. } => {
+                "The condition of this 'while' loop is always false, so its body never runs"
+            }
+            _ if is_constant(condition) => "The condition of this 'while' loop is constant",
+            _ => return,
+        };
+        self.report(Rule::ConstantCondition, message, span);
+    }
+
+    fn bool_comparison(&mut self, op: &Operator, lhs: &Node, rhs: &Node, span: &Span) {
+        let negated = match op {
+            Operator::Eq => false,
+            Operator::NotEq => true,
+            _ => return,
+        };
+        let value = match (lhs, rhs) {
+            (
+                Node::Literal {
+                    value: Object::Bool(value),
+                    ..
+                },
+                _,
+            )
+            | (
+                _,
+                Node::Literal {
+                    value: Object::Bool(value),
+                    ..
+                },
+            ) => value.value,
+            _ => return,
+        };
+        let suggestion = if value != negated {
+            "use the value itself"
+        } else {
+            "use 'not' instead"
+        };
+        self.report(
+            Rule::BoolComparison,
+            &format!("Comparing to '{}' is unnecessary, {}", value, suggestion),
+            span,
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! An optimisation pass over the syntax tree, which evaluates the parts of a
+//! program that don't depend on anything only known when it runs.
+//!
+//! - Operators applied to literals are folded into a single literal, using
+//!   the same semantics as the interpreter (`core::ops`), so `60 * 60 * 24`
+//!   becomes `86400`. Operations which would raise an error are left alone,
+//!   so the error is still raised, at the same place, when the program runs.
+//!   Integer arithmetic which overflows 64 bits and decimal division are
+//!   also left alone, as their results depend on how the interpreter is set
+//!   up. Repeating a string or list is only folded when the result is
+//!   short, so that programs don't grow (or run out of memory) before they
+//!   run.
+//! - `if` and `elif` branches whose condition is always false are removed,
+//!   as is everything after a branch whose condition is always true.
+//! - `while` loops whose condition is always false are removed.
+//!
+//! Conditions are folded before the branches and loops they guard, whose
+//! bodies are only folded if they can run.
+//!
+//! Folded nodes keep the span of the expression they replace, so errors and
+//! warnings about the optimised program still point at the right code.
+
+use std::rc::Rc;
+
+use crate::{
+    core::{ops, Bool, Int, Object, Overflow},
+    lexer::Span,
+    parser::ast::*,
+};
+
+/// The arithmetic settings used for folding. Integer overflow is an error,
+/// so it isn't folded. (Decimal division is never folded.)
+fn folding() -> ops::Arithmetic {
+    ops::Arithmetic {
+        overflow: Overflow::Error,
+        ..Default::default()
+    }
+}
+
+/// The longest string (in bytes) or list which repetition is folded into.
+const MAX_FOLDED_LEN: usize = 1024;
+
+/// Optimises a program in place. Functions whose body is shared with another
+/// tree (e.g. an interpreter which has already run the program) are left
+/// as they are.
+pub fn optimize(program: &mut Node) {
+    fold(program);
+}
+
+/// Returns the value of a node if it is a literal.
+fn literal(node: &Node) -> Option<&Object> {
+    match node {
+        Node::Literal { value, .. } => Some(value),
+        _ => None,
+    }
+}
+
+fn fold_all(nodes: &mut [Node]) {
+    for node in nodes {
+        fold(node);
+    }
+}
+
+fn fold_function(params: &mut Rc<[Param]>, body: &mut Rc<Node>) {
+    if let Some(params) = Rc::get_mut(params) {
+        for default in params.iter_mut().filter_map(|param| param.default.as_mut()) {
+            fold(default);
+        }
+    }
+    if let Some(body) = Rc::get_mut(body) {
+        fold(body);
+    }
+}
+
+/// Folds the statements of a program or block, removing those which can
+/// never run.
+fn fold_statements(statements: &mut Vec<Node>) {
+    statements.retain_mut(|statement| {
+        match statement {
+            Node::While { conditional, .. } => {
+                fold(&mut conditional.condition);
+                if literal(&conditional.condition).is_some_and(|value| !value.is_truthy()) {
+                    return false;
+                }
+            }
+            Node::Selection { .. } => {
+                prune_selection(statement);
+                if matches!(statement, Node::Block { statements, .. } if statements.is_empty()) {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+        fold(statement);
+        true
+    });
+}
+
+/// Folds the conditions of an `if` statement and removes the branches which
+/// can never run, without folding any of their bodies. An `if`
+/// left with a single branch which always runs is replaced by that branch's
+/// body, and one left with no branches by an empty block.
+fn prune_selection(node: &mut Node) {
+    let Node::Selection {
+        if_conditionals,
+        else_conditional,
+        span,
+    } = node
+    else {
+        return;
+    };
+    let mut kept = Vec::new();
+    for mut conditional in std::mem::take(if_conditionals) {
+        fold(&mut conditional.condition);
+        match literal(&conditional.condition).map(Object::is_truthy) {
+            Some(false) => {}
+            // Later branches can't be reached, so this one acts as the
+            // `else` branch.
+            Some(true) => {
+                *else_conditional = Some(conditional.body);
+                break;
+            }
+            None => kept.push(conditional),
+        }
+    }
+    *if_conditionals = kept;
+
+    if if_conditionals.is_empty() {
+        let body = match else_conditional.take() {
+            Some(body) => *body,
+            None => Node::Block {
+                statements: Vec::new(),
+                span: span.clone(),
+            },
+        };
+        *node = body;
+    }
+}
+
+fn fold(node: &mut Node) {
+    match node {
+        Node::Program { statements, .. } | Node::Block { statements, .. } => {
+            fold_statements(statements)
+        }
+        Node::VariableDecl { value, .. } | Node::Assign { value, .. } => fold(value),
+        Node::Field { object, .. } => fold(object),
+        Node::Return { value, .. } => {
+            if let Some(value) = value {
+                fold(value);
+            }
+        }
+        Node::FunctionDecl { params, body, .. } | Node::Lambda { params, body, .. } => {
+            fold_function(params, body)
+        }
+        Node::StructDecl { methods, .. } => fold_all(methods),
+        Node::Selection {
+            if_conditionals,
+            else_conditional,
+            ..
+        } => {
+            for conditional in if_conditionals {
+                fold(&mut conditional.condition);
+                fold(&mut conditional.body);
+            }
+            if let Some(body) = else_conditional {
+                fold(body);
+            }
+        }
+        Node::While { conditional, .. } => {
+            fold(&mut conditional.condition);
+            fold(&mut conditional.body);
+        }
+        Node::For { iterable, body, .. } => {
+            fold(iterable);
+            fold(body);
+        }
+        Node::Match { subject, cases, .. } => {
+            fold(subject);
+            for case in cases {
+                if let Some(guard) = &mut case.guard {
+                    fold(guard);
+                }
+                fold(&mut case.body);
+            }
+        }
+        Node::FunctionCall {
+            function,
+            args,
+            kwargs,
+            ..
+        } => {
+            fold(function);
+            fold_all(args);
+            for (_, value) in kwargs {
+                fold(value);
+            }
+        }
+        Node::List { elements, .. } => fold_all(elements),
+        Node::Dict { entries, .. } => {
+            for (key, value) in entries {
+                fold(key);
+                fold(value);
+            }
+        }
+        Node::Index { object, index, .. } => {
+            fold(object);
+            fold(index);
+        }
+        Node::Slice {
+            object,
+            start,
+            stop,
+            step,
+            ..
+        } => {
+            fold(object);
+            for bound in [start, stop, step].into_iter().flatten() {
+                fold(bound);
+            }
+        }
+        Node::FieldAssign { object, value, .. } => {
+            fold(object);
+            fold(value);
+        }
+        Node::IndexAssign {
+            object,
+            index,
+            value,
+            ..
+        } => {
+            fold(object);
+            fold(index);
+            fold(value);
+        }
+        Node::UnaryOp { op, child, span } => {
+            fold(child);
+            let value = literal(child).and_then(|child| ops::unary_op(op, child, &folding()).ok());
+            if let Some(value) = value {
+                *node = folded(value, span);
+            }
+        }
+        Node::BinaryOp { op, lhs, rhs, span } => {
+            fold(lhs);
+            fold(rhs);
+            if let Some(value) = fold_binary(op, lhs, rhs) {
+                *node = folded(value, span);
+            }
+        }
+        Node::Import { .. }
+        | Node::Break { .. }
+        | Node::Continue { .. }
+        | Node::Identifier { .. }
+        | Node::Literal { .. } => {}
+    }
+}
+
+fn folded(value: Object, span: &Span) -> Node {
+    Node::Literal {
+        value,
+        span: span.clone(),
+    }
+}
+
+/// Evaluates a binary operation if its result is known before the program
+/// runs, returning `None` if it isn't.
+fn fold_binary(op: &Operator, lhs: &Node, rhs: &Node) -> Option<Object> {
+    let left = literal(lhs)?;
+    // `and` and `or` short circuit, so they only need their left operand to
+    // be known when it decides the result.
+    match op {
+        Operator::And if !left.is_truthy() => return Some(Bool { value: false }.into()),
+        Operator::Or if left.is_truthy() => return Some(Bool { value: true }.into()),
+        _ => {}
+    }
+    let right = literal(rhs)?;
+    if matches!(op, Operator::Mul) && is_long_repetition(left, right) {
+        return None;
+    }
+    if matches!(op, Operator::Div)
+        && (matches!(left, Object::Decimal(_)) || matches!(right, Object::Decimal(_)))
+    {
+        return None;
+    }
+    ops::binary_op(op, left, right, &folding()).ok()
+}
+
+/// Whether multiplying `left` by `right` repeats a string or list into one
+/// longer than `MAX_FOLDED_LEN`.
+fn is_long_repetition(left: &Object, right: &Object) -> bool {
+    let (len, times) = match (left, right) {
+        (Object::Str(s), Object::Int(n)) | (Object::Int(n), Object::Str(s)) => (s.value.len(), n),
+        (Object::List(list), Object::Int(n)) | (Object::Int(n), Object::List(list)) => {
+            (list.len(), n)
+        }
+        _ => return false,
+    };
+    let times = match times {
+        Int::Small(times) => (*times).max(0) as u128,
+        Int::Big(_) => u128::MAX,
+    };
+    len > 0 && len as u128 * times.min(u64::MAX as u128) > MAX_FOLDED_LEN as u128
+}
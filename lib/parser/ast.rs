@@ -1,57 +1,366 @@
+use std::rc::Rc;
+
 use crate::core::Object;
+use crate::lexer::Span;
 
+#[derive(Debug)]
 pub enum Operator {
     Add,
     Sub,
     Mul,
     Div,
     Mod,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    More,
+    MoreEq,
+    And,
+    Or,
+    Not,
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Mod => "%",
+            Operator::Eq => "==",
+            Operator::NotEq => "!=",
+            Operator::Less => "<",
+            Operator::LessEq => "<=",
+            Operator::More => ">",
+            Operator::MoreEq => ">=",
+            Operator::And => "and",
+            Operator::Or => "or",
+            Operator::Not => "not",
+        };
+        write!(f, "{}", symbol)
+    }
 }
 
+#[derive(Debug)]
 pub struct Conditional {
     pub condition: Box<Node>,
     pub body: Box<Node>,
 }
 
+/// A type annotation, e.g. `int`, `list[str]` or `fn(int) -> bool`. Only the
+/// type checker looks at annotations; they have no effect when a program is
+/// run.
+#[derive(Debug)]
+pub enum TypeAnnotation {
+    /// A type's name, followed by its type arguments (if any) in brackets.
+    Named {
+        name: String,
+        args: Vec<TypeAnnotation>,
+        span: Span,
+    },
+    /// `fn(params) -> ret`, where `ret` defaults to `none` when omitted.
+    Function {
+        params: Vec<TypeAnnotation>,
+        ret: Option<Box<TypeAnnotation>>,
+        span: Span,
+    },
+}
+
+impl TypeAnnotation {
+    pub fn span(&self) -> &Span {
+        match self {
+            TypeAnnotation::Named { span, .. } | TypeAnnotation::Function { span, .. } => span,
+        }
+    }
+}
+
+impl std::fmt::Display for TypeAnnotation {
+    /// Formats the annotation the way it would be written in source code.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |types: &[TypeAnnotation]| {
+            types
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            TypeAnnotation::Named { name, args, .. } if args.is_empty() => write!(f, "{}", name),
+            TypeAnnotation::Named { name, args, .. } => write!(f, "{}[{}]", name, join(args)),
+            TypeAnnotation::Function { params, ret, .. } => {
+                write!(f, "fn({})", join(params))?;
+                match ret {
+                    Some(ret) => write!(f, " -> {}", ret),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// A parameter in a function's signature.
+#[derive(Debug)]
+pub struct Param {
+    pub name: String,
+    /// The span of the parameter's name.
+    pub span: Span,
+    /// The parameter's type annotation. For a `*rest` parameter this is the
+    /// type of each extra argument.
+    pub ty: Option<TypeAnnotation>,
+    /// The expression evaluated for the argument when the caller omits it.
+    pub default: Option<Node>,
+    /// Whether this is a trailing `*rest` parameter, which collects any
+    /// extra positional arguments into a list.
+    pub variadic: bool,
+}
+
+/// A pattern which a value is compared against by a `match` statement.
+#[derive(Debug)]
+pub enum Pattern {
+    /// `_`, which matches any value.
+    Wildcard { span: Span },
+    /// A name, which matches any value and binds it to the name.
+    Binding { name: String, span: Span },
+    /// Matches values equal to a literal, e.g. `1`, `-2.5` or `"a"`.
+    Literal { value: Object, span: Span },
+    /// `[first, *rest]`, which matches lists whose elements match the element
+    /// patterns in order.
+    List { elements: Vec<Pattern>, span: Span },
+    /// `*rest` (or `*_`) in a list pattern, which matches any number of
+    /// elements and binds them to the name as a list.
+    Rest { name: Option<String>, span: Span },
+    /// `Point(x, y=0)`, which matches instances of the struct type `name`
+    /// whose fields match, taken in declaration order for `args` and by name
+    /// for `kwargs`.
+    Struct {
+        name: String,
+        args: Vec<Pattern>,
+        kwargs: Vec<(String, Pattern)>,
+        span: Span,
+    },
+}
+
+impl Pattern {
+    pub fn span(&self) -> &Span {
+        match self {
+            Pattern::Wildcard { span }
+            | Pattern::Binding { span, .. }
+            | Pattern::Literal { span, .. }
+            | Pattern::List { span, .. }
+            | Pattern::Rest { span, .. }
+            | Pattern::Struct { span, .. } => span,
+        }
+    }
+
+    /// Whether the pattern matches every value.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard { .. } | Pattern::Binding { .. })
+    }
+}
+
+/// `case pattern if guard then body` in a `match` statement.
+#[derive(Debug)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    pub guard: Option<Node>,
+    pub body: Node,
+}
+
+#[derive(Debug)]
 pub enum Node {
     Program {
         statements: Vec<Node>,
+        span: Span,
+    },
+    Block {
+        statements: Vec<Node>,
+        span: Span,
     },
     Import {
         path: String,
+        span: Span,
     },
+    /// `let name = value` or `const name = value`, optionally with a type
+    /// annotation (`let name: ty = value`).
     VariableDecl {
         name: String,
+        ty: Option<TypeAnnotation>,
         value: Box<Node>,
+        constant: bool,
+        doc: Option<String>,
+        span: Span,
     },
-    FunctionCall {
+    /// `name = value`, or a compound assignment such as `name += value` when
+    /// `op` is set.
+    Assign {
         name: String,
+        op: Option<Operator>,
+        value: Box<Node>,
+        span: Span,
+    },
+    /// Calls the function `function` evaluates to, with positional `args`
+    /// followed by keyword arguments (`name=value`).
+    FunctionCall {
+        function: Box<Node>,
         args: Vec<Node>,
+        kwargs: Vec<(String, Node)>,
+        span: Span,
     },
     FunctionDecl {
         name: String,
-        args: Vec<String>,
-        body: Box<Node>,
+        params: Rc<[Param]>,
+        return_type: Option<TypeAnnotation>,
+        body: Rc<Node>,
+        doc: Option<String>,
+        span: Span,
+    },
+    /// `struct Name field, ... end`, where each of `methods` is a
+    /// `FunctionDecl`.
+    StructDecl {
+        name: String,
+        fields: Vec<String>,
+        methods: Vec<Node>,
+        doc: Option<String>,
+        span: Span,
+    },
+    /// An anonymous function, `fn(args) ... end`.
+    Lambda {
+        params: Rc<[Param]>,
+        return_type: Option<TypeAnnotation>,
+        body: Rc<Node>,
+        span: Span,
+    },
+    Return {
+        value: Option<Box<Node>>,
+        span: Span,
     },
     Selection {
         if_conditionals: Vec<Conditional>,
         else_conditional: Option<Box<Node>>,
+        span: Span,
     },
     While {
         conditional: Conditional,
+        span: Span,
+    },
+    /// `match subject case ... end`, which runs the body of the first case
+    /// whose pattern matches `subject`.
+    Match {
+        subject: Box<Node>,
+        cases: Vec<MatchCase>,
+        span: Span,
+    },
+    /// `for variable in iterable do ... end`
+    For {
+        variable: String,
+        variable_span: Span,
+        iterable: Box<Node>,
+        body: Box<Node>,
+        span: Span,
+    },
+    Break {
+        span: Span,
+    },
+    Continue {
+        span: Span,
     },
     List {
         elements: Vec<Node>,
+        span: Span,
+    },
+    Dict {
+        entries: Vec<(Node, Node)>,
+        span: Span,
+    },
+    Index {
+        object: Box<Node>,
+        index: Box<Node>,
+        span: Span,
+    },
+    /// `object[start:stop:step]`, where each of the bounds is optional.
+    Slice {
+        object: Box<Node>,
+        start: Option<Box<Node>>,
+        stop: Option<Box<Node>>,
+        step: Option<Box<Node>>,
+        span: Span,
+    },
+    /// `object.name`
+    Field {
+        object: Box<Node>,
+        name: String,
+        span: Span,
+    },
+    /// `object.name = value`, or a compound assignment when `op` is set.
+    FieldAssign {
+        object: Box<Node>,
+        name: String,
+        op: Option<Operator>,
+        value: Box<Node>,
+        span: Span,
+    },
+    IndexAssign {
+        object: Box<Node>,
+        index: Box<Node>,
+        op: Option<Operator>,
+        value: Box<Node>,
+        span: Span,
     },
     UnaryOp {
         op: Operator,
         child: Box<Node>,
+        span: Span,
     },
     BinaryOp {
         op: Operator,
         lhs: Box<Node>,
         rhs: Box<Node>,
+        span: Span,
+    },
+    Identifier {
+        name: String,
+        span: Span,
     },
     // Literals
-    Literal(Object),
+    Literal {
+        value: Object,
+        span: Span,
+    },
+}
+
+impl Node {
+    /// Returns the region of the source code this node was parsed from.
+    pub fn span(&self) -> &Span {
+        match self {
+            Node::Program { span, .. }
+            | Node::Block { span, .. }
+            | Node::Import { span, .. }
+            | Node::VariableDecl { span, .. }
+            | Node::Assign { span, .. }
+            | Node::FunctionCall { span, .. }
+            | Node::FunctionDecl { span, .. }
+            | Node::Lambda { span, .. }
+            | Node::StructDecl { span, .. }
+            | Node::Return { span, .. }
+            | Node::Selection { span, .. }
+            | Node::While { span, .. }
+            | Node::Match { span, .. }
+            | Node::For { span, .. }
+            | Node::Break { span, .. }
+            | Node::Continue { span, .. }
+            | Node::List { span, .. }
+            | Node::Dict { span, .. }
+            | Node::Index { span, .. }
+            | Node::Slice { span, .. }
+            | Node::Field { span, .. }
+            | Node::FieldAssign { span, .. }
+            | Node::IndexAssign { span, .. }
+            | Node::UnaryOp { span, .. }
+            | Node::BinaryOp { span, .. }
+            | Node::Identifier { span, .. }
+            | Node::Literal { span, .. } => span,
+        }
+    }
 }
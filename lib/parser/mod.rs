@@ -1,2 +1,4 @@
 pub mod ast;
+#[allow(clippy::module_inception)]
 pub mod parser;
+pub use parser::{parse, parse_with_warnings, Parser};
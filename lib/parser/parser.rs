@@ -1 +1,1243 @@
+use std::{collections::HashMap, rc::Rc};
 
+use crate::{
+    core::{Bool, Decimal, Float, Int, NoneType, Object, Str},
+    errors::{Error, ErrorType, Warning},
+    lexer::{token::*, Lexer, Span},
+    parser::ast::*,
+};
+
+/// Parses an entire source file into a `Node::Program`.
+pub fn parse(input: &str) -> Result<Node, Error> {
+    Parser::new(Lexer::from_str(input)).parse_program()
+}
+
+/// Parses an entire source file, along with any warnings about the program.
+pub fn parse_with_warnings(input: &str) -> Result<(Node, Vec<Warning>), Error> {
+    let mut parser = Parser::new(Lexer::from_str(input));
+    let program = parser.parse_program()?;
+    Ok((program, parser.warnings))
+}
+
+/// The value of a literal token, or `None` if the token isn't a literal.
+fn literal_value(kind: &TokenKind) -> Option<Object> {
+    match kind {
+        TokenKind::Int(value) => Some(Int::new(*value).into()),
+        TokenKind::BigInt(value) => Some(Int::from_big(value.clone()).into()),
+        TokenKind::Float(value) => Some(Float { value: *value }.into()),
+        TokenKind::Decimal(value) => Decimal::parse(value).map(Object::from),
+        TokenKind::String(value) => Some(
+            Str {
+                value: value.clone(),
+            }
+            .into(),
+        ),
+        TokenKind::Bool(value) => Some(Bool { value: *value }.into()),
+        TokenKind::None => Some(NoneType.into()),
+        _ => None,
+    }
+}
+
+/// Collects the names bound by `pattern`, along with where they're bound.
+fn pattern_bindings<'a>(pattern: &'a Pattern, bindings: &mut Vec<(&'a str, &'a Span)>) {
+    match pattern {
+        Pattern::Binding { name, span }
+        | Pattern::Rest {
+            name: Some(name),
+            span,
+        } => bindings.push((name, span)),
+        Pattern::List { elements, .. } => {
+            for element in elements {
+                pattern_bindings(element, bindings);
+            }
+        }
+        Pattern::Struct { args, kwargs, .. } => {
+            for pattern in args.iter().chain(kwargs.iter().map(|(_, pattern)| pattern)) {
+                pattern_bindings(pattern, bindings);
+            }
+        }
+        Pattern::Wildcard { .. } | Pattern::Literal { .. } | Pattern::Rest { name: None, .. } => {}
+    }
+}
+
+/// Maps an assignment token to the operator it applies before assigning, so
+/// `=` maps to `Some(None)` and `+=` to `Some(Some(Operator::Add))`.
+fn assignment_op(kind: &TokenKind) -> Option<Option<Operator>> {
+    match kind {
+        TokenKind::Assign => Some(None),
+        TokenKind::PlusAssign => Some(Some(Operator::Add)),
+        TokenKind::MinusAssign => Some(Some(Operator::Sub)),
+        TokenKind::MultAssign => Some(Some(Operator::Mul)),
+        TokenKind::DivAssign => Some(Some(Operator::Div)),
+        TokenKind::ModAssign => Some(Some(Operator::Mod)),
+        _ => None,
+    }
+}
+
+/// The parameters, return type annotation and body of a function.
+type ParsedFunction = (Rc<[Param]>, Option<TypeAnnotation>, Node);
+
+pub struct Parser<'source> {
+    lexer: Lexer<'source>,
+    cur_token: Token,
+    /// The end of the most recently consumed token, used to close off the
+    /// span of the node currently being parsed.
+    prev_end: usize,
+    /// How many function declarations the parser is currently inside of,
+    /// used to reject `return` statements outside of a function.
+    function_depth: usize,
+    /// How many loops enclose the current token within the current function.
+    loop_depth: usize,
+    /// The variables declared in the program and in each function the parser
+    /// is currently inside of, mapped to whether they are constant. Used to
+    /// reject assignments to undeclared or constant names.
+    scopes: Vec<HashMap<String, bool>>,
+    warnings: Vec<Warning>,
+}
+
+impl<'source> Parser<'source> {
+    pub fn new(lexer: Lexer<'source>) -> Self {
+        Self {
+            lexer,
+            cur_token: Token::empty(),
+            prev_end: 0,
+            function_depth: 0,
+            loop_depth: 0,
+            scopes: vec![HashMap::new()],
+            warnings: Vec::new(),
+        }
+    }
+
+    fn construct_error<T>(&self, msg: &str, token: Token) -> Result<T, Error> {
+        Err(Error {
+            message: msg.to_string(),
+            error_type: ErrorType::SyntaxError,
+            token,
+        })
+    }
+
+    fn unexpected_token<T>(&self, expected: &str) -> Result<T, Error> {
+        self.construct_error(
+            &format!("Expected {}, found {}", expected, self.cur_token.kind),
+            self.cur_token.clone(),
+        )
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        self.prev_end = self.cur_token.span.end;
+        self.cur_token = self.lexer.get_next_token()?;
+        Ok(())
+    }
+
+    /// Returns true if the token after `cur_token` is of the given kind.
+    fn next_is(&mut self, kind: TokenKind) -> bool {
+        matches!(self.lexer.peek_nth(0), Ok(token) if token.kind == kind)
+    }
+
+    /// Declares a name in the current scope. A constant can't be declared
+    /// again in the scope it was declared in, whether by `let`, a loop
+    /// variable or a pattern, as that would replace its value.
+    fn declare(&mut self, name: &str, constant: bool, span: &Span) -> Result<(), Error> {
+        let Some(scope) = self.scopes.last_mut() else {
+            return Ok(());
+        };
+        if scope.get(name) == Some(&true) {
+            return Err(Error::new(
+                ErrorType::SyntaxError,
+                &format!("Cannot redeclare constant '{}'", name),
+                span.clone(),
+            ));
+        }
+        scope.insert(name.to_string(), constant);
+        Ok(())
+    }
+
+    /// Checks that the variable `name` has been declared, and isn't constant.
+    fn check_assignable(&self, name: &str, span: &Span) -> Result<(), Error> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(false) => Ok(()),
+            Some(true) => Err(Error::new(
+                ErrorType::SyntaxError,
+                &format!("Cannot assign to constant '{}'", name),
+                span.clone(),
+            )),
+            None => Err(Error::new(
+                ErrorType::NameError,
+                &format!(
+                    "Cannot assign to undeclared variable '{}', use 'let {} = ...' to declare it",
+                    name, name
+                ),
+                span.clone(),
+            )),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), Error> {
+        if self.cur_token.kind == kind {
+            self.advance()
+        } else {
+            self.unexpected_token(&kind.to_string())
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, Error> {
+        match &self.cur_token.kind {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Ok(name)
+            }
+            _ => self.unexpected_token("identifier"),
+        }
+    }
+
+    fn skip_separators(&mut self) -> Result<(), Error> {
+        while self.cur_token.kind == TokenKind::Seperator {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    fn at_block_end(&self) -> bool {
+        matches!(
+            self.cur_token.kind,
+            TokenKind::End | TokenKind::Elif | TokenKind::Else | TokenKind::Case | TokenKind::Eof
+        )
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.prev_end)
+    }
+
+    pub fn parse_program(&mut self) -> Result<Node, Error> {
+        // Load the first token
+        self.advance()?;
+        let statements = self.parse_statement_list()?;
+        if self.cur_token.kind != TokenKind::Eof {
+            return self.unexpected_token("end of file");
+        }
+        Ok(Node::Program {
+            statements,
+            span: Span::new(0, self.cur_token.span.end),
+        })
+    }
+
+    fn parse_statement_list(&mut self) -> Result<Vec<Node>, Error> {
+        let mut statements = Vec::new();
+        loop {
+            self.skip_separators()?;
+            if self.at_block_end() {
+                break;
+            }
+            statements.push(self.parse_statement()?);
+            if !self.at_block_end() && self.cur_token.kind != TokenKind::Seperator {
+                return self.unexpected_token("newline or ';'");
+            }
+        }
+        Ok(statements)
+    }
+
+    fn parse_block(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        let statements = self.parse_statement_list()?;
+        Ok(Node::Block {
+            statements,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_statement(&mut self) -> Result<Node, Error> {
+        let is_lambda = self.next_is(TokenKind::Lparen);
+        match &self.cur_token.kind {
+            TokenKind::DocComment(_) => self.parse_documented_statement(),
+            TokenKind::Import => self.parse_import(),
+            TokenKind::Fn if !is_lambda => self.parse_function_decl(None),
+            TokenKind::Struct => self.parse_struct_decl(None),
+            TokenKind::If => self.parse_selection(),
+            TokenKind::While => self.parse_while(),
+            TokenKind::For => self.parse_for(),
+            TokenKind::Match => self.parse_match(),
+            TokenKind::Break | TokenKind::Continue => self.parse_loop_control(),
+            TokenKind::Return => self.parse_return(),
+            TokenKind::Let | TokenKind::Const => self.parse_variable_decl(None),
+            _ => self.parse_expr_statement(),
+        }
+    }
+
+    /// Parses an expression used as a statement, which may be the target of an
+    /// assignment (`x = value`, `xs[i] += value`).
+    fn parse_expr_statement(&mut self) -> Result<Node, Error> {
+        let expr = self.parse_expr()?;
+        let op = match assignment_op(&self.cur_token.kind) {
+            Some(op) => op,
+            None => return Ok(expr),
+        };
+        let start = expr.span().start;
+        match expr {
+            Node::Identifier { name, span } => {
+                self.check_assignable(&name, &span)?;
+                self.advance()?;
+                let value = self.parse_expr()?;
+                Ok(Node::Assign {
+                    name,
+                    op,
+                    value: Box::new(value),
+                    span: self.span_from(start),
+                })
+            }
+            Node::Field { object, name, .. } => {
+                self.advance()?;
+                let value = self.parse_expr()?;
+                Ok(Node::FieldAssign {
+                    object,
+                    name,
+                    op,
+                    value: Box::new(value),
+                    span: self.span_from(start),
+                })
+            }
+            Node::Index { object, index, .. } => {
+                self.advance()?;
+                let value = self.parse_expr()?;
+                Ok(Node::IndexAssign {
+                    object,
+                    index,
+                    op,
+                    value: Box::new(value),
+                    span: self.span_from(start),
+                })
+            }
+            _ => self.construct_error(
+                "Cannot assign to this expression",
+                Token {
+                    kind: TokenKind::Unknown,
+                    span: expr.span().clone(),
+                },
+            ),
+        }
+    }
+
+    /// Collects consecutive doc comment lines and attaches them to the
+    /// declaration which follows.
+    fn parse_documented_statement(&mut self) -> Result<Node, Error> {
+        let doc_token = self.cur_token.clone();
+        let doc = Some(self.parse_doc_comment()?);
+        match self.cur_token.kind {
+            TokenKind::Fn => self.parse_function_decl(doc),
+            TokenKind::Struct => self.parse_struct_decl(doc),
+            TokenKind::Let | TokenKind::Const => self.parse_variable_decl(doc),
+            _ => self.construct_error(
+                "Doc comments must be followed by a function, struct or variable declaration",
+                doc_token,
+            ),
+        }
+    }
+
+    /// Joins consecutive doc comment lines into a single string.
+    fn parse_doc_comment(&mut self) -> Result<String, Error> {
+        let mut lines = Vec::new();
+        while let TokenKind::DocComment(line) = &self.cur_token.kind {
+            lines.push(line.clone());
+            self.advance()?;
+            self.skip_separators()?;
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn parse_import(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::Import)?;
+        let path = self.expect_ident()?;
+        Ok(Node::Import {
+            path,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_variable_decl(&mut self, doc: Option<String>) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        let constant = match self.cur_token.kind {
+            TokenKind::Let => false,
+            TokenKind::Const => true,
+            _ => return self.unexpected_token("'let' or 'const'"),
+        };
+        self.advance()?;
+        let name_start = self.cur_token.span.start;
+        let name = self.expect_ident()?;
+        let name_span = self.span_from(name_start);
+        let ty = self.parse_annotation(TokenKind::Colon)?;
+        self.expect(TokenKind::Assign)?;
+        let value = self.parse_expr()?;
+        self.declare(&name, constant, &name_span)?;
+        Ok(Node::VariableDecl {
+            name,
+            ty,
+            value: Box::new(value),
+            constant,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_function_decl(&mut self, doc: Option<String>) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::Fn)?;
+        let name_start = self.cur_token.span.start;
+        let name = self.expect_ident()?;
+        // Declared before the body is parsed so that it can call itself.
+        self.declare(&name, false, &self.span_from(name_start))?;
+        let (params, return_type, body) = self.parse_function()?;
+        Ok(Node::FunctionDecl {
+            name,
+            params,
+            return_type,
+            body: Rc::new(body),
+            doc,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_struct_decl(&mut self, doc: Option<String>) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::Struct)?;
+        let name_start = self.cur_token.span.start;
+        let name = self.expect_ident()?;
+        self.declare(&name, false, &self.span_from(name_start))?;
+        self.skip_separators()?;
+
+        let mut members: Vec<String> = Vec::new();
+        let mut fields = Vec::new();
+        while let TokenKind::Ident(field) = &self.cur_token.kind {
+            let field = field.clone();
+            self.check_unique_member(&members, &field)?;
+            members.push(field.clone());
+            fields.push(field);
+            self.advance()?;
+            if self.cur_token.kind != TokenKind::Comma {
+                break;
+            }
+            self.advance()?;
+            self.skip_separators()?;
+        }
+
+        let mut methods = Vec::new();
+        loop {
+            self.skip_separators()?;
+            let doc = match self.cur_token.kind {
+                TokenKind::DocComment(_) => Some(self.parse_doc_comment()?),
+                TokenKind::Fn => None,
+                _ => break,
+            };
+            let method = self.parse_method(doc)?;
+            if let Node::FunctionDecl { name, span, .. } = &method {
+                if members.contains(name) {
+                    return self.construct_error(
+                        &format!("'{}' is already a field or method of this struct", name),
+                        Token {
+                            kind: TokenKind::Unknown,
+                            span: span.clone(),
+                        },
+                    );
+                }
+                members.push(name.clone());
+            }
+            methods.push(method);
+        }
+        self.expect(TokenKind::End)?;
+        Ok(Node::StructDecl {
+            name,
+            fields,
+            methods,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+
+    fn check_unique_member(&self, members: &[String], name: &str) -> Result<(), Error> {
+        if members.iter().any(|member| member == name) {
+            return self.construct_error(
+                &format!("'{}' is already a field or method of this struct", name),
+                self.cur_token.clone(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Parses a function declared inside of a struct, in which the instance
+    /// the method is called on is available as `self`.
+    fn parse_method(&mut self, doc: Option<String>) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::Fn)?;
+        let name = self.expect_ident()?;
+        self.scopes
+            .push(HashMap::from([("self".to_string(), true)]));
+        let function = self.parse_function();
+        self.scopes.pop();
+        let (params, return_type, body) = function?;
+        Ok(Node::FunctionDecl {
+            name,
+            params,
+            return_type,
+            body: Rc::new(body),
+            doc,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_lambda(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::Fn)?;
+        let (params, return_type, body) = self.parse_function()?;
+        Ok(Node::Lambda {
+            params,
+            return_type,
+            body: Rc::new(body),
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses the parameters, return type and body of a function, from the
+    /// opening parenthesis up to (and including) `end`.
+    fn parse_function(&mut self) -> Result<ParsedFunction, Error> {
+        self.expect(TokenKind::Lparen)?;
+        let mut params: Vec<Param> = Vec::new();
+        while self.cur_token.kind != TokenKind::Rparen {
+            let token = self.cur_token.clone();
+            let param = self.parse_param()?;
+            if params.iter().any(|other| other.name == param.name) {
+                return self
+                    .construct_error(&format!("Duplicate parameter '{}'", param.name), token);
+            }
+            if params.last().is_some_and(|last| last.variadic) {
+                return self.construct_error("'*' parameters must come last", token);
+            }
+            if param.default.is_none()
+                && !param.variadic
+                && params.iter().any(|other| other.default.is_some())
+            {
+                return self.construct_error(
+                    &format!(
+                        "Parameter '{}' must have a default, as it follows a parameter with one",
+                        param.name
+                    ),
+                    token,
+                );
+            }
+            params.push(param);
+            if self.cur_token.kind == TokenKind::Comma {
+                self.advance()?;
+            } else {
+                break;
+            }
+        }
+        self.expect(TokenKind::Rparen)?;
+        let return_type = self.parse_annotation(TokenKind::Arrow)?;
+        // `break` and `continue` can't reach loops outside of the function.
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        self.function_depth += 1;
+        self.scopes.push(
+            params
+                .iter()
+                .map(|param| (param.name.clone(), false))
+                .collect(),
+        );
+        let body = self.parse_block();
+        self.scopes.pop();
+        self.function_depth -= 1;
+        self.loop_depth = loop_depth;
+        let body = body?;
+        self.expect(TokenKind::End)?;
+        Ok((params.into(), return_type, body))
+    }
+
+    /// Parses `name`, `name = default` or `*name`, each optionally with a
+    /// type annotation (`name: ty`).
+    fn parse_param(&mut self) -> Result<Param, Error> {
+        let variadic = self.cur_token.kind == TokenKind::Mult;
+        if variadic {
+            self.advance()?;
+        }
+        let start = self.cur_token.span.start;
+        let name = self.expect_ident()?;
+        let span = self.span_from(start);
+        let ty = self.parse_annotation(TokenKind::Colon)?;
+        let default = if !variadic && self.cur_token.kind == TokenKind::Assign {
+            self.advance()?;
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        Ok(Param {
+            name,
+            span,
+            ty,
+            default,
+            variadic,
+        })
+    }
+
+    /// Parses a type annotation if the current token is `introducer` (`:` or
+    /// `->`).
+    fn parse_annotation(&mut self, introducer: TokenKind) -> Result<Option<TypeAnnotation>, Error> {
+        if self.cur_token.kind != introducer {
+            return Ok(None);
+        }
+        self.advance()?;
+        Ok(Some(self.parse_type()?))
+    }
+
+    fn parse_type(&mut self) -> Result<TypeAnnotation, Error> {
+        let start = self.cur_token.span.start;
+        match &self.cur_token.kind {
+            TokenKind::Fn => {
+                self.advance()?;
+                self.expect(TokenKind::Lparen)?;
+                let params = self.parse_comma_separated(TokenKind::Rparen, Self::parse_type)?;
+                let ret = self.parse_annotation(TokenKind::Arrow)?;
+                Ok(TypeAnnotation::Function {
+                    params,
+                    ret: ret.map(Box::new),
+                    span: self.span_from(start),
+                })
+            }
+            TokenKind::Ident(_) | TokenKind::None => {
+                let name = match &self.cur_token.kind {
+                    TokenKind::Ident(name) => name.clone(),
+                    _ => "none".to_string(),
+                };
+                self.advance()?;
+                let args = if self.cur_token.kind == TokenKind::LBracket {
+                    self.advance()?;
+                    self.parse_comma_separated(TokenKind::RBracket, Self::parse_type)?
+                } else {
+                    Vec::new()
+                };
+                Ok(TypeAnnotation::Named {
+                    name,
+                    args,
+                    span: self.span_from(start),
+                })
+            }
+            _ => self.unexpected_token("type"),
+        }
+    }
+
+    fn parse_return(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        if self.function_depth == 0 {
+            return self.construct_error("'return' outside of a function", self.cur_token.clone());
+        }
+        self.expect(TokenKind::Return)?;
+        let value = if self.at_block_end() || self.cur_token.kind == TokenKind::Seperator {
+            None
+        } else {
+            Some(Box::new(self.parse_expr()?))
+        };
+        Ok(Node::Return {
+            value,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_selection(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::If)?;
+        let mut if_conditionals = vec![self.parse_conditional(TokenKind::Then)?];
+        while self.cur_token.kind == TokenKind::Elif {
+            self.advance()?;
+            if_conditionals.push(self.parse_conditional(TokenKind::Then)?);
+        }
+        let else_conditional = if self.cur_token.kind == TokenKind::Else {
+            self.advance()?;
+            Some(Box::new(self.parse_block()?))
+        } else {
+            None
+        };
+        self.expect(TokenKind::End)?;
+        Ok(Node::Selection {
+            if_conditionals,
+            else_conditional,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_while(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::While)?;
+        let condition = self.parse_expr()?;
+        self.expect(TokenKind::Do)?;
+        let body = self.parse_loop_body()?;
+        self.expect(TokenKind::End)?;
+        Ok(Node::While {
+            conditional: Conditional {
+                condition: Box::new(condition),
+                body: Box::new(body),
+            },
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_for(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::For)?;
+        let variable_start = self.cur_token.span.start;
+        let variable = self.expect_ident()?;
+        let variable_span = self.span_from(variable_start);
+        self.expect(TokenKind::In)?;
+        let iterable = self.parse_expr()?;
+        self.expect(TokenKind::Do)?;
+        self.declare(&variable, false, &variable_span)?;
+        let body = self.parse_loop_body()?;
+        self.expect(TokenKind::End)?;
+        Ok(Node::For {
+            variable,
+            variable_span,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_match(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::Match)?;
+        let subject = self.parse_expr()?;
+        let header = self.span_from(start);
+        self.skip_separators()?;
+        let mut cases = Vec::new();
+        while cases.is_empty() || self.cur_token.kind == TokenKind::Case {
+            self.expect(TokenKind::Case)?;
+            cases.push(self.parse_match_case()?);
+        }
+        self.expect(TokenKind::End)?;
+
+        let has_catch_all = cases
+            .iter()
+            .any(|case| case.guard.is_none() && case.pattern.is_irrefutable());
+        if !has_catch_all {
+            self.warnings.push(Warning::new(
+                "This match has no catch-all case, so it may not handle every value",
+                header,
+            ));
+        }
+        Ok(Node::Match {
+            subject: Box::new(subject),
+            cases,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses `<pattern> (if <expr>)? then <stmt_list>`, following `case`.
+    fn parse_match_case(&mut self) -> Result<MatchCase, Error> {
+        let pattern = self.parse_pattern()?;
+        let mut bindings = Vec::new();
+        pattern_bindings(&pattern, &mut bindings);
+        for (i, (name, span)) in bindings.iter().enumerate() {
+            if bindings[..i].iter().any(|(other, _)| other == name) {
+                return self.construct_error(
+                    &format!("Name '{}' is bound more than once in this pattern", name),
+                    Token {
+                        kind: TokenKind::Unknown,
+                        span: (*span).clone(),
+                    },
+                );
+            }
+        }
+        for (name, span) in bindings {
+            self.declare(name, false, span)?;
+        }
+
+        let guard = if self.cur_token.kind == TokenKind::If {
+            self.advance()?;
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        self.expect(TokenKind::Then)?;
+        let body = self.parse_block()?;
+        Ok(MatchCase {
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.cur_token.span.start;
+        if let Some(value) = literal_value(&self.cur_token.kind) {
+            self.advance()?;
+            return Ok(Pattern::Literal {
+                value,
+                span: self.span_from(start),
+            });
+        }
+        match &self.cur_token.kind {
+            TokenKind::Minus => {
+                self.advance()?;
+                let value: Object = match &self.cur_token.kind {
+                    TokenKind::Int(value) => Int::new(-value).into(),
+                    TokenKind::BigInt(value) => Int::from_big(value.neg()).into(),
+                    TokenKind::Float(value) => Float { value: -value }.into(),
+                    TokenKind::Decimal(value) => match Decimal::parse(value) {
+                        Some(decimal) => decimal.neg().into(),
+                        None => return self.unexpected_token("number"),
+                    },
+                    _ => return self.unexpected_token("number"),
+                };
+                self.advance()?;
+                Ok(Pattern::Literal {
+                    value,
+                    span: self.span_from(start),
+                })
+            }
+            TokenKind::LBracket => {
+                self.advance()?;
+                let elements = self
+                    .parse_comma_separated(TokenKind::RBracket, Self::parse_list_pattern_element)?;
+                let span = self.span_from(start);
+                let rests = elements
+                    .iter()
+                    .filter(|element| matches!(element, Pattern::Rest { .. }))
+                    .count();
+                if rests > 1 {
+                    return self.construct_error(
+                        "A list pattern can only contain one '*' pattern",
+                        Token {
+                            kind: TokenKind::Unknown,
+                            span,
+                        },
+                    );
+                }
+                Ok(Pattern::List { elements, span })
+            }
+            TokenKind::Ident(name) if name == "_" => {
+                self.advance()?;
+                Ok(Pattern::Wildcard {
+                    span: self.span_from(start),
+                })
+            }
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                if self.cur_token.kind == TokenKind::Lparen {
+                    self.parse_struct_pattern(name, start)
+                } else {
+                    Ok(Pattern::Binding {
+                        name,
+                        span: self.span_from(start),
+                    })
+                }
+            }
+            _ => self.unexpected_token("pattern"),
+        }
+    }
+
+    fn parse_list_pattern_element(&mut self) -> Result<Pattern, Error> {
+        if self.cur_token.kind != TokenKind::Mult {
+            return self.parse_pattern();
+        }
+        let start = self.cur_token.span.start;
+        self.advance()?;
+        let name = self.expect_ident()?;
+        Ok(Pattern::Rest {
+            name: Some(name).filter(|name| name != "_"),
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses the field patterns of a struct pattern for the type `name`,
+    /// which starts at `start`.
+    fn parse_struct_pattern(&mut self, name: String, start: usize) -> Result<Pattern, Error> {
+        self.expect(TokenKind::Lparen)?;
+        let mut args = Vec::new();
+        let mut kwargs: Vec<(String, Pattern)> = Vec::new();
+        for (field, pattern) in
+            self.parse_comma_separated(TokenKind::Rparen, Self::parse_field_pattern)?
+        {
+            let message = match field {
+                Some(field) if kwargs.iter().any(|(other, _)| *other == field) => {
+                    format!("Field '{}' is matched more than once", field)
+                }
+                Some(field) => {
+                    kwargs.push((field, pattern));
+                    continue;
+                }
+                None if kwargs.is_empty() => {
+                    args.push(pattern);
+                    continue;
+                }
+                None => "Positional patterns must come before keyword patterns".to_string(),
+            };
+            return self.construct_error(
+                &message,
+                Token {
+                    kind: TokenKind::Unknown,
+                    span: pattern.span().clone(),
+                },
+            );
+        }
+        Ok(Pattern::Struct {
+            name,
+            args,
+            kwargs,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses a positional field pattern, or a keyword one (`field=pattern`).
+    fn parse_field_pattern(&mut self) -> Result<(Option<String>, Pattern), Error> {
+        let is_keyword = self.next_is(TokenKind::Assign);
+        let field = match &self.cur_token.kind {
+            TokenKind::Ident(field) if is_keyword => Some(field.clone()),
+            _ => None,
+        };
+        if field.is_some() {
+            self.advance()?;
+            self.expect(TokenKind::Assign)?;
+        }
+        Ok((field, self.parse_pattern()?))
+    }
+
+    /// Parses the body of a loop, in which `break` and `continue` are
+    /// allowed.
+    fn parse_loop_body(&mut self) -> Result<Node, Error> {
+        self.loop_depth += 1;
+        let body = self.parse_block();
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn parse_loop_control(&mut self) -> Result<Node, Error> {
+        let token = self.cur_token.clone();
+        if self.loop_depth == 0 {
+            return self.construct_error(&format!("{} outside of a loop", token.kind), token);
+        }
+        self.advance()?;
+        let span = token.span;
+        match token.kind {
+            TokenKind::Break => Ok(Node::Break { span }),
+            _ => Ok(Node::Continue { span }),
+        }
+    }
+
+    /// Parses `<expr> <keyword> <stmt_list>`, leaving the token which ends
+    /// the block unconsumed.
+    fn parse_conditional(&mut self, keyword: TokenKind) -> Result<Conditional, Error> {
+        let condition = self.parse_expr()?;
+        self.expect(keyword)?;
+        let body = self.parse_block()?;
+        Ok(Conditional {
+            condition: Box::new(condition),
+            body: Box::new(body),
+        })
+    }
+
+    pub fn parse_expr(&mut self) -> Result<Node, Error> {
+        self.parse_bool_expr()
+    }
+
+    /// Parses a left associative chain of binary operations, where `op_for`
+    /// maps a token to the operator it represents (if any), and `operand`
+    /// parses the next tightest binding level of expression.
+    fn parse_binary(
+        &mut self,
+        op_for: fn(&TokenKind) -> Option<Operator>,
+        operand: fn(&mut Self) -> Result<Node, Error>,
+    ) -> Result<Node, Error> {
+        let mut lhs = operand(self)?;
+        while let Some(op) = op_for(&self.cur_token.kind) {
+            self.advance()?;
+            let rhs = operand(self)?;
+            let span = Span::new(lhs.span().start, rhs.span().end);
+            lhs = Node::BinaryOp {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                span,
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn parse_bool_expr(&mut self) -> Result<Node, Error> {
+        self.parse_binary(
+            |kind| match kind {
+                TokenKind::And => Some(Operator::And),
+                TokenKind::Or => Some(Operator::Or),
+                _ => None,
+            },
+            Self::parse_not_expr,
+        )
+    }
+
+    fn parse_not_expr(&mut self) -> Result<Node, Error> {
+        if self.cur_token.kind == TokenKind::Not {
+            let start = self.cur_token.span.start;
+            self.advance()?;
+            let child = self.parse_not_expr()?;
+            return Ok(Node::UnaryOp {
+                op: Operator::Not,
+                child: Box::new(child),
+                span: self.span_from(start),
+            });
+        }
+        self.parse_comp_expr()
+    }
+
+    fn parse_comp_expr(&mut self) -> Result<Node, Error> {
+        self.parse_binary(
+            |kind| match kind {
+                TokenKind::Eq => Some(Operator::Eq),
+                TokenKind::NotEq => Some(Operator::NotEq),
+                TokenKind::Less => Some(Operator::Less),
+                TokenKind::LessEq => Some(Operator::LessEq),
+                TokenKind::More => Some(Operator::More),
+                TokenKind::MoreEq => Some(Operator::MoreEq),
+                _ => None,
+            },
+            Self::parse_add_expr,
+        )
+    }
+
+    fn parse_add_expr(&mut self) -> Result<Node, Error> {
+        self.parse_binary(
+            |kind| match kind {
+                TokenKind::Plus => Some(Operator::Add),
+                TokenKind::Minus => Some(Operator::Sub),
+                _ => None,
+            },
+            Self::parse_mult_expr,
+        )
+    }
+
+    fn parse_mult_expr(&mut self) -> Result<Node, Error> {
+        self.parse_binary(
+            |kind| match kind {
+                TokenKind::Mult => Some(Operator::Mul),
+                TokenKind::Div => Some(Operator::Div),
+                _ => None,
+            },
+            Self::parse_mod_expr,
+        )
+    }
+
+    fn parse_mod_expr(&mut self) -> Result<Node, Error> {
+        self.parse_binary(
+            |kind| match kind {
+                TokenKind::Mod => Some(Operator::Mod),
+                _ => None,
+            },
+            Self::parse_entity,
+        )
+    }
+
+    fn parse_entity(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        if self.cur_token.kind == TokenKind::Minus {
+            self.advance()?;
+            let child = self.parse_entity()?;
+            return Ok(Node::UnaryOp {
+                op: Operator::Sub,
+                child: Box::new(child),
+                span: self.span_from(start),
+            });
+        }
+        let mut node = self.parse_primary()?;
+        loop {
+            node = match self.cur_token.kind {
+                TokenKind::LBracket => self.parse_index(node, start)?,
+                TokenKind::Lparen => self.parse_function_call(node, start)?,
+                TokenKind::Dot => {
+                    self.advance()?;
+                    let name = self.expect_ident()?;
+                    Node::Field {
+                        object: Box::new(node),
+                        name,
+                        span: self.span_from(start),
+                    }
+                }
+                _ => return Ok(node),
+            };
+        }
+    }
+
+    /// Parses `[index]` or `[start:stop:step]` following `object`, which
+    /// starts at `start`.
+    fn parse_index(&mut self, object: Node, start: usize) -> Result<Node, Error> {
+        self.expect(TokenKind::LBracket)?;
+        let index = match self.cur_token.kind {
+            TokenKind::Colon => None,
+            _ => Some(Box::new(self.parse_expr()?)),
+        };
+
+        if self.cur_token.kind != TokenKind::Colon {
+            self.expect(TokenKind::RBracket)?;
+            return match index {
+                Some(index) => Ok(Node::Index {
+                    object: Box::new(object),
+                    index,
+                    span: self.span_from(start),
+                }),
+                None => self.unexpected_token("index"),
+            };
+        }
+
+        self.expect(TokenKind::Colon)?;
+        let stop = match self.cur_token.kind {
+            TokenKind::Colon | TokenKind::RBracket => None,
+            _ => Some(Box::new(self.parse_expr()?)),
+        };
+        let mut step = None;
+        if self.cur_token.kind == TokenKind::Colon {
+            self.advance()?;
+            if self.cur_token.kind != TokenKind::RBracket {
+                step = Some(Box::new(self.parse_expr()?));
+            }
+        }
+        self.expect(TokenKind::RBracket)?;
+        Ok(Node::Slice {
+            object: Box::new(object),
+            start: index,
+            stop,
+            step,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        if let Some(value) = literal_value(&self.cur_token.kind) {
+            self.advance()?;
+            return Ok(Node::Literal {
+                value,
+                span: self.span_from(start),
+            });
+        }
+        match &self.cur_token.kind {
+            TokenKind::Lparen => {
+                self.advance()?;
+                let expr = self.parse_expr()?;
+                self.expect(TokenKind::Rparen)?;
+                Ok(expr)
+            }
+            TokenKind::LBracket => self.parse_list(),
+            TokenKind::LBrace => self.parse_dict(),
+            TokenKind::Fn => self.parse_lambda(),
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Ok(Node::Identifier {
+                    name,
+                    span: self.span_from(start),
+                })
+            }
+            _ => self.unexpected_token("expression"),
+        }
+    }
+
+    /// Parses a comma separated list of items up to (and including) the
+    /// closing token, allowing a trailing comma. Newlines between items are
+    /// ignored, so that long lists can be split over several lines.
+    fn parse_comma_separated<T>(
+        &mut self,
+        close: TokenKind,
+        item: fn(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut items = Vec::new();
+        self.skip_separators()?;
+        while self.cur_token.kind != close {
+            items.push(item(self)?);
+            self.skip_separators()?;
+            if self.cur_token.kind == TokenKind::Comma {
+                self.advance()?;
+                self.skip_separators()?;
+            } else {
+                break;
+            }
+        }
+        self.expect(close)?;
+        Ok(items)
+    }
+
+    fn parse_expr_list(&mut self, close: TokenKind) -> Result<Vec<Node>, Error> {
+        self.parse_comma_separated(close, Self::parse_expr)
+    }
+
+    fn parse_dict(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::LBrace)?;
+        let entries = self.parse_comma_separated(TokenKind::RBrace, |parser| {
+            let key = parser.parse_expr()?;
+            parser.expect(TokenKind::Colon)?;
+            let value = parser.parse_expr()?;
+            Ok((key, value))
+        })?;
+        Ok(Node::Dict {
+            entries,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_list(&mut self) -> Result<Node, Error> {
+        let start = self.cur_token.span.start;
+        self.expect(TokenKind::LBracket)?;
+        let elements = self.parse_expr_list(TokenKind::RBracket)?;
+        Ok(Node::List {
+            elements,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses the arguments of a call to `function`, which starts at `start`.
+    fn parse_function_call(&mut self, function: Node, start: usize) -> Result<Node, Error> {
+        self.expect(TokenKind::Lparen)?;
+        let mut args = Vec::new();
+        let mut kwargs: Vec<(String, Node)> = Vec::new();
+        for (name, value) in self.parse_comma_separated(TokenKind::Rparen, Self::parse_argument)? {
+            let message = match name {
+                Some(name) if kwargs.iter().any(|(other, _)| *other == name) => {
+                    format!("Keyword argument '{}' is repeated", name)
+                }
+                Some(name) => {
+                    kwargs.push((name, value));
+                    continue;
+                }
+                None if kwargs.is_empty() => {
+                    args.push(value);
+                    continue;
+                }
+                None => "Positional arguments must come before keyword arguments".to_string(),
+            };
+            return self.construct_error(
+                &message,
+                Token {
+                    kind: TokenKind::Unknown,
+                    span: value.span().clone(),
+                },
+            );
+        }
+        Ok(Node::FunctionCall {
+            function: Box::new(function),
+            args,
+            kwargs,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses a positional argument, or a keyword argument (`name=value`)
+    /// along with its name. `end` may name a keyword argument, as in
+    /// `print(x, end="")`, since it can't start an expression.
+    fn parse_argument(&mut self) -> Result<(Option<String>, Node), Error> {
+        let is_keyword = self.next_is(TokenKind::Assign);
+        let name = match &self.cur_token.kind {
+            TokenKind::Ident(name) if is_keyword => Some(name.clone()),
+            TokenKind::End if is_keyword => Some("end".to_string()),
+            _ => None,
+        };
+        if name.is_some() {
+            self.advance()?;
+            self.expect(TokenKind::Assign)?;
+        }
+        Ok((name, self.parse_expr()?))
+    }
+}
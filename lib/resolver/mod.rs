@@ -0,0 +1,109 @@
+//! Name resolution, which finds the declaration each name in a program refers
+//! to without running it.
+//!
+//! The program is divided into a tree of scopes: the program itself, the body
+//! of each function, and each block (the body of an `if`, a loop or a `match`
+//! case). A name can be used in the scope which declares it and any scope
+//! nested inside it. Uses of names which are never declared are reported as
+//! `NameError`s, and declarations which are never used, hide another
+//! declaration, or can't be reached are reported as warnings.
+
+use crate::{
+    errors::{Error, Warning},
+    lexer::Span,
+};
+
+#[allow(clippy::module_inception)]
+pub mod resolver;
+pub use resolver::resolve;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    Program,
+    /// The parameters and body of a function, lambda or method.
+    Function,
+    /// The body of an `if` branch, a loop, or a `match` case.
+    Block,
+}
+
+#[derive(Debug)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    /// The index of the enclosing scope, which is `None` for the program.
+    pub parent: Option<usize>,
+    pub span: Span,
+    /// The indices of the declarations made in this scope, in source order.
+    pub declarations: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclarationKind {
+    Variable,
+    Constant,
+    Parameter,
+    /// `self` in a method.
+    Receiver,
+    /// The variable of a `for` loop.
+    LoopVariable,
+    /// A name bound by a `match` pattern.
+    PatternBinding,
+    Function,
+    Struct,
+    Import,
+}
+
+#[derive(Debug)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    pub span: Span,
+    /// The index of the scope the declaration was made in.
+    pub scope: usize,
+    /// The number of times the declared value is read.
+    pub uses: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    /// The index of the declaration the name refers to.
+    Declaration(usize),
+    /// A builtin function, which a name refers to when it is called and the
+    /// program doesn't declare it.
+    Builtin,
+}
+
+/// A use of a name, either to read its value or to assign to it.
+#[derive(Debug)]
+pub struct Reference {
+    pub name: String,
+    pub span: Span,
+    /// What the name refers to, or `None` if it isn't declared.
+    pub binding: Option<Binding>,
+}
+
+/// The scope tree of a program, and what each name in it refers to.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    /// Every scope in the program, starting with the program's own scope.
+    pub scopes: Vec<Scope>,
+    pub declarations: Vec<Declaration>,
+    /// Every use of a name, in the order they were resolved.
+    pub references: Vec<Reference>,
+    /// A `NameError` for each use of a name which is never declared.
+    pub errors: Vec<Error>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Resolution {
+    /// Returns the declaration the name used at `span` refers to.
+    pub fn declaration_at(&self, span: &Span) -> Option<&Declaration> {
+        let reference = self
+            .references
+            .iter()
+            .find(|reference| &reference.span == span)?;
+        match reference.binding? {
+            Binding::Declaration(index) => self.declarations.get(index),
+            Binding::Builtin => None,
+        }
+    }
+}
@@ -0,0 +1,555 @@
+use std::collections::HashMap;
+
+use crate::{
+    errors::{Error, ErrorType, Warning},
+    interpreter::builtins,
+    lexer::Span,
+    parser::ast::*,
+    resolver::{Binding, Declaration, DeclarationKind, Reference, Resolution, Scope, ScopeKind},
+};
+
+/// Builds the scope tree of a program and resolves each name in it to its
+/// declaration, reporting undeclared names as errors and likely mistakes as
+/// warnings.
+pub fn resolve(program: &Node) -> Resolution {
+    let mut resolver = Resolver {
+        resolution: Resolution::default(),
+        stack: Vec::new(),
+        ended: Vec::new(),
+        deferred: Vec::new(),
+    };
+    resolver.push_scope(ScopeKind::Program, program.span());
+    match program {
+        Node::Program { statements, .. } => resolver.statements(statements),
+        _ => resolver.statement(program),
+    }
+    resolver.pop_scope();
+    resolver.finish()
+}
+
+/// How a name is used.
+#[derive(Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    /// Called, which may refer to a builtin function.
+    Call,
+    /// Assigned to, which doesn't count as a use of the variable's value.
+    Write,
+}
+
+/// Returns the keyword of a statement which always leaves the enclosing
+/// block, so that any statements after it can't be reached.
+fn exit_keyword(statement: &Node) -> Option<&'static str> {
+    match statement {
+        Node::Return { .. } => Some("return"),
+        Node::Break { .. } => Some("break"),
+        Node::Continue { .. } => Some("continue"),
+        _ => None,
+    }
+}
+
+struct Resolver {
+    resolution: Resolution,
+    /// The scopes enclosing the code being resolved, innermost last.
+    stack: Vec<usize>,
+    /// For each scope, the names declared in blocks nested within it which
+    /// have ended. Variables live until the function which declares them
+    /// returns, so these may still be defined after the block.
+    ended: Vec<HashMap<String, usize>>,
+    /// References inside functions to names which hadn't been declared when
+    /// they were reached, along with the scope enclosing the function. A
+    /// function can use names declared after it, as long as they are declared
+    /// by the time it is called, so these are retried once every declaration
+    /// is known.
+    deferred: Vec<(usize, usize, Access)>,
+}
+
+impl Resolver {
+    fn warn(&mut self, message: &str, span: &Span) {
+        self.resolution
+            .warnings
+            .push(Warning::new(message, span.clone()));
+    }
+
+    fn undefined(&mut self, name: &str, span: &Span) {
+        self.resolution.errors.push(Error::new(
+            ErrorType::NameError,
+            &format!("Name '{}' is not defined", name),
+            span.clone(),
+        ));
+    }
+
+    fn current_scope(&self) -> usize {
+        *self.stack.last().expect("there is always a scope")
+    }
+
+    fn push_scope(&mut self, kind: ScopeKind, span: &Span) {
+        let index = self.resolution.scopes.len();
+        self.resolution.scopes.push(Scope {
+            kind,
+            parent: self.stack.last().copied(),
+            span: span.clone(),
+            declarations: Vec::new(),
+        });
+        self.ended.push(HashMap::new());
+        self.stack.push(index);
+    }
+
+    fn pop_scope(&mut self) {
+        let Some(index) = self.stack.pop() else {
+            return;
+        };
+        let scope = &self.resolution.scopes[index];
+        if scope.kind != ScopeKind::Block {
+            return;
+        }
+        let Some(parent) = scope.parent else {
+            return;
+        };
+        let mut names = std::mem::take(&mut self.ended[index]);
+        for &declaration in &scope.declarations {
+            let name = self.resolution.declarations[declaration].name.clone();
+            names.insert(name, declaration);
+        }
+        self.ended[parent].extend(names);
+    }
+
+    /// Finds the most recent declaration of `name` which is visible from
+    /// `scope`.
+    fn find(&self, name: &str, scope: Option<usize>) -> Option<usize> {
+        let mut scope = scope;
+        while let Some(index) = scope {
+            let found = self.resolution.scopes[index]
+                .declarations
+                .iter()
+                .rev()
+                .find(|&&declaration| self.resolution.declarations[declaration].name == name);
+            if let Some(&declaration) = found {
+                return Some(declaration);
+            }
+            scope = self.resolution.scopes[index].parent;
+        }
+        None
+    }
+
+    /// Finds a declaration of `name` in a block which has ended, from which
+    /// `scope` may be able to use it.
+    fn find_ended(&self, name: &str, scope: Option<usize>) -> Option<usize> {
+        let mut scope = scope;
+        while let Some(index) = scope {
+            if let Some(&declaration) = self.ended[index].get(name) {
+                return Some(declaration);
+            }
+            scope = self.resolution.scopes[index].parent;
+        }
+        None
+    }
+
+    fn declare(&mut self, name: &str, kind: DeclarationKind, span: &Span) {
+        let scope = self.current_scope();
+        let redeclared = self.resolution.scopes[scope]
+            .declarations
+            .iter()
+            .any(|&declaration| self.resolution.declarations[declaration].name == name);
+        let parent = self.resolution.scopes[scope].parent;
+        if !redeclared && !name.starts_with('_') && self.find(name, parent).is_some() {
+            self.warn(
+                &format!("'{}' shadows a declaration in an enclosing scope", name),
+                span,
+            );
+        }
+
+        let index = self.resolution.declarations.len();
+        self.resolution.declarations.push(Declaration {
+            name: name.to_string(),
+            kind,
+            span: span.clone(),
+            scope,
+            uses: 0,
+        });
+        self.resolution.scopes[scope].declarations.push(index);
+    }
+
+    fn bind(&mut self, reference: usize, declaration: usize, access: Access) {
+        self.resolution.references[reference].binding = Some(Binding::Declaration(declaration));
+        if access != Access::Write {
+            self.resolution.declarations[declaration].uses += 1;
+        }
+    }
+
+    fn warn_ended(&mut self, name: &str, span: &Span) {
+        self.warn(
+            &format!(
+                "'{}' is declared in a block which has ended, so it may not be defined here",
+                name
+            ),
+            span,
+        );
+    }
+
+    fn reference(&mut self, name: &str, span: &Span, access: Access) {
+        let index = self.resolution.references.len();
+        self.resolution.references.push(Reference {
+            name: name.to_string(),
+            span: span.clone(),
+            binding: None,
+        });
+
+        let scope = self.stack.last().copied();
+        if let Some(declaration) = self.find(name, scope) {
+            self.bind(index, declaration, access);
+        } else if access == Access::Call && builtins::lookup(name).is_some() {
+            self.resolution.references[index].binding = Some(Binding::Builtin);
+        } else if let Some(declaration) = self.find_ended(name, scope) {
+            self.warn_ended(name, span);
+            self.bind(index, declaration, access);
+        } else if let Some(outer) = self.function_parent() {
+            self.deferred.push((index, outer, access));
+        } else {
+            self.undefined(name, span);
+        }
+    }
+
+    /// Returns the scope enclosing the innermost function being resolved.
+    fn function_parent(&self) -> Option<usize> {
+        self.stack
+            .iter()
+            .rev()
+            .find(|&&scope| self.resolution.scopes[scope].kind == ScopeKind::Function)
+            .and_then(|&scope| self.resolution.scopes[scope].parent)
+    }
+
+    /// Retries the deferred references, then warns about unused variables.
+    fn finish(mut self) -> Resolution {
+        for (index, scope, access) in std::mem::take(&mut self.deferred) {
+            let reference = &self.resolution.references[index];
+            let (name, span) = (reference.name.clone(), reference.span.clone());
+            if let Some(declaration) = self.find(&name, Some(scope)) {
+                self.bind(index, declaration, access);
+            } else if let Some(declaration) = self.find_ended(&name, Some(scope)) {
+                self.warn_ended(&name, &span);
+                self.bind(index, declaration, access);
+            } else {
+                self.undefined(&name, &span);
+            }
+        }
+
+        let mut unused = Vec::new();
+        for declaration in &self.resolution.declarations {
+            if declaration.uses > 0 || declaration.name.starts_with('_') {
+                continue;
+            }
+            let message = match declaration.kind {
+                DeclarationKind::Parameter => {
+                    format!("Argument '{}' is never used", declaration.name)
+                }
+                DeclarationKind::Variable
+                | DeclarationKind::Constant
+                | DeclarationKind::LoopVariable
+                | DeclarationKind::PatternBinding => {
+                    format!("Variable '{}' is never used", declaration.name)
+                }
+                _ => continue,
+            };
+            unused.push(Warning::new(&message, declaration.span.clone()));
+        }
+        self.resolution.warnings.extend(unused);
+
+        let mut resolution = self.resolution;
+        resolution
+            .errors
+            .sort_by_key(|error| error.token.span.start);
+        resolution
+            .warnings
+            .sort_by_key(|warning| warning.span.start);
+        resolution
+    }
+
+    fn statements(&mut self, statements: &[Node]) {
+        let mut exit = None;
+        for (i, statement) in statements.iter().enumerate() {
+            self.statement(statement);
+            if exit.is_none() {
+                exit = exit_keyword(statement).map(|keyword| (i, keyword));
+            }
+        }
+        // Only the first unreachable statement of a block is reported.
+        if let (Some((i, keyword)), Some(last)) = (exit, statements.last()) {
+            if let Some(first) = statements.get(i + 1) {
+                self.warn(
+                    &format!(
+                        "This code is unreachable, as it comes after a '{}'",
+                        keyword
+                    ),
+                    &Span::new(first.span().start, last.span().end),
+                );
+            }
+        }
+    }
+
+    /// Resolves the body of an `if` branch or a loop in a new block scope.
+    fn block(&mut self, body: &Node) {
+        self.push_scope(ScopeKind::Block, body.span());
+        self.body(body);
+        self.pop_scope();
+    }
+
+    fn body(&mut self, body: &Node) {
+        match body {
+            Node::Block { statements, .. } => self.statements(statements),
+            _ => self.statement(body),
+        }
+    }
+
+    fn function(&mut self, params: &[Param], body: &Node, span: &Span, is_method: bool) {
+        self.push_scope(ScopeKind::Function, span);
+        if is_method {
+            self.declare("self", DeclarationKind::Receiver, span);
+        }
+        // Defaults are evaluated when the function is called, and can refer
+        // to the parameters before them.
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+            self.declare(&param.name, DeclarationKind::Parameter, &param.span);
+        }
+        self.body(body);
+        self.pop_scope();
+    }
+
+    fn statement(&mut self, node: &Node) {
+        match node {
+            Node::Program { statements, .. } | Node::Block { statements, .. } => {
+                self.statements(statements)
+            }
+            Node::Import { path, span } => self.declare(path, DeclarationKind::Import, span),
+            Node::VariableDecl {
+                name,
+                value,
+                constant,
+                span,
+                ..
+            } => {
+                self.expr(value);
+                let kind = if *constant {
+                    DeclarationKind::Constant
+                } else {
+                    DeclarationKind::Variable
+                };
+                self.declare(name, kind, span);
+            }
+            Node::Assign {
+                name,
+                op,
+                value,
+                span,
+            } => {
+                self.expr(value);
+                let access = match op {
+                    Some(_) => Access::Read,
+                    None => Access::Write,
+                };
+                self.reference(name, span, access);
+            }
+            Node::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            Node::FieldAssign { object, value, .. } => {
+                self.expr(object);
+                self.expr(value);
+            }
+            Node::FunctionDecl {
+                name,
+                params,
+                body,
+                span,
+                ..
+            } => {
+                // Declared before the body so that the function can call
+                // itself.
+                self.declare(name, DeclarationKind::Function, span);
+                self.function(params, body, span, false);
+            }
+            Node::StructDecl {
+                name,
+                methods,
+                span,
+                ..
+            } => {
+                self.declare(name, DeclarationKind::Struct, span);
+                for method in methods {
+                    if let Node::FunctionDecl {
+                        params, body, span, ..
+                    } = method
+                    {
+                        self.function(params, body, span, true);
+                    }
+                }
+            }
+            Node::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Node::Selection {
+                if_conditionals,
+                else_conditional,
+                ..
+            } => {
+                for conditional in if_conditionals {
+                    self.expr(&conditional.condition);
+                    self.block(&conditional.body);
+                }
+                if let Some(body) = else_conditional {
+                    self.block(body);
+                }
+            }
+            Node::While { conditional, .. } => {
+                self.expr(&conditional.condition);
+                self.block(&conditional.body);
+            }
+            Node::For {
+                variable,
+                variable_span,
+                iterable,
+                body,
+                span,
+            } => {
+                self.expr(iterable);
+                self.push_scope(ScopeKind::Block, span);
+                self.declare(variable, DeclarationKind::LoopVariable, variable_span);
+                self.body(body);
+                self.pop_scope();
+            }
+            Node::Match { subject, cases, .. } => {
+                self.expr(subject);
+                for case in cases {
+                    let span = Span::new(case.pattern.span().start, case.body.span().end);
+                    self.push_scope(ScopeKind::Block, &span);
+                    self.pattern(&case.pattern);
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.body(&case.body);
+                    self.pop_scope();
+                }
+            }
+            Node::Break { .. } | Node::Continue { .. } => {}
+            _ => self.expr(node),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding { name, span }
+            | Pattern::Rest {
+                name: Some(name),
+                span,
+            } => self.declare(name, DeclarationKind::PatternBinding, span),
+            Pattern::List { elements, .. } => {
+                for element in elements {
+                    self.pattern(element);
+                }
+            }
+            Pattern::Struct {
+                name,
+                args,
+                kwargs,
+                span,
+            } => {
+                self.reference(name, span, Access::Read);
+                for arg in args {
+                    self.pattern(arg);
+                }
+                for (_, pattern) in kwargs {
+                    self.pattern(pattern);
+                }
+            }
+            Pattern::Wildcard { .. } | Pattern::Literal { .. } | Pattern::Rest { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, node: &Node) {
+        match node {
+            Node::Identifier { name, span } => self.reference(name, span, Access::Read),
+            Node::FunctionCall {
+                function,
+                args,
+                kwargs,
+                ..
+            } => {
+                match function.as_ref() {
+                    Node::Identifier { name, span } => self.reference(name, span, Access::Call),
+                    callee => self.expr(callee),
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+                for (_, value) in kwargs {
+                    self.expr(value);
+                }
+            }
+            Node::Lambda {
+                params, body, span, ..
+            } => self.function(params, body, span, false),
+            Node::List { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Node::Dict { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Node::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Node::Slice {
+                object,
+                start,
+                stop,
+                step,
+                ..
+            } => {
+                self.expr(object);
+                for bound in [start, stop, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            Node::Field { object, .. } => self.expr(object),
+            Node::UnaryOp { child, .. } => self.expr(child),
+            Node::BinaryOp { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Node::Literal { .. } => {}
+            Node::Program { .. }
+            | Node::Block { .. }
+            | Node::Import { .. }
+            | Node::VariableDecl { .. }
+            | Node::Assign { .. }
+            | Node::IndexAssign { .. }
+            | Node::FieldAssign { .. }
+            | Node::FunctionDecl { .. }
+            | Node::StructDecl { .. }
+            | Node::Return { .. }
+            | Node::Selection { .. }
+            | Node::While { .. }
+            | Node::For { .. }
+            | Node::Match { .. }
+            | Node::Break { .. }
+            | Node::Continue { .. } => self.statement(node),
+        }
+    }
+}
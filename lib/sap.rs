@@ -1,6 +1,12 @@
 #[allow(non_upper_case_globals)]
 pub mod colours;
 pub mod core;
+pub mod doc;
 pub mod errors;
+pub mod interpreter;
 pub mod lexer;
+pub mod lint;
+pub mod optimizer;
 pub mod parser;
+pub mod resolver;
+pub mod types;
@@ -0,0 +1,1014 @@
+use std::collections::HashMap;
+
+use crate::{
+    core::Object,
+    errors::{Error, ErrorType},
+    lexer::Span,
+    parser::ast::*,
+    types::{FunctionType, ParamType, Type},
+};
+
+/// Infers the types of a program's expressions, returning an error for each
+/// value which is used in a way its type doesn't allow, or which doesn't
+/// match an annotation.
+pub fn check(program: &Node) -> Vec<Error> {
+    let mut checker = Checker {
+        scopes: vec![HashMap::new()],
+        structs: HashMap::new(),
+        functions: Vec::new(),
+        errors: Vec::new(),
+    };
+    checker.statement(program);
+    checker.errors
+}
+
+struct Variable {
+    ty: Type,
+    /// Whether the variable was declared with a type annotation. Assignments
+    /// to annotated variables must match the annotation, while other
+    /// variables can be assigned anything, widening their type.
+    annotated: bool,
+}
+
+struct StructInfo {
+    fields: Vec<String>,
+    methods: HashMap<String, Type>,
+}
+
+/// A function whose body is being checked.
+struct FunctionContext {
+    name: String,
+    /// The return type annotation, if there is one.
+    declared: Option<Type>,
+    /// The types of the values returned by the function's `return`
+    /// statements.
+    returns: Vec<Type>,
+}
+
+/// The types a function's signature declares, with `any` for parameters
+/// without annotations.
+struct Signature {
+    params: Vec<ParamType>,
+    rest: Option<Type>,
+    ret: Option<Type>,
+}
+
+/// The type of a builtin function, for names the program doesn't define.
+fn builtin_type(name: &str) -> Type {
+    let int = |optional| ParamType {
+        name: None,
+        ty: Type::Int,
+        optional,
+    };
+    let any = |optional| ParamType {
+        name: None,
+        ty: Type::Any,
+        optional,
+    };
+    let conversion = |ret| Type::function(vec![any(false)], None, ret);
+    match name {
+        "print" => Type::function(vec![], Some(Type::Any), Type::None),
+        "input" => Type::function(vec![any(true)], None, Type::Str),
+        "len" => Type::function(vec![any(false)], None, Type::Int),
+        "type" => Type::function(vec![any(false)], None, Type::Str),
+        "assert" => Type::function(vec![any(false), any(true)], None, Type::None),
+        "abs" => Type::function(vec![any(false)], None, Type::Any),
+        "min" | "max" => Type::function(vec![any(false)], Some(Type::Any), Type::Any),
+        "sum" => Type::function(vec![any(false), any(true)], None, Type::Any),
+        "round" => Type::function(vec![any(false), int(true)], None, Type::Any),
+        "range" => Type::function(vec![int(false), int(true), int(true)], None, Type::Range),
+        "int" => conversion(Type::Int),
+        "float" => conversion(Type::Float),
+        "str" => conversion(Type::Str),
+        "bool" => conversion(Type::Bool),
+        "list" => conversion(Type::list(Type::Any)),
+        _ => Type::Any,
+    }
+}
+
+/// The type of the builtin method `name` of a list of `element`s. Values
+/// added to a list aren't checked against its element type, as the list may
+/// be meant to hold values of any type.
+fn list_method_type(element: &Type, name: &str) -> Option<Type> {
+    let param = |ty, optional| ParamType {
+        name: None,
+        ty,
+        optional,
+    };
+    let method_type = match name {
+        "push" | "remove" => Type::function(vec![param(Type::Any, false)], None, Type::None),
+        "pop" => Type::function(vec![param(Type::Int, true)], None, element.clone()),
+        "insert" => Type::function(
+            vec![param(Type::Int, false), param(Type::Any, false)],
+            None,
+            Type::None,
+        ),
+        "sort" | "reverse" => Type::function(Vec::new(), None, Type::None),
+        "copy" => Type::function(Vec::new(), None, Type::list(element.clone())),
+        _ => return None,
+    };
+    Some(method_type)
+}
+
+fn literal_type(value: &Object) -> Type {
+    match value {
+        Object::Bool(_) => Type::Bool,
+        Object::Int(_) => Type::Int,
+        Object::Float(_) => Type::Float,
+        Object::Decimal(_) => Type::Decimal,
+        Object::Str(_) => Type::Str,
+        Object::None => Type::None,
+        _ => Type::Any,
+    }
+}
+
+/// Whether every path through a function's body ends with a `return`, in
+/// which case the function can't finish without returning a value. An `if`
+/// needs an `else`, and a `match` a case which matches anything, for every
+/// path through them to be covered.
+fn ends_with_return(body: &Node) -> bool {
+    match body {
+        Node::Block { statements, .. } => statements.last().is_some_and(ends_with_return),
+        Node::Return { .. } => true,
+        Node::Selection {
+            if_conditionals,
+            else_conditional: Some(else_conditional),
+            ..
+        } => {
+            if_conditionals
+                .iter()
+                .all(|conditional| ends_with_return(&conditional.body))
+                && ends_with_return(else_conditional)
+        }
+        Node::Match { cases, .. } => {
+            cases.iter().all(|case| ends_with_return(&case.body))
+                && cases.iter().any(|case| {
+                    case.guard.is_none()
+                        && matches!(
+                            case.pattern,
+                            Pattern::Wildcard { .. } | Pattern::Binding { .. }
+                        )
+                })
+        }
+        _ => false,
+    }
+}
+
+struct Checker {
+    /// The variables of the program and of each function being checked.
+    scopes: Vec<HashMap<String, Variable>>,
+    structs: HashMap<String, StructInfo>,
+    functions: Vec<FunctionContext>,
+    errors: Vec<Error>,
+}
+
+impl Checker {
+    fn error(&mut self, error_type: ErrorType, message: &str, span: &Span) {
+        self.errors
+            .push(Error::new(error_type, message, span.clone()));
+    }
+
+    fn define(&mut self, name: &str, ty: Type, annotated: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), Variable { ty, annotated });
+        }
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Variable> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+
+    /// Converts a type annotation into the type it refers to.
+    fn resolve(&mut self, annotation: &TypeAnnotation) -> Type {
+        match annotation {
+            TypeAnnotation::Named { name, args, span } => {
+                match (name.as_str(), args.as_slice()) {
+                    ("list", []) => return Type::list(Type::Any),
+                    ("list", [element]) => return Type::list(self.resolve(element)),
+                    ("list", _) => {
+                        self.error(ErrorType::TypeError, "'list' takes 1 type argument", span);
+                        return Type::Any;
+                    }
+                    (_, []) => {}
+                    _ => {
+                        self.error(
+                            ErrorType::TypeError,
+                            &format!("'{}' does not take type arguments", name),
+                            span,
+                        );
+                        return Type::Any;
+                    }
+                }
+                match name.as_str() {
+                    "any" => Type::Any,
+                    "none" => Type::None,
+                    "bool" => Type::Bool,
+                    "int" => Type::Int,
+                    "float" => Type::Float,
+                    "decimal" => Type::Decimal,
+                    "str" => Type::Str,
+                    "dict" => Type::Dict,
+                    "range" => Type::Range,
+                    _ if self.structs.contains_key(name) => Type::Struct(name.clone()),
+                    _ => {
+                        self.error(
+                            ErrorType::NameError,
+                            &format!("Unknown type '{}'", name),
+                            span,
+                        );
+                        Type::Any
+                    }
+                }
+            }
+            TypeAnnotation::Function { params, ret, .. } => {
+                let params = params
+                    .iter()
+                    .map(|param| ParamType {
+                        name: None,
+                        ty: self.resolve(param),
+                        optional: false,
+                    })
+                    .collect();
+                let ret = match ret {
+                    Some(ret) => self.resolve(ret),
+                    None => Type::None,
+                };
+                Type::function(params, None, ret)
+            }
+        }
+    }
+
+    fn statement(&mut self, node: &Node) {
+        match node {
+            Node::Program { statements, .. } | Node::Block { statements, .. } => {
+                for statement in statements {
+                    self.statement(statement);
+                }
+            }
+            Node::VariableDecl {
+                name, ty, value, ..
+            } => match ty {
+                Some(annotation) => {
+                    let ty = self.resolve(annotation);
+                    let value_type = self.expr_as(value, &ty);
+                    if !ty.accepts(&value_type) {
+                        self.mismatch(name, &ty, &value_type, value.span());
+                    }
+                    self.define(name, ty, true);
+                }
+                None => {
+                    let value_type = self.expr(value);
+                    self.define(name, value_type, false);
+                }
+            },
+            Node::FunctionDecl {
+                name,
+                params,
+                return_type,
+                body,
+                span,
+                ..
+            } => {
+                let signature = self.signature(params, return_type);
+                // Defined before the body is checked so that it can call
+                // itself.
+                let ty = Type::function(
+                    signature.params.clone(),
+                    signature.rest.clone(),
+                    signature.ret.clone().unwrap_or(Type::Any),
+                );
+                self.define(name, ty, false);
+                let ty = self.function(name, params, signature, body, span);
+                self.define(name, ty, false);
+            }
+            Node::StructDecl {
+                name,
+                fields,
+                methods,
+                ..
+            } => self.struct_decl(name, fields, methods),
+            Node::Assign {
+                name,
+                op,
+                value,
+                span,
+            } => {
+                let expected = match self.lookup(name) {
+                    Some(variable) if variable.annotated && op.is_none() => variable.ty.clone(),
+                    _ => Type::Any,
+                };
+                let mut value_type = self.expr_as(value, &expected);
+                let Some(variable) = self.lookup(name) else {
+                    return;
+                };
+                let (current, annotated) = (variable.ty.clone(), variable.annotated);
+                if let Some(op) = op {
+                    value_type = self.binary(op, &current, &value_type, span);
+                }
+                if annotated {
+                    if !current.accepts(&value_type) {
+                        self.mismatch(name, &current, &value_type, span);
+                    }
+                } else if let Some(variable) = self.lookup(name) {
+                    variable.ty = current.join(&value_type);
+                }
+            }
+            Node::IndexAssign {
+                object,
+                index,
+                op,
+                value,
+                span,
+            } => {
+                let object_type = self.expr(object);
+                let index_type = self.expr(index);
+                let mut value_type = self.expr(value);
+                let element = self.index(&object_type, &index_type, index, span);
+                if let Some(op) = op {
+                    value_type = self.binary(op, &element, &value_type, span);
+                } else if !matches!(object_type, Type::List(_) | Type::Dict | Type::Any) {
+                    self.error(
+                        ErrorType::TypeError,
+                        &format!("'{}' object does not support index assignment", object_type),
+                        span,
+                    );
+                    return;
+                }
+                // Only annotated lists are required to keep the type of
+                // their elements.
+                if let (Node::Identifier { name, .. }, Type::List(element)) =
+                    (object.as_ref(), &object_type)
+                {
+                    let annotated = self.lookup(name).is_some_and(|variable| variable.annotated);
+                    if annotated && !element.accepts(&value_type) {
+                        self.error(
+                            ErrorType::TypeError,
+                            &format!(
+                                "Cannot assign a value of type '{}' to an element of '{}', which has type '{}'",
+                                value_type, name, object_type
+                            ),
+                            span,
+                        );
+                    }
+                }
+            }
+            Node::FieldAssign {
+                object,
+                name,
+                op,
+                value,
+                span,
+            } => {
+                let object_type = self.expr(object);
+                let field = self.field(&object_type, name, span);
+                let value_type = self.expr(value);
+                if let Some(op) = op {
+                    self.binary(op, &field, &value_type, span);
+                }
+            }
+            Node::Return { value, span } => {
+                let declared = self
+                    .functions
+                    .last()
+                    .and_then(|function| function.declared.clone())
+                    .unwrap_or(Type::Any);
+                let ty = match value {
+                    Some(value) => self.expr_as(value, &declared),
+                    None => Type::None,
+                };
+                let Some(function) = self.functions.last_mut() else {
+                    return;
+                };
+                function.returns.push(ty.clone());
+                if let Some(declared) = &function.declared {
+                    if !declared.accepts(&ty) {
+                        let message = format!(
+                            "Cannot return a value of type '{}' from '{}', which returns '{}'",
+                            ty, function.name, declared
+                        );
+                        self.error(ErrorType::TypeError, &message, span);
+                    }
+                }
+            }
+            Node::Selection {
+                if_conditionals,
+                else_conditional,
+                ..
+            } => {
+                for conditional in if_conditionals {
+                    self.expr(&conditional.condition);
+                    self.statement(&conditional.body);
+                }
+                if let Some(body) = else_conditional {
+                    self.statement(body);
+                }
+            }
+            Node::While { conditional, .. } => {
+                self.expr(&conditional.condition);
+                self.statement(&conditional.body);
+            }
+            Node::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                let iterable_type = self.expr(iterable);
+                let element = iterable_type.element().unwrap_or_else(|| {
+                    self.error(
+                        ErrorType::TypeError,
+                        &format!("'{}' object is not iterable", iterable_type),
+                        iterable.span(),
+                    );
+                    Type::Any
+                });
+                self.define(variable, element, false);
+                self.statement(body);
+            }
+            Node::Match { subject, cases, .. } => {
+                let subject_type = self.expr(subject);
+                for case in cases {
+                    self.bind_pattern(&case.pattern, &subject_type);
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.statement(&case.body);
+                }
+            }
+            Node::Import { .. } | Node::Break { .. } | Node::Continue { .. } => {}
+            _ => {
+                self.expr(node);
+            }
+        }
+    }
+
+    fn mismatch(&mut self, name: &str, expected: &Type, found: &Type, span: &Span) {
+        self.error(
+            ErrorType::TypeError,
+            &format!(
+                "Cannot assign a value of type '{}' to '{}', which has type '{}'",
+                found, name, expected
+            ),
+            span,
+        );
+    }
+
+    fn struct_decl(&mut self, name: &str, fields: &[String], methods: &[Node]) {
+        let constructor = Type::function(
+            fields
+                .iter()
+                .map(|field| ParamType {
+                    name: Some(field.clone()),
+                    ty: Type::Any,
+                    optional: false,
+                })
+                .collect(),
+            None,
+            Type::Struct(name.to_string()),
+        );
+        self.define(name, constructor, false);
+        self.structs.insert(
+            name.to_string(),
+            StructInfo {
+                fields: fields.to_vec(),
+                methods: HashMap::new(),
+            },
+        );
+
+        // Each method's signature is recorded before any bodies are checked,
+        // so that methods can call each other through `self`.
+        let mut signatures = Vec::new();
+        for method in methods {
+            if let Node::FunctionDecl {
+                name: method_name,
+                params,
+                return_type,
+                body,
+                span,
+                ..
+            } = method
+            {
+                let signature = self.signature(params, return_type);
+                let ty = Type::function(
+                    signature.params.clone(),
+                    signature.rest.clone(),
+                    signature.ret.clone().unwrap_or(Type::Any),
+                );
+                if let Some(info) = self.structs.get_mut(name) {
+                    info.methods.insert(method_name.clone(), ty);
+                }
+                signatures.push((method_name, params, signature, body, span));
+            }
+        }
+        for (method_name, params, signature, body, span) in signatures {
+            self.scopes.push(HashMap::from([(
+                "self".to_string(),
+                Variable {
+                    ty: Type::Struct(name.to_string()),
+                    annotated: true,
+                },
+            )]));
+            let qualified = format!("{}.{}", name, method_name);
+            let ty = self.function(&qualified, params, signature, body, span);
+            self.scopes.pop();
+            if let Some(info) = self.structs.get_mut(name) {
+                info.methods.insert(method_name.clone(), ty);
+            }
+        }
+    }
+
+    /// Resolves the annotations of a function's parameters and return type,
+    /// checking that any defaults match their parameter's annotation.
+    fn signature(&mut self, params: &[Param], return_type: &Option<TypeAnnotation>) -> Signature {
+        let mut signature = Signature {
+            params: Vec::new(),
+            rest: None,
+            ret: return_type.as_ref().map(|ret| self.resolve(ret)),
+        };
+        for param in params {
+            let ty = match &param.ty {
+                Some(annotation) => self.resolve(annotation),
+                None => Type::Any,
+            };
+            if param.variadic {
+                signature.rest = Some(ty);
+                continue;
+            }
+            if let Some(default) = &param.default {
+                let default_type = self.expr(default);
+                if !ty.accepts(&default_type) {
+                    self.error(
+                        ErrorType::TypeError,
+                        &format!(
+                            "Cannot use a value of type '{}' as the default for '{}', which has type '{}'",
+                            default_type, param.name, ty
+                        ),
+                        default.span(),
+                    );
+                }
+            }
+            signature.params.push(ParamType {
+                name: Some(param.name.clone()),
+                ty,
+                optional: param.default.is_some(),
+            });
+        }
+        signature
+    }
+
+    /// Checks the body of a function, returning the function's type. Without
+    /// a return type annotation, the return type is inferred from the
+    /// function's `return` statements.
+    fn function(
+        &mut self,
+        name: &str,
+        params: &[Param],
+        signature: Signature,
+        body: &Node,
+        span: &Span,
+    ) -> Type {
+        let mut scope = HashMap::new();
+        for (param, ty) in params
+            .iter()
+            .filter(|param| !param.variadic)
+            .zip(&signature.params)
+        {
+            let variable = Variable {
+                ty: ty.ty.clone(),
+                annotated: param.ty.is_some(),
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+        if let (Some(param), Some(rest)) =
+            (params.iter().find(|param| param.variadic), &signature.rest)
+        {
+            let variable = Variable {
+                ty: Type::list(rest.clone()),
+                annotated: param.ty.is_some(),
+            };
+            scope.insert(param.name.clone(), variable);
+        }
+
+        self.scopes.push(scope);
+        self.functions.push(FunctionContext {
+            name: name.to_string(),
+            declared: signature.ret.clone(),
+            returns: Vec::new(),
+        });
+        self.statement(body);
+        let context = self.functions.pop();
+        self.scopes.pop();
+
+        if let Some(ret) = &signature.ret {
+            if !ends_with_return(body) && !ret.accepts(&Type::None) {
+                let message = format!(
+                    "'{}' returns '{}', but can reach the end of its body without returning a value",
+                    name, ret
+                );
+                self.error(ErrorType::TypeError, &message, span);
+            }
+        }
+
+        let ret = match (signature.ret, context) {
+            (Some(ret), _) => ret,
+            (None, Some(context)) => {
+                let mut returns = context.returns;
+                if !ends_with_return(body) {
+                    returns.push(Type::None);
+                }
+                returns
+                    .into_iter()
+                    .reduce(|a, b| a.join(&b))
+                    .unwrap_or(Type::None)
+            }
+            (None, None) => Type::Any,
+        };
+        Type::function(signature.params, signature.rest, ret)
+    }
+
+    /// Defines the names bound by `pattern`, which is matched against a value
+    /// of type `ty`.
+    fn bind_pattern(&mut self, pattern: &Pattern, ty: &Type) {
+        match pattern {
+            Pattern::Binding { name, .. } => self.define(name, ty.clone(), false),
+            Pattern::Rest {
+                name: Some(name), ..
+            } => {
+                let element = ty.element().unwrap_or(Type::Any);
+                self.define(name, Type::list(element), false);
+            }
+            Pattern::List { elements, .. } => {
+                let element = match ty {
+                    Type::List(element) => element.as_ref().clone(),
+                    _ => Type::Any,
+                };
+                for pattern in elements {
+                    match pattern {
+                        Pattern::Rest { .. } => {
+                            self.bind_pattern(pattern, &Type::list(element.clone()))
+                        }
+                        _ => self.bind_pattern(pattern, &element),
+                    }
+                }
+            }
+            Pattern::Struct { args, kwargs, .. } => {
+                for pattern in args.iter().chain(kwargs.iter().map(|(_, pattern)| pattern)) {
+                    self.bind_pattern(pattern, &Type::Any);
+                }
+            }
+            Pattern::Wildcard { .. }
+            | Pattern::Literal { .. }
+            | Pattern::Rest { name: None, .. } => {}
+        }
+    }
+
+    /// Infers the type of `node`, which is used where a value of type
+    /// `expected` is expected. Each element of a list literal is checked
+    /// against the expected element type, as the join of mismatched elements
+    /// would otherwise be `list[any]`, which is accepted anywhere.
+    fn expr_as(&mut self, node: &Node, expected: &Type) -> Type {
+        let (Node::List { elements, .. }, Type::List(element)) = (node, expected) else {
+            return self.expr(node);
+        };
+        for node in elements {
+            let ty = self.expr_as(node, element);
+            if !element.accepts(&ty) {
+                self.error(
+                    ErrorType::TypeError,
+                    &format!(
+                        "Cannot use a value of type '{}' as an element of '{}'",
+                        ty, expected
+                    ),
+                    node.span(),
+                );
+            }
+        }
+        expected.clone()
+    }
+
+    fn expr(&mut self, node: &Node) -> Type {
+        match node {
+            Node::Literal { value, .. } => literal_type(value),
+            Node::Identifier { name, .. } => match self.lookup(name) {
+                Some(variable) => variable.ty.clone(),
+                None => builtin_type(name),
+            },
+            Node::List { elements, .. } => {
+                let mut element: Option<Type> = None;
+                for node in elements {
+                    let ty = self.expr(node);
+                    element = Some(match element {
+                        Some(element) => element.join(&ty),
+                        None => ty,
+                    });
+                }
+                Type::list(element.unwrap_or(Type::Any))
+            }
+            Node::Dict { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+                Type::Dict
+            }
+            Node::Index {
+                object,
+                index,
+                span,
+            } => {
+                let object_type = self.expr(object);
+                let index_type = self.expr(index);
+                self.index(&object_type, &index_type, index, span)
+            }
+            Node::Slice {
+                object,
+                start,
+                stop,
+                step,
+                span,
+            } => {
+                let object_type = self.expr(object);
+                for bound in [start, stop, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+                match object_type {
+                    Type::List(_) | Type::Str | Type::Range | Type::Any => object_type,
+                    _ => self.not_subscriptable(&object_type, span),
+                }
+            }
+            Node::Field { object, name, span } => {
+                let object_type = self.expr(object);
+                self.field(&object_type, name, span)
+            }
+            Node::UnaryOp { op, child, span } => {
+                let child = self.expr(child);
+                match (op, &child) {
+                    (Operator::Not, _) => Type::Bool,
+                    (Operator::Sub, Type::Int | Type::Float | Type::Decimal | Type::Any) => child,
+                    _ => {
+                        self.error(
+                            ErrorType::TypeError,
+                            &format!("Unsupported operand type for unary '{}': '{}'", op, child),
+                            span,
+                        );
+                        Type::Any
+                    }
+                }
+            }
+            Node::BinaryOp { op, lhs, rhs, span } => {
+                let lhs = self.expr(lhs);
+                let rhs = self.expr(rhs);
+                self.binary(op, &lhs, &rhs, span)
+            }
+            Node::Lambda {
+                params,
+                return_type,
+                body,
+                span,
+            } => {
+                let signature = self.signature(params, return_type);
+                self.function("<lambda>", params, signature, body, span)
+            }
+            Node::FunctionCall {
+                function,
+                args,
+                kwargs,
+                span,
+            } => self.call(function, args, kwargs, span),
+            _ => Type::Any,
+        }
+    }
+
+    /// The type of the result of `lhs op rhs`, following `core::ops`.
+    fn binary(&mut self, op: &Operator, lhs: &Type, rhs: &Type, span: &Span) -> Type {
+        let result = match op {
+            Operator::Eq | Operator::NotEq | Operator::And | Operator::Or => Some(Type::Bool),
+            Operator::Less | Operator::LessEq | Operator::More | Operator::MoreEq => {
+                match (lhs, rhs) {
+                    (Type::Any, _) | (_, Type::Any) | (Type::Str, Type::Str) => Some(Type::Bool),
+                    _ if lhs.is_numeric() && rhs.is_numeric() => Some(Type::Bool),
+                    _ => None,
+                }
+            }
+            _ => {
+                match (op, lhs, rhs) {
+                    (_, Type::Any, _) | (_, _, Type::Any) => Some(Type::Any),
+                    (Operator::Not, _, _) => None,
+                    (_, Type::Int, Type::Int) => Some(Type::Int),
+                    (_, Type::Decimal, Type::Decimal | Type::Int)
+                    | (_, Type::Int, Type::Decimal) => Some(Type::Decimal),
+                    // Decimals can't be mixed with floats
+                    (_, Type::Decimal, _) | (_, _, Type::Decimal) => None,
+                    _ if lhs.is_numeric() && rhs.is_numeric() => Some(Type::Float),
+                    (Operator::Add, Type::Str, Type::Str) => Some(Type::Str),
+                    (Operator::Mul, Type::Str, Type::Int)
+                    | (Operator::Mul, Type::Int, Type::Str) => Some(Type::Str),
+                    (Operator::Add, Type::List(a), Type::List(b)) => Some(Type::list(a.join(b))),
+                    (Operator::Mul, Type::List(_), Type::Int) => Some(lhs.clone()),
+                    (Operator::Mul, Type::Int, Type::List(_)) => Some(rhs.clone()),
+                    _ => None,
+                }
+            }
+        };
+        result.unwrap_or_else(|| {
+            self.error(
+                ErrorType::TypeError,
+                &format!(
+                    "Unsupported operand types for '{}': '{}' and '{}'",
+                    op, lhs, rhs
+                ),
+                span,
+            );
+            Type::Any
+        })
+    }
+
+    /// The type of `object[index]`.
+    fn index(&mut self, object: &Type, index_type: &Type, index: &Node, span: &Span) -> Type {
+        let (container, element) = match object {
+            Type::List(element) => ("list", element.as_ref().clone()),
+            Type::Str => ("str", Type::Str),
+            Type::Range => ("range", Type::Int),
+            Type::Dict | Type::Any => return Type::Any,
+            _ => return self.not_subscriptable(object, span),
+        };
+        if !Type::Int.accepts(index_type) {
+            self.error(
+                ErrorType::TypeError,
+                &format!(
+                    "{} indices must be integers, not '{}'",
+                    container, index_type
+                ),
+                index.span(),
+            );
+        }
+        element
+    }
+
+    fn not_subscriptable(&mut self, object: &Type, span: &Span) -> Type {
+        self.error(
+            ErrorType::TypeError,
+            &format!("'{}' object is not subscriptable", object),
+            span,
+        );
+        Type::Any
+    }
+
+    /// The type of `object.name`. Fields can hold any value, while methods
+    /// (including the builtin methods of lists) have the type of their
+    /// signature.
+    fn field(&mut self, object: &Type, name: &str, span: &Span) -> Type {
+        let message = match object {
+            Type::Any => return Type::Any,
+            Type::Struct(struct_name) => match self.structs.get(struct_name) {
+                Some(info) if info.fields.iter().any(|field| field == name) => return Type::Any,
+                Some(info) => match info.methods.get(name) {
+                    Some(method) => return method.clone(),
+                    None => format!("'{}' has no field '{}'", struct_name, name),
+                },
+                None => return Type::Any,
+            },
+            Type::List(element) => match list_method_type(element, name) {
+                Some(method) => return method,
+                None => format!("'{}' object has no field '{}'", object, name),
+            },
+            _ => format!("'{}' object has no field '{}'", object, name),
+        };
+        self.error(ErrorType::AttributeError, &message, span);
+        Type::Any
+    }
+
+    /// Checks the arguments of a call against the parameters of the function
+    /// called, returning the type of the value it returns.
+    fn call(
+        &mut self,
+        function: &Node,
+        args: &[Node],
+        kwargs: &[(String, Node)],
+        span: &Span,
+    ) -> Type {
+        let callee = self.expr(function);
+        let args: Vec<(Type, &Span)> = args
+            .iter()
+            .map(|arg| (self.expr(arg), arg.span()))
+            .collect();
+        let kwargs: Vec<(&str, Type, &Span)> = kwargs
+            .iter()
+            .map(|(name, value)| (name.as_str(), self.expr(value), value.span()))
+            .collect();
+
+        let function_type = match callee {
+            Type::Function(function_type) => function_type,
+            Type::Any => return Type::Any,
+            other => {
+                self.error(
+                    ErrorType::TypeError,
+                    &format!("'{}' object is not callable", other),
+                    function.span(),
+                );
+                return Type::Any;
+            }
+        };
+        self.check_arity(function, &function_type, args.len(), &kwargs, span);
+        for (i, (ty, span)) in args.iter().enumerate() {
+            let (expected, param) = match function_type.params.get(i) {
+                Some(param) => (&param.ty, param.name.clone()),
+                None => match &function_type.rest {
+                    Some(rest) => (rest, None),
+                    None => break,
+                },
+            };
+            if !expected.accepts(ty) {
+                let param = match param {
+                    Some(name) => format!("the argument '{}'", name),
+                    None => format!("argument {}", i + 1),
+                };
+                self.argument_mismatch(ty, &param, expected, span);
+            }
+        }
+        for (name, ty, span) in kwargs {
+            let param = function_type
+                .params
+                .iter()
+                .find(|param| param.name.as_deref() == Some(name));
+            if let Some(param) = param {
+                if !param.ty.accepts(&ty) {
+                    let expected = param.ty.clone();
+                    self.argument_mismatch(
+                        &ty,
+                        &format!("the argument '{}'", name),
+                        &expected,
+                        span,
+                    );
+                }
+            }
+        }
+        function_type.ret.clone()
+    }
+
+    /// Checks that a call gives a function no more positional arguments than
+    /// it takes, and a value for each parameter without a default. Parameters
+    /// of function type annotations have no names, so may have been given as
+    /// keyword arguments which can't be matched to them.
+    fn check_arity(
+        &mut self,
+        function: &Node,
+        function_type: &FunctionType,
+        args: usize,
+        kwargs: &[(&str, Type, &Span)],
+        span: &Span,
+    ) {
+        let callee = match function {
+            Node::Identifier { name, .. } | Node::Field { name, .. } => format!("{}()", name),
+            _ => "The function".to_string(),
+        };
+        let params = &function_type.params;
+        if function_type.rest.is_none() && args > params.len() {
+            let required = params.iter().filter(|param| !param.optional).count();
+            let expected = if required == params.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, params.len())
+            };
+            self.error(
+                ErrorType::TypeError,
+                &format!(
+                    "{} takes {} arguments but {} were given",
+                    callee, expected, args
+                ),
+                span,
+            );
+            return;
+        }
+        for (i, param) in params.iter().enumerate().skip(args) {
+            if param.optional {
+                continue;
+            }
+            let missing = match &param.name {
+                Some(name) if kwargs.iter().any(|(keyword, ..)| keyword == name) => continue,
+                Some(name) => format!("the argument '{}'", name),
+                None if !kwargs.is_empty() => continue,
+                None => format!("argument {}", i + 1),
+            };
+            self.error(
+                ErrorType::TypeError,
+                &format!("{} is missing {}", callee, missing),
+                span,
+            );
+        }
+    }
+
+    fn argument_mismatch(&mut self, found: &Type, param: &str, expected: &Type, span: &Span) {
+        self.error(
+            ErrorType::TypeError,
+            &format!(
+                "Cannot pass a value of type '{}' as {}, which has type '{}'",
+                found, param, expected
+            ),
+            span,
+        );
+    }
+}
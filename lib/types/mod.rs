@@ -0,0 +1,144 @@
+//! Static types, inferred for a program without running it by `check`.
+//!
+//! Type annotations are optional, and anything the checker can't work out
+//! has the type `any`, which is compatible with every other type. This means
+//! the checker only reports mismatches it is sure of: a program which passes
+//! can still raise a `TypeError` when it is run.
+
+use std::rc::Rc;
+
+pub mod checker;
+pub use checker::check;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// A value whose type isn't known.
+    Any,
+    None,
+    Bool,
+    Int,
+    Float,
+    Decimal,
+    Str,
+    /// A list whose elements are all of the given type.
+    List(Box<Type>),
+    Dict,
+    Range,
+    Function(Rc<FunctionType>),
+    /// An instance of the struct type with the given name.
+    Struct(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionType {
+    pub params: Vec<ParamType>,
+    /// The type of each extra argument collected by a `*rest` parameter.
+    pub rest: Option<Type>,
+    pub ret: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamType {
+    /// The parameter's name, which function type annotations leave out.
+    pub name: Option<String>,
+    pub ty: Type,
+    /// Whether the parameter has a default, so may be left out of calls.
+    pub optional: bool,
+}
+
+impl Type {
+    pub fn function(params: Vec<ParamType>, rest: Option<Type>, ret: Type) -> Self {
+        Type::Function(Rc::new(FunctionType { params, rest, ret }))
+    }
+
+    pub fn list(element: Type) -> Self {
+        Type::List(Box::new(element))
+    }
+
+    /// Whether a value of type `other` can be used where a value of this type
+    /// is expected. Ints can be used in place of floats and decimals.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Float | Type::Decimal, Type::Int) => true,
+            (Type::List(a), Type::List(b)) => a.accepts(b),
+            (Type::Function(a), Type::Function(b)) => {
+                // The function given must accept every call the expected
+                // function type allows, and return something it allows.
+                b.params.len() >= a.params.len()
+                    && b.params[a.params.len()..]
+                        .iter()
+                        .all(|param| param.optional)
+                    && a.params
+                        .iter()
+                        .zip(&b.params)
+                        .all(|(a, b)| b.ty.accepts(&a.ty) && (b.optional || !a.optional))
+                    && match (&a.rest, &b.rest) {
+                        (Some(a), Some(b)) => b.accepts(a),
+                        (Some(_), None) => false,
+                        (None, _) => true,
+                    }
+                    && a.ret.accepts(&b.ret)
+            }
+            _ => self == other,
+        }
+    }
+
+    /// The most specific type which both `self` and `other` can be used as,
+    /// e.g. for the elements of a list containing both.
+    pub fn join(&self, other: &Type) -> Type {
+        match (self, other) {
+            _ if self == other => self.clone(),
+            (Type::Int, Type::Float) | (Type::Float, Type::Int) => Type::Float,
+            (Type::Int, Type::Decimal) | (Type::Decimal, Type::Int) => Type::Decimal,
+            (Type::List(a), Type::List(b)) => Type::list(a.join(b)),
+            _ => Type::Any,
+        }
+    }
+
+    /// The type of the values produced by iterating over a value of this
+    /// type, or `None` if it can't be iterated over.
+    pub fn element(&self) -> Option<Type> {
+        match self {
+            Type::List(element) => Some(element.as_ref().clone()),
+            Type::Str => Some(Type::Str),
+            Type::Range => Some(Type::Int),
+            Type::Any | Type::Dict => Some(Type::Any),
+            _ => None,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Decimal)
+    }
+}
+
+impl std::fmt::Display for Type {
+    /// Formats the type the way it would be written in an annotation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Any => write!(f, "any"),
+            Type::None => write!(f, "none"),
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Decimal => write!(f, "decimal"),
+            Type::Str => write!(f, "str"),
+            Type::List(element) => write!(f, "list[{}]", element),
+            Type::Dict => write!(f, "dict"),
+            Type::Range => write!(f, "range"),
+            Type::Function(function) => {
+                let mut params: Vec<String> = function
+                    .params
+                    .iter()
+                    .map(|param| param.ty.to_string())
+                    .collect();
+                if let Some(rest) = &function.rest {
+                    params.push(format!("*{}", rest));
+                }
+                write!(f, "fn({}) -> {}", params.join(", "), function.ret)
+            }
+            Type::Struct(name) => write!(f, "{}", name),
+        }
+    }
+}
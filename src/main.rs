@@ -1,40 +1,336 @@
 extern crate sap;
 
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
 use sap::{
     colours::*,
-    errors::error::handle_error,
-    lexer::{token::TokenKind, Lexer},
+    doc::{self, DocError, Format},
+    errors::{
+        error::{handle_error, handle_warning},
+        Error, ErrorType, Warning,
+    },
+    interpreter::{
+        interpreter::{MAX_CALL_DEPTH, STACK_PER_CALL},
+        Interpreter,
+    },
+    lint::{self, Config, Severity},
+    optimizer,
+    parser::{ast::Node, parse_with_warnings},
+    resolver, types,
 };
 
-const FILE: &str = "src/grammar.txt";
+/// The stack size of the thread every subcommand runs on. It is worked out
+/// from the stack `MAX_CALL_DEPTH` nested function calls need, with a margin
+/// of four times that for deeply nested expressions, which the parser, the
+/// checkers and the interpreter all recurse on.
+const STACK_SIZE: usize = MAX_CALL_DEPTH * STACK_PER_CALL * 4;
+
+const USAGE: &str = "\
+Usage:
+    sap <file>                                      Run a program
+    sap parse [--optimize] <file>                   Parse a file and print its syntax tree
+    sap check [--types] <file>                      Check a file for errors without running it
+    sap lint [--config <file>] <file>               Check a file against the lint rules
+    sap doc [--format md|html] [--out <dir>] <file>...  Generate a reference from doc comments";
+
+fn report_error(error: Error, src: &str, file_name: &str) {
+    println!(
+        "{style_bold}{colour_red}Error {colour_reset}aborting execution due to error{style_reset}"
+    );
+    handle_error(error, src, file_name);
+}
+
+fn report_warnings(warnings: &[Warning], src: &str, file_name: &str) {
+    for warning in warnings {
+        handle_warning(warning, src, file_name);
+    }
+}
+
+/// Parses a file, reporting any warnings about it.
+fn parse_source(src: &str, file_name: &str) -> Result<Node, Error> {
+    let (program, warnings) = parse_with_warnings(src)?;
+    report_warnings(&warnings, src, file_name);
+    Ok(program)
+}
 
-fn main() {
-    let input = std::fs::read_to_string(FILE).unwrap();
+fn usage_error(msg: &str) -> ExitCode {
+    eprintln!(
+        "{colour_red}{style_bold}error{colour_reset}{style_reset}: {}\n\n{}",
+        msg, USAGE
+    );
+    ExitCode::FAILURE
+}
 
-    let now = std::time::Instant::now();
+fn read_file(file: &str) -> Result<String, ExitCode> {
+    std::fs::read_to_string(file)
+        .map_err(|error| usage_error(&format!("could not read '{}': {}", file, error)))
+}
 
-    let mut lex = Lexer::new(input.chars());
-    let mut i = 1;
+fn parse_file(file: &str, optimize: bool) -> ExitCode {
+    let input = match read_file(file) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
 
-    // while token.is_ok_and(|token| token.kind != TokenKind::Eof)
-    loop {
-        let token = lex.get_next_token();
-        println!("{:?}", token);
-        match token {
-            Ok(token) => {
-                if token.kind == TokenKind::Eof {
-                    break;
-                }
+    match parse_source(&input, file) {
+        Ok(mut program) => {
+            if optimize {
+                optimizer::optimize(&mut program);
             }
-            Err(error) => {
-                println!("{style_bold}{colour_red}Error {colour_reset}aborting execution due to error{style_reset}");
-                handle_error(error, &input, FILE);
-                break;
+            println!("{:#?}", program);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            report_error(error, &input, file);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_file(file: &str) -> ExitCode {
+    let input = match read_file(file) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
+
+    let result = parse_source(&input, file).and_then(|mut program| {
+        optimizer::optimize(&mut program);
+        Interpreter::new().run(&program)
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            report_error(error, &input, file);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn check_file(args: &[String]) -> ExitCode {
+    let mut check_types = false;
+    let mut file = None;
+    for arg in args {
+        match arg.as_str() {
+            "--types" => check_types = true,
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => return usage_error("check expects a single file"),
+        }
+    }
+    let Some(file) = file else {
+        return usage_error("no file given");
+    };
+    let input = match read_file(file) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
+
+    let (mut errors, mut warnings) = match parse_with_warnings(&input) {
+        Ok((program, warnings)) => {
+            let resolution = resolver::resolve(&program);
+            let mut errors = resolution.errors;
+            if check_types {
+                errors.extend(types::check(&program));
             }
+            (errors, [warnings, resolution.warnings].concat())
         }
+        Err(error) => (vec![error], Vec::new()),
+    };
+    let count = errors.len() + warnings.len();
+    if count == 0 {
+        println!("No problems found in {}", file);
+        return ExitCode::SUCCESS;
+    }
+    errors.sort_by_key(|error| error.token.span.start);
+    warnings.sort_by_key(|warning| warning.span.start);
+    report_warnings(&warnings, &input, file);
+    let failed = !errors.is_empty();
+    for error in errors {
+        handle_error(error, &input, file);
+    }
+    let colour = if failed { colour_red } else { colour_yellow };
+    println!(
+        "{style_bold}{colour}Found {} problem{} in {}{colour_reset}{style_reset}",
+        count,
+        if count == 1 { "" } else { "s" },
+        file
+    );
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
 
-        i += 1;
+fn lint_file(args: &[String]) -> ExitCode {
+    let mut config_path = None;
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => match args.next() {
+                Some(path) => config_path = Some(PathBuf::from(path)),
+                None => return usage_error("--config expects a file"),
+            },
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => return usage_error("lint expects a single file"),
+        }
+    }
+    let Some(file) = file else {
+        return usage_error("no file given");
+    };
+    let input = match read_file(file) {
+        Ok(input) => input,
+        Err(code) => return code,
+    };
+    // Without `--config`, the nearest project file is used if there is one.
+    let config = match config_path.or_else(|| lint::find_config(Path::new(file))) {
+        Some(path) => match Config::load(&path) {
+            Ok(config) => config,
+            Err(message) => return usage_error(&message),
+        },
+        None => Config::default(),
+    };
+
+    let program = match parse_with_warnings(&input) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            report_error(error, &input, file);
+            return ExitCode::FAILURE;
+        }
+    };
+    let lints = lint::lint(&program, &input, &config);
+    if lints.is_empty() {
+        println!("No problems found in {}", file);
+        return ExitCode::SUCCESS;
+    }
+    let count = lints.len();
+    let mut failed = false;
+    for lint in lints {
+        let message = format!("{} [{}]", lint.message, lint.rule);
+        match lint.severity {
+            Severity::Error => {
+                failed = true;
+                handle_error(
+                    Error::new(ErrorType::LintError, &message, lint.span),
+                    &input,
+                    file,
+                )
+            }
+            _ => handle_warning(&Warning::new(&message, lint.span), &input, file),
+        }
+    }
+    let colour = if failed { colour_red } else { colour_yellow };
+    println!(
+        "{style_bold}{colour}Found {} problem{} in {}{colour_reset}{style_reset}",
+        count,
+        if count == 1 { "" } else { "s" },
+        file
+    );
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn generate_docs(args: &[String]) -> ExitCode {
+    let mut format = Format::Markdown;
+    let mut out_dir = PathBuf::from("docs");
+    let mut entries = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().and_then(|name| Format::from_name(name)) {
+                Some(f) => format = f,
+                None => return usage_error("--format expects 'md' or 'html'"),
+            },
+            "--out" => match args.next() {
+                Some(dir) => out_dir = PathBuf::from(dir),
+                None => return usage_error("--out expects a directory"),
+            },
+            _ => entries.push(PathBuf::from(arg)),
+        }
+    }
+    if entries.is_empty() {
+        return usage_error("no modules given");
     }
 
-    println!("Processed {} tokens in {}ms", i, now.elapsed().as_millis());
+    let modules = match doc::load_modules(&entries) {
+        Ok(modules) => modules,
+        Err(DocError::Io { path, error }) => {
+            return usage_error(&format!("could not read '{}': {}", path.display(), error))
+        }
+        Err(DocError::Parse {
+            path,
+            source,
+            error,
+        }) => {
+            report_error(*error, &source, &path.to_string_lossy());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut pages = vec![(
+        format!("index.{}", format.extension()),
+        doc::render::render_index(&modules, format),
+    )];
+    for module in &modules {
+        pages.push((
+            doc::render::page_name(&module.name, format),
+            doc::render::render_module(module, &modules, format),
+        ));
+    }
+
+    let written = std::fs::create_dir_all(&out_dir).and_then(|_| {
+        pages.iter().try_for_each(|(name, page)| {
+            let path = out_dir.join(name);
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, page)
+        })
+    });
+    if let Err(error) = written {
+        return usage_error(&format!(
+            "could not write to '{}': {}",
+            out_dir.display(),
+            error
+        ));
+    }
+
+    println!(
+        "Documented {} modules in {}",
+        modules.len(),
+        out_dir.display()
+    );
+    ExitCode::SUCCESS
+}
+
+fn run_command(args: &[String]) -> ExitCode {
+    match args.first().map(String::as_str) {
+        Some("doc") => generate_docs(&args[1..]),
+        Some("parse") if args.len() == 2 => parse_file(&args[1], false),
+        Some("parse") if args.len() == 3 && args[1] == "--optimize" => parse_file(&args[2], true),
+        Some("check") => check_file(&args[1..]),
+        Some("lint") => lint_file(&args[1..]),
+        Some(file) if args.len() == 1 => run_file(file),
+        _ => usage_error("expected a file or a subcommand"),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Everything runs on a thread with a known stack size, as the parser,
+    // the checkers and the interpreter all recurse on the syntax tree.
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || run_command(&args))
+        .and_then(|handle| {
+            handle
+                .join()
+                .map_err(|_| std::io::Error::other("sap panicked"))
+        })
+        .unwrap_or(ExitCode::FAILURE)
 }
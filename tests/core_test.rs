@@ -0,0 +1,215 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::core::{
+        heap, BigInt, Bool, ConversionErrorKind, Decimal, DecimalContext, Dict, Float, Int, Key,
+        List, NoneType, Object, Rounding, Str,
+    };
+
+    #[test]
+    fn none_conversions() {
+        let object: Object = NoneType.into();
+        assert!(matches!(object, Object::None));
+        assert_eq!(object.to_str().value, "none");
+        assert!(!object.to_bool().value);
+        assert!(!object.is_truthy());
+    }
+
+    #[test]
+    fn big_int_arithmetic() {
+        let big = |src: &str| BigInt::parse(src).unwrap();
+        let a = big("-123456789012345678901234567890");
+        let b = big("987654321098765432");
+        assert_eq!(a.add(&b).to_string(), "-123456789011358024580135802458");
+        assert_eq!(a.sub(&b).to_string(), "-123456789013333333222333333322");
+        assert_eq!(
+            a.mul(&b).to_string(),
+            "-121932631137021795212620027521140070120989178480"
+        );
+        // Division rounds towards zero, and the remainder has the sign of
+        // the dividend
+        let (quotient, remainder) = a.div_rem(&b).unwrap();
+        assert_eq!(quotient.to_string(), "-124999998860");
+        assert_eq!(remainder.to_string(), "-925925953827160370");
+        assert!(a.div_rem(&big("0")).is_none());
+
+        assert!(a < b && b < a.neg());
+        assert_eq!(big("-0"), big("0"));
+        assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(
+            BigInt::from_f64(-1e20).unwrap().to_string(),
+            "-100000000000000000000"
+        );
+        assert!(BigInt::from_f64(f64::NAN).is_none());
+    }
+
+    #[test]
+    fn int_conversions() {
+        assert!(matches!(
+            str("100000000000000000000").to_int(),
+            Ok(Int::Big(_))
+        ));
+        let int = Object::from(Float { value: -2.9 }).to_int().unwrap();
+        assert_eq!(int, Int::new(-2));
+        assert_eq!(Object::from(int).to_float().unwrap().value, -2.0);
+        // Ints are stored in 64 bits whenever they fit
+        assert_eq!(Int::from_big(BigInt::from_i64(7)), Int::Small(7));
+    }
+
+    #[test]
+    fn decimal_division_rounds() {
+        let decimal = |src: &str| Decimal::parse(src).unwrap();
+        let divide = |a: &str, b: &str, precision, rounding| {
+            let context = DecimalContext {
+                precision,
+                rounding,
+            };
+            decimal(a).div(&decimal(b), &context).unwrap().to_string()
+        };
+        assert_eq!(divide("1", "3", 5, Rounding::HalfEven), "0.33333");
+        assert_eq!(divide("2", "3", 5, Rounding::Down), "0.66666");
+        assert_eq!(divide("-2", "3", 5, Rounding::Floor), "-0.66667");
+        assert_eq!(divide("-2", "3", 5, Rounding::Ceiling), "-0.66666");
+        assert_eq!(divide("0.125", "1", 2, Rounding::HalfEven), "0.12");
+        assert_eq!(divide("0.135", "1", 2, Rounding::HalfEven), "0.14");
+        assert_eq!(divide("0.125", "1", 2, Rounding::HalfUp), "0.13");
+        assert_eq!(divide("0.125", "1", 2, Rounding::HalfDown), "0.12");
+        assert_eq!(divide("0.121", "1", 2, Rounding::Up), "0.13");
+        // Trailing zeros are removed, down to the dividend's extra digits
+        assert_eq!(divide("1", "4", 28, Rounding::HalfEven), "0.25");
+        assert_eq!(divide("10.00", "4", 28, Rounding::HalfEven), "2.50");
+        assert_eq!(divide("100", "0.5", 28, Rounding::HalfEven), "200");
+        assert!(decimal("1")
+            .div(&decimal("0.0"), &DecimalContext::default())
+            .is_none());
+    }
+
+    #[test]
+    fn decimal_conversions() {
+        let decimal = Object::from(Float { value: 0.1 }).to_decimal().unwrap();
+        assert_eq!(decimal.to_string(), "0.1");
+        let decimal = str("-0.05").to_decimal().unwrap();
+        assert_eq!(decimal.to_string(), "-0.05");
+        assert_eq!(decimal.to_f64(), -0.05);
+        let int = Object::from(Decimal::parse("-19.99").unwrap()).to_int();
+        assert_eq!(int.unwrap(), Int::new(-19));
+        let decimal = Object::from(Int::new(3)).to_decimal().unwrap();
+        assert_eq!(decimal, Decimal::parse("3.00").unwrap());
+        assert_eq!(Object::from(decimal).to_str().value, "3");
+        let nan = Object::from(Float { value: f64::NAN }).to_decimal();
+        assert!(nan.is_err());
+    }
+
+    #[test]
+    fn conversion_errors() {
+        let error = str("12a").to_int().unwrap_err();
+        assert_eq!(error.kind, ConversionErrorKind::Invalid);
+        assert_eq!(error.target, "int");
+        assert!(matches!(&error.value, Object::Str(s) if s.value == "12a"));
+        assert_eq!(error.to_string(), "Cannot convert \"12a\" to int");
+
+        let list: Object = List::new(vec![Int::new(1).into()]).into();
+        let error = list.to_float().unwrap_err();
+        assert_eq!(error.kind, ConversionErrorKind::Unsupported);
+        assert_eq!(error.to_string(), "Cannot convert 'list' to float");
+        let error = Object::from(Int::new(1)).to_list().unwrap_err();
+        assert_eq!(error.to_string(), "Cannot convert 'int' to list");
+        let error = Object::from(Float {
+            value: f64::INFINITY,
+        })
+        .to_int();
+        assert_eq!(error.unwrap_err().kind, ConversionErrorKind::Invalid);
+
+        // Every object has a string and a truth value
+        assert_eq!(list.to_str().value, "[1]");
+        assert!(list.to_bool().value);
+        assert_eq!(str(" 2.5 ").to_float().unwrap().value, 2.5);
+        assert_eq!(str("ab").to_list().unwrap().to_string(), "[\"a\", \"b\"]");
+    }
+
+    fn str(value: &str) -> Object {
+        Str {
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn display_and_repr() {
+        let dict = Dict::new();
+        dict.insert(Key::Str("a\"b".to_string()), Float { value: 2.0 }.into());
+        let list: Object = List::new(vec![
+            Int::new(1).into(),
+            str("one"),
+            List::new(vec![Decimal::parse("1.50").unwrap().into(), Object::None]).into(),
+            dict.into(),
+        ])
+        .into();
+        // Strings are only quoted inside containers when displayed
+        assert_eq!(str("one").to_string(), "one");
+        assert_eq!(str("one").repr(), "\"one\"");
+        assert_eq!(
+            list.to_string(),
+            r#"[1, "one", [1.50d, none], {"a\"b": 2.0}]"#
+        );
+        assert_eq!(list.repr(), list.to_string());
+        assert_eq!(format!("{:?}", list), list.repr());
+
+        let decimal: Object = Decimal::parse("19.99").unwrap().into();
+        assert_eq!(decimal.to_string(), "19.99");
+        assert_eq!(decimal.repr(), "19.99d");
+        assert_eq!(format!("{}", Float { value: 3.0 }), "3.0");
+        assert_eq!(format!("{:?}", Bool { value: true }), "true");
+        assert_eq!(format!("{}", NoneType), "none");
+    }
+
+    #[test]
+    fn list_equality_and_sharing() {
+        let inner = List::new(vec![Int::new(1).into(), Float { value: 2.0 }.into()]);
+        let list = List::new(vec![inner.clone().into(), str("a")]);
+        // Equality is deep, and numbers are compared by value
+        let other = List::new(vec![
+            List::new(vec![Float { value: 1.0 }.into(), Int::new(2).into()]).into(),
+            str("a"),
+        ]);
+        assert_eq!(list, other);
+        assert_ne!(list, inner);
+
+        // Clones refer to the same list, while copies are new lists which
+        // share their elements
+        let alias = list.clone();
+        let copy = list.copy();
+        alias.push(Object::None);
+        assert!(alias.ptr_eq(&list));
+        assert_eq!(list.len(), 3);
+        assert_eq!(copy.len(), 2);
+        inner.push(Object::None);
+        assert_eq!(copy.get(0), Some(inner.into()));
+    }
+
+    #[test]
+    fn cyclic_containers() {
+        let list = List::new(vec![Int::new(1).into()]);
+        list.push(list.clone().into());
+        let dict = Dict::new();
+        dict.insert(Key::Int(Int::new(0)), dict.clone().into());
+        dict.insert(Key::Int(Int::new(1)), list.clone().into());
+        assert_eq!(list.to_string(), "[1, [...]]");
+        assert_eq!(dict.to_string(), "{0: {...}, 1: [1, [...]]}");
+
+        // Comparing cyclic lists terminates
+        let other = List::new(vec![Int::new(1).into()]);
+        other.push(other.clone().into());
+        assert_eq!(list, other);
+
+        // Containers only kept alive by each other are freed by collecting
+        let live = heap::live();
+        drop((list, other, dict));
+        assert_eq!(heap::live(), live);
+        assert_eq!(heap::collect(&[]).freed, 3);
+        assert_eq!(heap::live(), live - 3);
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::doc::render::{page_name, render_index, render_module};
+    use sap::doc::{collect_module, load_modules, Format, ModuleDoc};
+
+    const MATHS: &str = "import strings\nimport missing\n\n/// Adds two numbers.\nfn add(a, b)\n    a + b\nend\n\nfn undocumented()\n    1\nend\n";
+
+    fn modules() -> Vec<ModuleDoc> {
+        vec![
+            collect_module("maths", MATHS).unwrap(),
+            collect_module("strings", "/// Repeats `s`\nfn repeat(s, n)\n    s\nend").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn collects_functions_and_imports() {
+        let module = collect_module("maths", MATHS).unwrap();
+        assert_eq!(module.imports, vec!["strings", "missing"]);
+        assert_eq!(module.functions.len(), 2);
+        assert_eq!(module.functions[0].signature(), "fn add(a, b)");
+        assert_eq!(
+            module.functions[0].doc.as_deref(),
+            Some("Adds two numbers.")
+        );
+        assert_eq!(module.functions[1].doc, None);
+    }
+
+    #[test]
+    fn markdown_links_documented_imports() {
+        let modules = modules();
+        let page = render_module(&modules[0], &modules, Format::Markdown);
+        assert!(page.contains("- [`strings`](strings.md)"));
+        assert!(page.contains("- `missing`\n"));
+        assert!(page.contains("### `fn add(a, b)`\n\nAdds two numbers."));
+        assert!(render_index(&modules, Format::Markdown).contains("- [strings](strings.md)"));
+    }
+
+    #[test]
+    fn html_escapes_documentation() {
+        let module = collect_module("m", "/// Returns <b> & \"c\"\nfn f()\n    1\nend").unwrap();
+        let page = render_module(&module, &[], Format::Html);
+        assert!(page.contains("<p>Returns &lt;b&gt; &amp; &quot;c&quot;</p>"));
+    }
+
+    #[test]
+    fn signatures_show_defaults_as_written() {
+        let src = "fn connect(host, port = 80 + 8, *options)\nend";
+        let module = collect_module("net", src).unwrap();
+        assert_eq!(
+            module.functions[0].signature(),
+            "fn connect(host, port = 80 + 8, *options)"
+        );
+    }
+
+    #[test]
+    fn signatures_show_type_annotations() {
+        let src = "fn connect(host: str, port: int = 80, *options: list[str]) -> bool\nend";
+        let module = collect_module("net", src).unwrap();
+        assert_eq!(
+            module.functions[0].signature(),
+            "fn connect(host: str, port: int = 80, *options: list[str]) -> bool"
+        );
+    }
+
+    #[test]
+    fn modules_are_named_by_their_path() {
+        let root = std::env::temp_dir().join(format!("sap-doc-{}", std::process::id()));
+        for dir in ["a", "b"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("a/main.sap"), "import util\nfn main()\nend").unwrap();
+        std::fs::write(root.join("a/util.sap"), "fn first()\nend").unwrap();
+        std::fs::write(root.join("b/util.sap"), "fn second()\nend").unwrap();
+
+        let modules = load_modules(&[root.join("a/main.sap"), root.join("b/util.sap")]);
+        std::fs::remove_dir_all(&root).unwrap();
+        let Ok(modules) = modules else {
+            panic!("failed to load modules");
+        };
+
+        let names: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["a/main", "b/util", "a/util"]);
+        assert_eq!(page_name("a/util", Format::Markdown), "a/util.md");
+        assert_eq!(modules[2].functions[0].name, "first");
+        assert_eq!(modules[1].functions[0].name, "second");
+
+        let page = render_module(&modules[0], &modules, Format::Markdown);
+        assert!(page.starts_with("[Modules](../index.md)"));
+        assert!(page.contains("- [`util`](util.md)"));
+        assert!(render_index(&modules, Format::Markdown).contains("- [b/util](b/util.md)"));
+    }
+}
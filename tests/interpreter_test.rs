@@ -0,0 +1,1252 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::core::heap::{GcSettings, GcStats};
+    use sap::core::{DecimalContext, Int, Object, Overflow, Rounding};
+    use sap::errors::{Error, ErrorType};
+    use sap::interpreter::Interpreter;
+    use sap::parser::parse;
+    use std::cell::RefCell;
+    use std::io::{Cursor, Write};
+    use std::rc::Rc;
+
+    fn run(src: &str) -> Result<Interpreter, Error> {
+        let program = parse(src)?;
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program)?;
+        Ok(interpreter)
+    }
+
+    /// An output which can still be read after it's given to an interpreter.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn get_int(interpreter: &Interpreter, name: &str) -> i64 {
+        match interpreter.get_variable(name) {
+            Some(Object::Int(Int::Small(value))) => value,
+            other => panic!("expected '{}' to be an int, got {:?}", name, other),
+        }
+    }
+
+    #[test]
+    fn arithmetic_and_variables() {
+        let interpreter = run("let x = 10\nlet y = x * 2 + 1 - 3 % 2\nlet z = -(x / 3)").unwrap();
+        assert_eq!(get_int(&interpreter, "y"), 20);
+        assert_eq!(get_int(&interpreter, "z"), -3);
+    }
+
+    #[test]
+    fn return_with_value() {
+        let interpreter = run("fn add(a, b)\n    return a + b\nend\nlet x = add(1, 2)").unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 3);
+    }
+
+    #[test]
+    fn return_unwinds_nested_blocks() {
+        let src = "
+fn find(limit)
+    let i = 0
+    while true do
+        if i == limit then
+            return i * 10
+        end
+        i = i + 1
+    end
+end
+let x = find(4)";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 40);
+    }
+
+    #[test]
+    fn falling_off_the_end_returns_none() {
+        let interpreter =
+            run("fn f()\n    let y = 1\nend\nfn g()\n    return\nend\nlet a = f()\nlet b = g()")
+                .unwrap();
+        assert!(matches!(interpreter.get_variable("a"), Some(Object::None)));
+        assert!(matches!(interpreter.get_variable("b"), Some(Object::None)));
+    }
+
+    #[test]
+    fn recursion() {
+        let src = "fn fact(n)\n    if n <= 1 then return 1 end\n    return n * fact(n - 1)\nend\nlet x = fact(10)";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 3628800);
+    }
+
+    #[test]
+    fn return_outside_function_is_a_syntax_error() {
+        let error = run("let x = 1\nreturn x").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::SyntaxError));
+        assert_eq!(error.token.span.start, 10);
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let error = run("let x = 1 / 0").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::ZeroDivisionError));
+        let error = run("let x = y").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::NameError));
+        let error = run("let x = \"a\" - 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        // Each nested call uses the host's stack, so this runs on a thread
+        // with enough stack space rather than the test harness's default.
+        let error = std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let program = parse("fn f()\n    return f()\nend\nf()").unwrap();
+                let mut interpreter = Interpreter::new();
+                interpreter.set_max_call_depth(50);
+                interpreter.run(&program).err().unwrap()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(matches!(error.error_type, ErrorType::RecursionError));
+    }
+
+    fn get_bool(interpreter: &Interpreter, name: &str) -> bool {
+        match interpreter.get_variable(name) {
+            Some(Object::Bool(bool)) => bool.value,
+            other => panic!("expected '{}' to be a bool, got {:?}", name, other),
+        }
+    }
+
+    #[test]
+    fn none_literal_and_equality() {
+        let src = "fn f()\nend\nlet a = none\nlet b = f() == none\nlet c = none == 0\nlet d = none != false\nlet e = not none";
+        let interpreter = run(src).unwrap();
+        assert!(matches!(interpreter.get_variable("a"), Some(Object::None)));
+        assert!(get_bool(&interpreter, "b"));
+        assert!(!get_bool(&interpreter, "c"));
+        assert!(get_bool(&interpreter, "d"));
+        assert!(get_bool(&interpreter, "e"));
+    }
+
+    #[test]
+    fn none_in_arithmetic_is_a_type_error() {
+        for src in [
+            "let x = none + 1",
+            "let x = 2 * none",
+            "let x = -none",
+            "let x = none < 1",
+        ] {
+            let error = run(src).err().unwrap();
+            assert!(matches!(error.error_type, ErrorType::TypeError), "{}", src);
+        }
+    }
+
+    #[test]
+    fn dict_literals_indexing_and_assignment() {
+        let src = "
+let d = {
+    \"a\": 1,
+    2: {\"nested\": 3},
+    true: 4,
+}
+let a = d[\"a\"]
+d[\"b\"] = a + 1
+d[2][\"nested\"] = 30
+fn update()
+    d[\"a\"] = 10
+end
+update()
+let x = d[\"a\"] + d[\"b\"] + d[2][\"nested\"] + d[true]";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "a"), 1);
+        assert_eq!(get_int(&interpreter, "x"), 10 + 2 + 30 + 4);
+        match interpreter.get_variable("d") {
+            Some(Object::Dict(dict)) => {
+                let keys: Vec<String> = dict.keys().iter().map(|key| key.to_string()).collect();
+                assert_eq!(keys, vec!["\"a\"", "2", "true", "\"b\""]);
+            }
+            other => panic!("expected a dict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dict_equality_ignores_order() {
+        let interpreter =
+            run("let x = {1: 2, 3: 4} == {3: 4, 1: 2}\nlet y = {1: 2} == {1: 3}\nlet z = {1: 2} == {true: 2}")
+                .unwrap();
+        assert!(get_bool(&interpreter, "x"));
+        assert!(!get_bool(&interpreter, "y"));
+        assert!(!get_bool(&interpreter, "z"));
+    }
+
+    #[test]
+    fn dict_errors() {
+        let error = run("let d = {\"a\": 1}\nlet x = d[\"b\"]").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::KeyError));
+        assert_eq!(error.message, "Key \"b\" not found");
+        assert_eq!(error.token.span.start, 25);
+
+        let error = run("let d = {[1]: 1}").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        assert_eq!(error.token.span.start, 9);
+
+        let error = run("let d = {}\nd[1.5] = 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+
+        let error = run("let d = {}\nd[1][2] = 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::KeyError));
+
+        let error = run("f() = 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::SyntaxError));
+    }
+
+    fn get_str(interpreter: &Interpreter, name: &str) -> String {
+        match interpreter.get_variable(name) {
+            Some(Object::Str(string)) => string.value,
+            other => panic!("expected '{}' to be a str, got {:?}", name, other),
+        }
+    }
+
+    fn get_ints(interpreter: &Interpreter, name: &str) -> Vec<i64> {
+        match interpreter.get_variable(name) {
+            Some(Object::List(list)) => list
+                .to_vec()
+                .into_iter()
+                .map(|element| match element {
+                    Object::Int(Int::Small(value)) => value,
+                    other => panic!("expected an int, got {:?}", other),
+                })
+                .collect(),
+            other => panic!("expected '{}' to be a list, got {:?}", name, other),
+        }
+    }
+
+    #[test]
+    fn list_and_string_indexing() {
+        let src = "let xs = [1, 2, 3]\nlet a = xs[0]\nlet b = xs[-1]\nlet s = \"héllo\"\nlet c = s[1]\nlet d = s[-1]";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "a"), 1);
+        assert_eq!(get_int(&interpreter, "b"), 3);
+        assert_eq!(get_str(&interpreter, "c"), "é");
+        assert_eq!(get_str(&interpreter, "d"), "o");
+    }
+
+    #[test]
+    fn slicing() {
+        let src = "
+let xs = [0, 1, 2, 3, 4, 5]
+let a = xs[1:3]
+let b = xs[:2]
+let c = xs[4:]
+let d = xs[::2]
+let e = xs[::-1]
+let f = xs[-2:]
+let g = xs[5:1:-2]
+let h = xs[10:20]
+let s = \"hello\"[1:3]
+let t = \"hello\"[::-1]
+let u = xs[1::9223372036854775807]
+let v = xs[4::-9223372036854775807 - 1]
+let w = \"hello\"[::9223372036854775806]";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_ints(&interpreter, "a"), vec![1, 2]);
+        assert_eq!(get_ints(&interpreter, "b"), vec![0, 1]);
+        assert_eq!(get_ints(&interpreter, "c"), vec![4, 5]);
+        assert_eq!(get_ints(&interpreter, "d"), vec![0, 2, 4]);
+        assert_eq!(get_ints(&interpreter, "e"), vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(get_ints(&interpreter, "f"), vec![4, 5]);
+        assert_eq!(get_ints(&interpreter, "g"), vec![5, 3]);
+        assert_eq!(get_ints(&interpreter, "h"), Vec::<i64>::new());
+        assert_eq!(get_str(&interpreter, "s"), "el");
+        assert_eq!(get_str(&interpreter, "t"), "olleh");
+        // Steps too large to add to an index end the slice
+        assert_eq!(get_ints(&interpreter, "u"), vec![1]);
+        assert_eq!(get_ints(&interpreter, "v"), vec![4]);
+        assert_eq!(get_str(&interpreter, "w"), "h");
+    }
+
+    #[test]
+    fn list_index_assignment() {
+        let src = "let xs = [[1, 2], [3, 4]]\nxs[0][1] = 20\nxs[-1][-1] = 40\nlet ys = xs[0]\nlet zs = xs[1]";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_ints(&interpreter, "ys"), vec![1, 20]);
+        assert_eq!(get_ints(&interpreter, "zs"), vec![3, 40]);
+    }
+
+    #[test]
+    fn lists_are_shared() {
+        let src = "
+fn append(xs, x)
+    xs.push(x)
+end
+let a = [1]
+let b = a
+append(b, 2)
+let nested = [a, a]
+nested[0].push(3)
+let copy = a.copy()
+copy.push(4)
+let same = a == b
+let grid = [[0]] * 2
+grid[0][0] = 1
+let row = grid[1]";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_ints(&interpreter, "a"), vec![1, 2, 3]);
+        assert_eq!(get_ints(&interpreter, "b"), vec![1, 2, 3]);
+        assert_eq!(get_ints(&interpreter, "copy"), vec![1, 2, 3, 4]);
+        assert!(get_bool(&interpreter, "same"));
+        // Repeating a list repeats references to its elements
+        assert_eq!(get_ints(&interpreter, "row"), vec![1]);
+    }
+
+    #[test]
+    fn list_methods() {
+        let src = "
+let xs = [3, 1, 2]
+xs.push(5)
+let last = xs.pop()
+let first = xs.pop(0)
+xs.insert(0, 7)
+xs.insert(-1, 8)
+xs.insert(100, 9)
+xs.remove(8)
+let sorted = xs.copy()
+sorted.sort()
+let reversed = [1, 2, 3]
+reversed.reverse()
+let result = [1].push(2)";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "last"), 5);
+        assert_eq!(get_int(&interpreter, "first"), 3);
+        assert_eq!(get_ints(&interpreter, "xs"), vec![7, 1, 2, 9]);
+        assert_eq!(get_ints(&interpreter, "sorted"), vec![1, 2, 7, 9]);
+        assert_eq!(get_ints(&interpreter, "reversed"), vec![3, 2, 1]);
+        assert!(matches!(
+            interpreter.get_variable("result"),
+            Some(Object::None)
+        ));
+
+        for (program, error_type, message) in [
+            ("[].pop()", "IndexError", "Cannot pop from an empty list"),
+            ("[1].remove(2)", "ValueError", "2 is not in the list"),
+            (
+                "[1, \"a\"].sort()",
+                "TypeError",
+                "Cannot sort a list containing 'str' and 'int'",
+            ),
+            (
+                "[1].push(1, 2)",
+                "TypeError",
+                "list.push() takes 1 arguments but 2 were given",
+            ),
+            (
+                "[1].pop(value=0)",
+                "TypeError",
+                "list.pop() does not take keyword arguments",
+            ),
+            (
+                "[1].append(2)",
+                "AttributeError",
+                "'list' object has no field 'append'",
+            ),
+        ] {
+            let error = run(program).err().unwrap();
+            assert_eq!(format!("{:?}", error.error_type), error_type, "{}", program);
+            assert_eq!(error.message, message);
+        }
+    }
+
+    #[test]
+    fn self_referencing_lists() {
+        let interpreter = run("let xs = [1]\nxs.push(xs)\nlet same = xs == xs[1]").unwrap();
+        let xs = interpreter.get_variable("xs").unwrap();
+        assert_eq!(xs.to_string(), "[1, [...]]");
+        assert!(get_bool(&interpreter, "same"));
+    }
+
+    #[test]
+    fn cycles_are_collected() {
+        let src = "
+fn make_cycles(n)
+    for i in range(n) do
+        let xs = [i]
+        xs.push(xs)
+        // A closure stored in the scope it captures
+        fn get()
+            return xs
+        end
+    end
+end
+let kept = [1]
+kept.push(kept)
+make_cycles(100)";
+        let program = parse(src).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_gc_settings(GcSettings {
+            enabled: true,
+            threshold: 10,
+        });
+        interpreter.run(&program).unwrap();
+        let stats = interpreter.gc_stats();
+        assert!(stats.collections > 0);
+        assert!(stats.freed > 0 && stats.bytes_freed > 0);
+
+        // Everything left over is freed by collecting, except what the
+        // program can still reach
+        let collection = interpreter.collect_garbage();
+        assert_eq!(interpreter.gc_stats().collections, stats.collections + 1);
+        assert!(collection.freed > 0);
+        assert_eq!(interpreter.collect_garbage().freed, 0);
+        let kept = interpreter.get_variable("kept").unwrap();
+        assert_eq!(kept.to_string(), "[1, [...]]");
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_gc_settings(GcSettings {
+            enabled: false,
+            threshold: 10,
+        });
+        interpreter.run(&program).unwrap();
+        assert_eq!(interpreter.gc_stats(), GcStats::default());
+    }
+
+    #[test]
+    fn conversion_builtins() {
+        let src = "
+let a = int(\" -42 \") + int(2.9) + int(true)
+let b = float(\"1.5\") + float(1)
+let c = str(1.5) + str([1, \"x\"]) + str(none)
+let d = [bool(0), bool(\"false\"), bool([])]
+let e = list(\"ab\") + list(range(2))
+let xs = [1]
+let copy = list(xs)
+copy.push(2)";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "a"), -42 + 2 + 1);
+        assert!(matches!(interpreter.get_variable("b"), Some(Object::Float(f)) if f.value == 2.5));
+        assert_eq!(get_str(&interpreter, "c"), "1.5[1, \"x\"]none");
+        let d = interpreter.get_variable("d").unwrap();
+        assert_eq!(d.to_string(), "[false, true, false]");
+        let e = interpreter.get_variable("e").unwrap();
+        assert_eq!(e.to_string(), "[\"a\", \"b\", 0, 1]");
+        assert_eq!(get_ints(&interpreter, "xs"), vec![1]);
+
+        for (program, error_type, message) in [
+            (
+                "int(\"1.5\")",
+                "ValueError",
+                "Cannot convert \"1.5\" to int",
+            ),
+            ("float([1])", "TypeError", "Cannot convert 'list' to float"),
+            ("list(1)", "TypeError", "Cannot convert 'int' to list"),
+            (
+                "int()",
+                "TypeError",
+                "int() takes 1 arguments but 0 were given",
+            ),
+        ] {
+            let error = run(program).err().unwrap();
+            assert_eq!(format!("{:?}", error.error_type), error_type, "{}", program);
+            assert_eq!(error.message, message);
+        }
+    }
+
+    #[test]
+    fn print_and_input() {
+        let src = "
+let name = input(\"Name? \")
+print(\"Hello,\", name)
+print(1, 2.5, [1, \"a\"], none, sep=\", \", end=\"!\")
+print()
+let last = input()";
+        let output = SharedOutput::default();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_input(Cursor::new("Ada\r\nBob"));
+        interpreter.run(&parse(src).unwrap()).unwrap();
+        assert_eq!(
+            String::from_utf8(output.0.borrow().clone()).unwrap(),
+            "Name? Hello, Ada\n1, 2.5, [1, \"a\"], none!\n"
+        );
+        assert_eq!(get_str(&interpreter, "last"), "Bob");
+
+        let error = interpreter.run(&parse("input()").unwrap()).err().unwrap();
+        assert_eq!(format!("{:?}", error.error_type), "EOFError");
+    }
+
+    #[test]
+    fn standard_builtins() {
+        let src = "
+let lengths = [len([1, 2]), len(\"h\u{e9}llo\"), len({\"a\": 1}), len(range(5))]
+let huge = len(range(-9223372036854775807, 9223372036854775807))
+let last = range(-9223372036854775807, 9223372036854775807)[-1]
+let types = [type(1), type(\"a\"), type(none), type([]), type(1.5d), type(1 < 2)]
+let absolutes = [abs(-3), abs(4), abs(-2.5), abs(-1.50d), abs(-9223372036854775808)]
+let extremes = [min(3, 1, 2), max([3, 7, 5]), min(\"b\", \"a\"), max(range(4)), max(1, 1.0)]
+let sums = [sum([1, 2, 3]), sum([1.5, 2]), sum([[1], [2]], [])]
+let rounded = [round(2.5), round(3.5), round(-2.7), round(1234, -2), round(2.675, 2), round(1.005d, 2)]
+assert(len(sums) == 3, \"sums are missing\")";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_ints(&interpreter, "lengths"), vec![2, 5, 1, 5]);
+        let value = |name| interpreter.get_variable(name).unwrap().to_string();
+        assert_eq!(
+            value("types"),
+            "[\"int\", \"str\", \"none\", \"list\", \"decimal\", \"bool\"]"
+        );
+        assert_eq!(
+            value("absolutes"),
+            "[3, 4, 2.5, 1.50d, 9223372036854775808]"
+        );
+        assert_eq!(value("extremes"), "[1, 7, \"a\", 3, 1]");
+        assert_eq!(value("sums"), "[6, 3.5, [1, 2]]");
+        assert_eq!(value("rounded"), "[2, 4, -3, 1200, 2.68, 1.00d]");
+
+        for (program, error_type, message) in [
+            (
+                "len(1)",
+                "TypeError",
+                "len() argument 1 must be a str, list, dict or range, not 'int'",
+            ),
+            (
+                "abs(\"a\")",
+                "TypeError",
+                "abs() argument 1 must be a number, not 'str'",
+            ),
+            (
+                "min()",
+                "TypeError",
+                "min() takes at least 1 arguments but 0 were given",
+            ),
+            ("max([])", "ValueError", "max() was given no values"),
+            (
+                "min(1, \"a\")",
+                "TypeError",
+                "Unsupported operand types for '<': 'str' and 'int'",
+            ),
+            (
+                "sum([1, \"a\"])",
+                "TypeError",
+                "Unsupported operand types for '+': 'int' and 'str'",
+            ),
+            (
+                "round(1.5, 1.5)",
+                "TypeError",
+                "round() argument 2 must be an int, not 'float'",
+            ),
+            (
+                "round(float(\"inf\"))",
+                "ValueError",
+                "Cannot round inf to an int",
+            ),
+            ("assert(false)", "AssertionError", "Assertion failed"),
+            (
+                "print(1, sep=2)",
+                "TypeError",
+                "print() argument 'sep' must be a str, not 'int'",
+            ),
+            (
+                "print(1, file=2)",
+                "TypeError",
+                "print() got an unexpected keyword argument 'file'",
+            ),
+            (
+                "len([], x=1)",
+                "TypeError",
+                "len() does not take keyword arguments",
+            ),
+            (
+                "type(1, 2)",
+                "TypeError",
+                "type() takes 1 arguments but 2 were given",
+            ),
+        ] {
+            let error = run(program).err().unwrap();
+            assert_eq!(format!("{:?}", error.error_type), error_type, "{}", program);
+            assert_eq!(error.message, message);
+        }
+
+        // Assertions point at the condition which was false
+        let error = run("let x = 1\nassert(x > 2, x)").err().unwrap();
+        assert_eq!(error.message, "1");
+        assert_eq!(error.token.span.start, 17);
+        assert_eq!(error.token.span.end, 22);
+    }
+
+    #[test]
+    fn index_errors() {
+        let error = run("let xs = [1, 2]\nlet x = xs[1 + 1]").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::IndexError));
+        assert_eq!(
+            error.message,
+            "Index 2 is out of range for list of length 2"
+        );
+        assert_eq!(error.token.span.start, 27);
+        assert_eq!(error.token.span.end, 32);
+
+        let error = run("let xs = [1]\nxs[-2] = 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::IndexError));
+        assert_eq!(error.token.span.start, 16);
+
+        let error = run("let x = \"abc\"[3]").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::IndexError));
+
+        let error = run("let s = \"abc\"\ns[0] = \"x\"").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+
+        let error = run("let x = [1][\"a\"]").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+
+        let error = run("let x = [1, 2][::0]").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::ValueError));
+    }
+
+    #[test]
+    fn for_loops() {
+        let src = "
+let total = 0
+for x in [1, 2, 3] do total = total + x end
+let chars = []
+for c in \"héy\" do chars = chars + [c] end
+let keys = []
+for key in {\"a\": 1, \"b\": 2} do keys = keys + [key] end
+let evens = []
+for i in range(10, 0, -2) do evens = evens + [i] end";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "total"), 6);
+        assert_eq!(get_int(&interpreter, "x"), 3);
+        match interpreter.get_variable("chars") {
+            Some(Object::List(list)) => assert_eq!(list.len(), 3),
+            other => panic!("expected a list, got {:?}", other),
+        }
+        match interpreter.get_variable("keys") {
+            Some(Object::List(list)) => {
+                assert!(matches!(list.get(1), Some(Object::Str(s)) if s.value == "b"))
+            }
+            other => panic!("expected a list, got {:?}", other),
+        }
+        assert_eq!(get_ints(&interpreter, "evens"), vec![10, 8, 6, 4, 2]);
+    }
+
+    #[test]
+    fn ranges() {
+        let src = "let a = range(3)\nlet b = range(1, 4)[-1]\nlet c = range(0, 10, 3) == range(0, 12, 3)\nlet d = range(5, 0)";
+        let interpreter = run(src).unwrap();
+        match interpreter.get_variable("a") {
+            Some(Object::Range(range)) => {
+                assert_eq!(range.iter().collect::<Vec<_>>(), vec![0, 1, 2])
+            }
+            other => panic!("expected a range, got {:?}", other),
+        }
+        assert_eq!(get_int(&interpreter, "b"), 3);
+        assert!(get_bool(&interpreter, "c"));
+        match interpreter.get_variable("d") {
+            Some(Object::Range(range)) => assert!(range.is_empty()),
+            other => panic!("expected a range, got {:?}", other),
+        }
+
+        let error = run("let x = range(0, 10, 0)").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::ValueError));
+        let error = run("let x = range()").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        let error = run("let x = range(\"a\")").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        let error = run("for x in 1 do end").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        assert_eq!(error.token.span.start, 9);
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let src = "
+fn first_even_square(xs)
+    for x in xs do
+        if x % 2 == 1 then continue end
+        let i = 0
+        while true do
+            i = i + 1
+            if i == x then break end
+        end
+        return i * x
+    end
+end
+let x = first_even_square([1, 3, 4, 6])
+let skipped = 0
+for i in range(100) do
+    if i >= 5 then break end
+    if i == 2 then continue end
+    skipped = skipped + i
+end";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 16);
+        assert_eq!(get_int(&interpreter, "skipped"), 8);
+    }
+
+    #[test]
+    fn higher_order_functions() {
+        let src = "
+fn map(f, xs)
+    let result = []
+    for x in xs do result = result + [f(x)] end
+    return result
+end
+fn filter(f, xs)
+    let result = []
+    for x in xs do
+        if f(x) then result = result + [x] end
+    end
+    return result
+end
+fn double(x)
+    return x * 2
+end
+let doubled = map(double, [1, 2, 3])
+let odd = filter(fn(x) return x % 2 == 1 end, range(6))
+let square = fn(x)
+    return x * x
+end
+let squares = map(square, [1, 2, 3])";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_ints(&interpreter, "doubled"), vec![2, 4, 6]);
+        assert_eq!(get_ints(&interpreter, "odd"), vec![1, 3, 5]);
+        assert_eq!(get_ints(&interpreter, "squares"), vec![1, 4, 9]);
+    }
+
+    #[test]
+    fn closures() {
+        let src = "
+fn make_adder(n)
+    return fn(x) return x + n end
+end
+let a = make_adder(1)(2)
+let add_ten = make_adder(10)
+let b = add_ten(5)
+let offset = 1
+let get_offset = fn() return offset end
+offset = 100
+let c = get_offset()
+fn outer()
+    fn fact(n)
+        if n <= 1 then return 1 end
+        return n * fact(n - 1)
+    end
+    return fact
+end
+let d = outer()(5)
+let e = [make_adder(1), make_adder(2)][1](3)";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "a"), 3);
+        assert_eq!(get_int(&interpreter, "b"), 15);
+        assert_eq!(get_int(&interpreter, "c"), 100);
+        assert_eq!(get_int(&interpreter, "d"), 120);
+        assert_eq!(get_int(&interpreter, "e"), 5);
+    }
+
+    #[test]
+    fn function_values() {
+        let interpreter =
+            run("fn f()\nend\nlet g = f\nlet a = g == f\nlet b = f == fn() end").unwrap();
+        assert!(get_bool(&interpreter, "a"));
+        assert!(!get_bool(&interpreter, "b"));
+
+        let error = run("let x = 1\nlet y = x(2)").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        assert_eq!(error.message, "'int' object is not callable");
+        assert_eq!(error.token.span.start, 18);
+
+        let error = run("let f = fn(a, b) end\nf(1)").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        assert_eq!(error.message, "<lambda>(a, b) is missing the argument 'b'");
+
+        let error = run("let x = g(1)").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::NameError));
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let src = "
+let x = 10
+x += 5
+x -= 3
+x *= 4
+x /= 6
+x %= 5
+let xs = [1, [2, 3]]
+xs[0] += 10
+xs[1][-1] *= 7
+let s = \"ab\"
+s += \"c\"
+let ys = xs[1]";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 3);
+        assert_eq!(get_ints(&interpreter, "ys"), vec![2, 21]);
+        assert_eq!(get_str(&interpreter, "s"), "abc");
+        match interpreter.get_variable("xs") {
+            Some(Object::List(list)) => {
+                assert!(matches!(list.get(0), Some(Object::Int(Int::Small(11)))))
+            }
+            other => panic!("expected a list, got {:?}", other),
+        }
+
+        let error = run("let x = \"a\"\nx -= 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        let error = run("let xs = []\nxs[0] += 1").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::IndexError));
+    }
+
+    #[test]
+    fn assignment_updates_enclosing_scopes() {
+        let src = "
+fn make_counter()
+    let count = 0
+    return fn()
+        count += 1
+        return count
+    end
+end
+let counter = make_counter()
+counter()
+let a = counter()
+let total = 0
+fn add(n)
+    total += n
+end
+add(2)
+add(3)
+let shadowed = 1
+fn shadow()
+    let shadowed = 100
+    shadowed += 1
+end
+shadow()";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "a"), 2);
+        assert_eq!(get_int(&interpreter, "total"), 5);
+        assert_eq!(get_int(&interpreter, "shadowed"), 1);
+    }
+
+    #[test]
+    fn constant_and_undeclared_assignment_errors() {
+        let error = run("const x = 1\nx = 2").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::SyntaxError));
+        assert_eq!(error.message, "Cannot assign to constant 'x'");
+        assert_eq!(error.token.span.start, 12);
+
+        // Reported before anything runs, even if the assignment is never reached.
+        let error = run("fn f()\n    y += 1\nend").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::NameError));
+        assert_eq!(error.token.span.start, 11);
+
+        let error = run("fn f(a)\n    let b = 1\nend\nb = 2").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::NameError));
+
+        let error = run("const xs = [1]\nxs[0] = 2\nxs = []").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::SyntaxError));
+        assert_eq!(error.token.span.start, 25);
+
+        let interpreter = run("fn f(a)\n    a = 2\nend\nfor i in range(3) do i = 0 end").unwrap();
+        assert!(interpreter.get_variable("i").is_some());
+
+        // Constants can't be declared again in their scope, by any means
+        for (program, start) in [
+            ("const z = 1\nlet z = 2\nz = 3", 16),
+            ("const x = 1\nfor x in [2, 3] do end\nx = 5\nprint(x)", 16),
+            ("const y = 1\nmatch 7\ncase y then\nend\ny = 9", 25),
+            ("const f = 1\nfn f()\nend", 15),
+        ] {
+            let error = run(program).err().unwrap();
+            assert!(
+                matches!(error.error_type, ErrorType::SyntaxError),
+                "{}",
+                program
+            );
+            assert_eq!(error.token.span.start, start, "{}", program);
+            assert!(error.message.starts_with("Cannot redeclare constant"));
+        }
+        // A function has its own scope
+        let interpreter =
+            run("const x = 1\nfn f()\n    let x = 2\n    for x in [3] do end\nend\nf()").unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 1);
+    }
+
+    #[test]
+    fn default_keyword_and_variadic_parameters() {
+        let src = "
+fn connect(host, port = 80, secure = port == 443)
+    return [host, port, secure]
+end
+let a = connect(\"x\")
+let a_port = a[1]
+let a_secure = a[2]
+let b_secure = connect(\"x\", port=443)[2]
+let c = connect(port=1, host=\"y\", secure=false)
+let c_host = c[0]
+let c_port = c[1]
+fn sum(first, *rest)
+    let total = first
+    for x in rest do total += x end
+    return total
+end
+let d = sum(1)
+let e = sum(1, 2, 3)
+fn describe(*items)
+    return items
+end
+let f = describe()";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "a_port"), 80);
+        assert!(!get_bool(&interpreter, "a_secure"));
+        assert!(get_bool(&interpreter, "b_secure"));
+        assert_eq!(get_str(&interpreter, "c_host"), "y");
+        assert_eq!(get_int(&interpreter, "c_port"), 1);
+        assert_eq!(get_int(&interpreter, "d"), 1);
+        assert_eq!(get_int(&interpreter, "e"), 6);
+        assert_eq!(get_ints(&interpreter, "f"), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn argument_errors() {
+        let define = "fn connect(host, port = 80, *rest)\nend\n";
+        for (call, message) in [
+            (
+                "connect()",
+                "connect(host, port = ..., *rest) is missing the argument 'host'",
+            ),
+            (
+                "connect(1, host=2)",
+                "connect(host, port = ..., *rest) got multiple values for argument 'host'",
+            ),
+            (
+                "connect(1, user=2)",
+                "connect(host, port = ..., *rest) got an unexpected keyword argument 'user'",
+            ),
+        ] {
+            let error = run(&format!("{}{}", define, call)).err().unwrap();
+            assert!(matches!(error.error_type, ErrorType::TypeError));
+            assert_eq!(error.message, message);
+        }
+
+        let error = run("fn f(a)\nend\nf(1, 2)").err().unwrap();
+        assert_eq!(error.message, "f(a) takes 1 arguments but 2 were given");
+        assert_eq!(error.token.span.start, 12);
+
+        let error = run("let r = range(stop=1)").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+    }
+
+    #[test]
+    fn structs() {
+        let src = "
+struct Point x, y
+    fn norm()
+        return self.x * self.x + self.y * self.y
+    end
+
+    fn shift(by)
+        self.x += by
+        self.y += by
+    end
+end
+struct Line start, stop end
+let p = Point(1, 2)
+let q = Point(y=2, x=1)
+let equal = p == q
+p.x = 3
+let px = p.x
+let norm = p.norm()
+p.shift(1)
+let shifted = [p.x, p.y]
+let line = Line(Point(0, 0), Point(1, 2))
+line.stop.y *= 5
+let lines = [line]
+lines[0].start.shift(2)
+let start = [lines[0].start.x, line.start.x]
+let stop_y = line.stop.y
+let norm_of = q.norm
+let bound = norm_of()
+let alias = q
+alias.x = 10
+let qx = q.x";
+        let interpreter = run(src).unwrap();
+        assert!(get_bool(&interpreter, "equal"));
+        assert_eq!(get_int(&interpreter, "px"), 3);
+        assert_eq!(get_int(&interpreter, "norm"), 13);
+        assert_eq!(get_ints(&interpreter, "shifted"), vec![4, 3]);
+        // Structs are shared, so shifting the start through the list moves
+        // the line's start too
+        assert_eq!(get_ints(&interpreter, "start"), vec![2, 2]);
+        assert_eq!(get_int(&interpreter, "stop_y"), 10);
+        assert_eq!(get_int(&interpreter, "qx"), 10);
+        assert_eq!(get_int(&interpreter, "bound"), 5);
+        let q = interpreter.get_variable("q").unwrap();
+        assert_eq!(q.type_name(), "struct");
+        assert_eq!(q.to_string(), "Point(x=10, y=2)");
+        let point = interpreter.get_variable("Point").unwrap();
+        assert_eq!(point.to_string(), "<struct Point>");
+    }
+
+    #[test]
+    fn struct_errors() {
+        let define = "struct Point x, y\n    fn norm()\n    end\nend\n";
+        for (program, error_type, message) in [
+            (
+                "Point(1)",
+                "TypeError",
+                "Point(x, y) is missing the argument 'y'",
+            ),
+            (
+                "Point(1, 2, 3)",
+                "TypeError",
+                "Point(x, y) takes 2 arguments but 3 were given",
+            ),
+            (
+                "Point(1, z=2)",
+                "TypeError",
+                "Point(x, y) got an unexpected keyword argument 'z'",
+            ),
+            (
+                "let z = Point(1, 2).z",
+                "AttributeError",
+                "'Point' has no field 'z'",
+            ),
+            (
+                "let p = Point(1, 2)\np.z = 1",
+                "AttributeError",
+                "'Point' has no field 'z'",
+            ),
+            (
+                "let n = 1\nn.x = 1",
+                "AttributeError",
+                "'int' object has no field 'x'",
+            ),
+            (
+                "Point(1, 2).norm(1)",
+                "TypeError",
+                "Point.norm() takes 0 arguments but 1 were given",
+            ),
+        ] {
+            let error = run(&format!("{}{}", define, program)).err().unwrap();
+            assert_eq!(format!("{:?}", error.error_type), error_type, "{}", program);
+            assert_eq!(error.message, message);
+        }
+    }
+
+    #[test]
+    fn failed_guards_do_not_bind() {
+        let src = "
+let x = 5
+let seen = 0
+match 3
+case x if x > 10 then
+    seen = 1
+case _ then
+    seen = x
+end";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_int(&interpreter, "x"), 5);
+        assert_eq!(get_int(&interpreter, "seen"), 5);
+    }
+
+    #[test]
+    fn match_statements() {
+        let src = "
+struct Point x, y end
+fn describe(value)
+    match value
+    case 0 then
+        return \"zero\"
+    case -1.5 then
+        return \"negative\"
+    case [] then
+        return \"empty\"
+    case [first, *rest] if first > 0 then
+        return rest
+    case [first, *_, last] then
+        return [first, last]
+    case Point(0, y=y) then
+        return y
+    case Point(x, _) if x > 10 then
+        return \"far\"
+    case n then
+        return none
+    end
+end
+let zero = describe(0)
+let negative = describe(-1.5)
+let empty = describe([])
+let rest = describe([1, 2, 3])
+let ends = describe([-1, 2, 3])
+let pair = describe([-1, 2])
+let y = describe(Point(0, 3))
+let far = describe(Point(11, 3))
+let other = describe(Point(1, 1))
+let bound = 0
+match [4, 5]
+case [a, b] then bound = a * b
+end";
+        let interpreter = run(src).unwrap();
+        assert_eq!(get_str(&interpreter, "zero"), "zero");
+        assert_eq!(get_str(&interpreter, "negative"), "negative");
+        assert_eq!(get_str(&interpreter, "empty"), "empty");
+        assert_eq!(get_ints(&interpreter, "rest"), vec![2, 3]);
+        assert_eq!(get_ints(&interpreter, "ends"), vec![-1, 3]);
+        assert_eq!(get_ints(&interpreter, "pair"), vec![-1, 2]);
+        assert_eq!(get_int(&interpreter, "y"), 3);
+        assert_eq!(get_str(&interpreter, "far"), "far");
+        assert!(matches!(
+            interpreter.get_variable("other"),
+            Some(Object::None)
+        ));
+        assert_eq!(get_int(&interpreter, "bound"), 20);
+
+        let define = "struct Point x, y end\nlet n = 1\n";
+        for (program, message) in [
+            (
+                "match 1\ncase Point(z=1) then\nend",
+                "'Point' has no field 'z'",
+            ),
+            (
+                "match 1\ncase Point(1, 2, 3) then\nend",
+                "Point(x, y) has 2 fields but 3 patterns were given",
+            ),
+            (
+                "match 1\ncase n(1) then\nend",
+                "'n' is not a struct type, it is a 'int'",
+            ),
+            (
+                "match 1\ncase Line(1) then\nend",
+                "Name 'Line' is not defined",
+            ),
+        ] {
+            let error = run(&format!("{}{}", define, program)).err().unwrap();
+            assert_eq!(error.message, message);
+        }
+    }
+
+    #[test]
+    fn ints_are_promoted_when_they_overflow() {
+        let src = "let max = 9223372036854775807
+let big = max + 1
+let square = big * big
+let back = square / big - 1
+let neg = -(-max - 1)
+let wrapped = big % 10
+let literal = 99999999999999999999 + 1
+let min = -9223372036854775808
+let matched = 0
+match min
+case -9223372036854775808 then
+    matched = 1
+end";
+        let interpreter = run(src).unwrap();
+        let show = |name| interpreter.get_variable(name).unwrap().to_string();
+        assert_eq!(show("big"), "9223372036854775808");
+        assert_eq!(show("square"), "85070591730234615865843651857942052864");
+        assert_eq!(show("neg"), "9223372036854775808");
+        assert!(matches!(
+            interpreter.get_variable("big"),
+            Some(Object::Int(Int::Big(_)))
+        ));
+        // Results which fit in 64 bits again are stored in 64 bits
+        assert_eq!(get_int(&interpreter, "back"), 9223372036854775807);
+        assert_eq!(get_int(&interpreter, "wrapped"), 8);
+        // Literals may be too large for 64 bits too
+        assert_eq!(show("literal"), "100000000000000000000");
+        assert_eq!(get_int(&interpreter, "min"), i64::MIN);
+        assert_eq!(get_int(&interpreter, "matched"), 1);
+
+        // Floats are 64 bits too
+        let interpreter = run("let x = 0.1 + 0.2
+let y = 16777217.0")
+        .unwrap();
+        assert_eq!(
+            interpreter.get_variable("x").unwrap().to_string(),
+            "0.30000000000000004"
+        );
+        assert_eq!(
+            interpreter.get_variable("y").unwrap().to_string(),
+            "16777217.0"
+        );
+    }
+
+    #[test]
+    fn overflow_can_be_an_error() {
+        let program = parse(
+            "let x = 9223372036854775807
+x = x * 2",
+        )
+        .unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_overflow(Overflow::Error);
+        let error = interpreter.run(&program).err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::OverflowError));
+        assert_eq!(error.message, "Integer overflow in '*'");
+        assert_eq!(get_int(&interpreter, "x"), i64::MAX);
+    }
+
+    #[test]
+    fn huge_repetition_is_an_error() {
+        for program in [
+            "let s = \"ab\" * 9223372036854775807",
+            "let s = 9223372036854775807 * \"ab\"",
+            "let s = \"ab\" * 99999999999999999999",
+            "let xs = [1, 2] * 9223372036854775807",
+            "let xs = [1] * 300000000",
+        ] {
+            let error = run(program).err().unwrap();
+            assert!(
+                matches!(error.error_type, ErrorType::OverflowError),
+                "{}",
+                program
+            );
+        }
+        let error = run("let s = \"ab\" * 9223372036854775807").err().unwrap();
+        assert_eq!(
+            error.message,
+            "Cannot repeat a sequence of length 2 9223372036854775807 times, as the result would be too long"
+        );
+
+        // Empty sequences can be repeated any number of times
+        let interpreter =
+            run("let s = \"\" * 9223372036854775807\nlet xs = [] * 99999999999999999999").unwrap();
+        assert_eq!(get_str(&interpreter, "s"), "");
+        assert_eq!(get_ints(&interpreter, "xs"), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn decimals_are_exact() {
+        let src = "let total = 0.1d + 0.2d\nlet price = 19.99d * 3\nlet share = 10d / 3\nlet exact = total == 0.3d\nlet mixed = 2 * 1.50d - 1\nlet left = 10.5d % 3\nlet less = -0.5d < 0";
+        let interpreter = run(src).unwrap();
+        let show = |name| interpreter.get_variable(name).unwrap().to_string();
+        assert_eq!(show("total"), "0.3");
+        assert_eq!(show("price"), "59.97");
+        assert_eq!(show("share"), "3.3333333333333333333333333333");
+        assert_eq!(show("exact"), "true");
+        assert_eq!(show("mixed"), "2.00");
+        assert_eq!(show("left"), "1.5");
+        assert_eq!(show("less"), "true");
+
+        let error = run("let x = 1.5d + 0.5").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::TypeError));
+        assert_eq!(
+            error.message,
+            "Unsupported operand types for '+': 'decimal' and 'float'"
+        );
+        let error = run("let x = 1.5d / 0").err().unwrap();
+        assert!(matches!(error.error_type, ErrorType::ZeroDivisionError));
+
+        let program = parse("let x = 2d / 3").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_decimal_context(DecimalContext {
+            precision: 2,
+            rounding: Rounding::Down,
+        });
+        interpreter.run(&program).unwrap();
+        assert_eq!(interpreter.get_variable("x").unwrap().to_string(), "0.66");
+    }
+}
@@ -0,0 +1,117 @@
+// Property tests asserting that no input can make the lexer panic or loop.
+// Inputs are produced by a small seeded xorshift generator so that failures
+// are reproducible without pulling in any dependencies.
+
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::lexer::token::TokenKind;
+    use sap::lexer::{tokenize, Lexer};
+
+    const CASES: usize = 2000;
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    /// Characters the lexer gives special meaning to, weighted into the
+    /// generated inputs so that interesting paths are actually exercised.
+    const INTERESTING: &[char] = &[
+        '/', '*', '"', '\n', '\r', ' ', '.', '!', '=', '<', '>', '_', '0', '9', 'a', 'Z', '\0',
+        '²', 'é', '٣',
+    ];
+
+    fn random_bytes(rng: &mut XorShift) -> Vec<u8> {
+        let len = rng.below(64) as usize;
+        (0..len).map(|_| rng.next() as u8).collect()
+    }
+
+    fn random_source(rng: &mut XorShift) -> String {
+        let len = rng.below(64) as usize;
+        (0..len)
+            .map(|_| match rng.below(3) {
+                0 => INTERESTING[rng.below(INTERESTING.len() as u64) as usize],
+                1 => (b' ' + rng.below(95) as u8) as char,
+                _ => char::from_u32(rng.next() as u32 % 0x11_0000).unwrap_or('?'),
+            })
+            .collect()
+    }
+
+    /// Lexes the input and checks the invariants which must hold for any input.
+    fn check(input: &str) {
+        let char_count = input.chars().count();
+        let mut results = Vec::new();
+        for token in Lexer::from_str(input) {
+            results.push(token);
+            assert!(
+                results.len() <= char_count + 2,
+                "lexer did not make progress on {:?}",
+                input
+            );
+        }
+
+        match results.last() {
+            Some(Ok(token)) => assert_eq!(token.kind, TokenKind::Eof, "input: {:?}", input),
+            other => panic!(
+                "expected Eof as the last token, got {:?} for {:?}",
+                other, input
+            ),
+        }
+
+        for result in &results {
+            let span = match result {
+                Ok(token) => &token.span,
+                Err(error) => &error.token.span,
+            };
+            assert!(span.start <= span.end, "input: {:?}", input);
+            assert!(span.start <= char_count, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic() {
+        let mut rng = XorShift(0x5eed_1e55_ba5e_ba11);
+        for _ in 0..CASES {
+            let bytes = random_bytes(&mut rng);
+            check(&String::from_utf8_lossy(&bytes));
+        }
+    }
+
+    #[test]
+    fn arbitrary_sources_never_panic() {
+        let mut rng = XorShift(0xdead_beef_cafe_f00d);
+        for _ in 0..CASES {
+            check(&random_source(&mut rng));
+        }
+    }
+
+    #[test]
+    fn known_failure_paths_are_errors() {
+        for input in [
+            "1.",
+            "1.x",
+            "\"unterminated",
+            "\"new\nline\"",
+            "/* never closed",
+            "²",
+            "!",
+            "$",
+        ] {
+            let (_, errors) = tokenize(input);
+            assert!(!errors.is_empty(), "expected an error for {:?}", input);
+        }
+    }
+}
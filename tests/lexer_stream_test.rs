@@ -0,0 +1,128 @@
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::core::BigInt;
+    use sap::lexer::{tokenize, Lexer, Span, Suppression};
+    use sap::lexer::token::*;
+
+    fn token(kind: TokenKind, start: usize, end: usize) -> Token {
+        Token { kind, span: Span::new(start, end) }
+    }
+
+    #[test]
+    fn iterator_yields_eof_then_fuses() {
+        let mut lexer = Lexer::from_str("a = 1");
+        assert_eq!(lexer.next().unwrap().unwrap(), token(TokenKind::Ident("a".to_string()), 0, 1));
+        assert_eq!(lexer.next().unwrap().unwrap(), token(TokenKind::Assign, 2, 3));
+        assert_eq!(lexer.next().unwrap().unwrap(), token(TokenKind::Int(1), 4, 5));
+        assert_eq!(lexer.next().unwrap().unwrap(), token(TokenKind::Eof, 5, 5));
+        assert!(lexer.next().is_none());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn trailing_whitespace_is_eof() {
+        let kinds: Vec<TokenKind> = Lexer::from_str("x   ").map(|t| t.unwrap().kind).collect();
+        assert_eq!(kinds, vec![TokenKind::Ident("x".to_string()), TokenKind::Eof]);
+    }
+
+    #[test]
+    fn peek_nth_does_not_consume() {
+        let mut lexer = Lexer::from_str("f(x)");
+        assert_eq!(lexer.peek_nth(1).as_ref().unwrap().kind, TokenKind::Lparen);
+        assert_eq!(lexer.peek_nth(0).as_ref().unwrap().kind, TokenKind::Ident("f".to_string()));
+        assert_eq!(lexer.peek_nth(10).as_ref().unwrap().kind, TokenKind::Eof);
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Ident("f".to_string()));
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Lparen);
+        assert_eq!(lexer.peek_nth(0).as_ref().unwrap().kind, TokenKind::Ident("x".to_string()));
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Ident("x".to_string()));
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Rparen);
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn tokenize_collects_errors_and_recovers() {
+        let (tokens, errors) = tokenize("a $ b ! c");
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Ident("a".to_string()),
+            TokenKind::Ident("b".to_string()),
+            TokenKind::Ident("c".to_string()),
+            TokenKind::Eof,
+        ]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].token.span, Span::new(2, 3));
+        assert_eq!(errors[1].token.span, Span::new(6, 8));
+    }
+
+    #[test]
+    fn line_comment_keeps_separator() {
+        let kinds: Vec<TokenKind> = Lexer::from_str("a // comment\nb // trailing").map(|t| t.unwrap().kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Ident("a".to_string()),
+            TokenKind::Seperator,
+            TokenKind::Ident("b".to_string()),
+            TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn compound_assignment_operators() {
+        let (tokens, errors) = tokenize("x += 1 -= *= /= %=- 2 / = 3");
+        assert!(errors.is_empty());
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Ident("x".to_string()), TokenKind::PlusAssign, TokenKind::Int(1),
+            TokenKind::MinusAssign, TokenKind::MultAssign, TokenKind::DivAssign, TokenKind::ModAssign,
+            TokenKind::Minus, TokenKind::Int(2), TokenKind::Div, TokenKind::Assign, TokenKind::Int(3),
+            TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn decimal_suffix() {
+        let (tokens, errors) = tokenize("19.99d 5d 2.5 3do");
+        assert!(errors.is_empty());
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Decimal("19.99".to_string()), TokenKind::Decimal("5".to_string()),
+            TokenKind::Float(2.5), TokenKind::Int(3), TokenKind::Do, TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn big_int_literals() {
+        let (tokens, errors) = tokenize("9223372036854775807 9223372036854775808");
+        assert!(errors.is_empty());
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Int(i64::MAX), TokenKind::BigInt(BigInt::parse("9223372036854775808").unwrap()),
+            TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn arrow() {
+        let (tokens, errors) = tokenize("-> - > x-1");
+        assert!(errors.is_empty());
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Arrow, TokenKind::Minus, TokenKind::More,
+            TokenKind::Ident("x".to_string()), TokenKind::Minus, TokenKind::Int(1), TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn allow_comments_are_kept() {
+        let mut lexer = Lexer::from_str("x // sap:allow(empty-block, deep-nesting)\n// a comment\n//sap:allow(long-function)");
+        let kinds: Vec<TokenKind> = lexer.by_ref().map(|t| t.unwrap().kind).collect();
+        assert_eq!(kinds, vec![TokenKind::Ident("x".to_string()), TokenKind::Seperator, TokenKind::Eof]);
+        assert_eq!(lexer.suppressions(), &[
+            Suppression { rules: vec!["empty-block".to_string(), "deep-nesting".to_string()], span: Span::new(2, 41) },
+            Suppression { rules: vec!["long-function".to_string()], span: Span::new(55, 81) },
+        ]);
+    }
+}
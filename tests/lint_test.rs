@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::lexer::Span;
+    use sap::lint::{lint, Config, Rule, Severity};
+    use sap::parser::parse;
+
+    fn lints_with(src: &str, config: &Config) -> Vec<(Rule, String)> {
+        lint(&parse(src).unwrap(), src, config)
+            .into_iter()
+            .map(|lint| (lint.rule, lint.message))
+            .collect()
+    }
+
+    fn lints(src: &str) -> Vec<(Rule, String)> {
+        lints_with(src, &Config::default())
+    }
+
+    fn rules(src: &str) -> Vec<Rule> {
+        lints(src).into_iter().map(|(rule, _)| rule).collect()
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let src = "let x = 1\nif x then\n    if x then\n        if x then\n            if x then\n                if x then x = 2 end\n            end\n        end\n    end\nend";
+        let found = lint(&parse(src).unwrap(), src, &Config::default());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].rule, Rule::DeepNesting);
+        assert_eq!(
+            found[0].message,
+            "'if' statements are nested 4 deep here, more than the limit of 3"
+        );
+        assert_eq!(found[0].span.start, 64);
+
+        // Each function starts counting again
+        let src = "let x = 1\nif x then\n    if x then\n        if x then\n            let f = fn()\n                if x then x = 2 end\n            end\n        end\n    end\nend";
+        assert_eq!(rules(src), vec![]);
+    }
+
+    #[test]
+    fn bool_comparison() {
+        assert_eq!(
+            lints("let x = true\nlet a = x == true\nlet b = false != x\nlet c = x == false\nlet d = x == 1"),
+            vec![
+                (Rule::BoolComparison, "Comparing to 'true' is unnecessary, use the value itself".to_string()),
+                (Rule::BoolComparison, "Comparing to 'false' is unnecessary, use the value itself".to_string()),
+                (Rule::BoolComparison, "Comparing to 'false' is unnecessary, use 'not' instead".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_condition() {
+        assert_eq!(
+            lints("let x = 1\nwhile true do x += 1 end\nwhile 0 do x += 1 end\nwhile 1 < 2 do x += 1 end"),
+            vec![
+                (Rule::ConstantCondition, "The condition of this 'while' loop is always true, and nothing in it leaves the loop, so it never ends".to_string()),
+                (Rule::ConstantCondition, "The condition of this 'while' loop is always false, so its body never runs".to_string()),
+                (Rule::ConstantCondition, "The condition of this 'while' loop is constant".to_string()),
+            ]
+        );
+        // A loop which is left some other way is fine
+        let src = "let x = 1\nwhile true do\n    x += 1\n    if x > 10 then break end\nend\nwhile x < 20 do x += 1 end";
+        assert_eq!(rules(src), vec![]);
+        // But a `break` in a nested loop doesn't leave it
+        let src = "while true do\n    for i in range(3) do break end\nend";
+        assert_eq!(rules(src), vec![Rule::ConstantCondition]);
+    }
+
+    #[test]
+    fn empty_block() {
+        let src = "let x = 1\nif x then\nelif x then x = 2\nelse\nend\nfor i in range(3) do end\nfn stub()\nend\nmatch x\ncase 1 then\ncase _ then x = 1\nend";
+        assert_eq!(
+            lints(src),
+            vec![
+                (
+                    Rule::EmptyBlock,
+                    "The 'if' branch of this 'if' is empty".to_string()
+                ),
+                (
+                    Rule::EmptyBlock,
+                    "The 'else' branch of this 'if' is empty".to_string()
+                ),
+                (
+                    Rule::EmptyBlock,
+                    "The body of this 'for' loop is empty".to_string()
+                ),
+                (Rule::EmptyBlock, "The body of 'stub' is empty".to_string()),
+                (
+                    Rule::EmptyBlock,
+                    "The body of this case is empty".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_function() {
+        let mut config = Config::default();
+        config.max_function_lines = 3;
+        let src = "fn short()\n    return 1\nend\nfn long()\n    let x = 1\n    return x\nend\nlet f = fn() return 1 end";
+        assert_eq!(
+            lints_with(src, &config),
+            vec![(
+                Rule::LongFunction,
+                "The body of 'long' is 4 lines long, more than the limit of 3".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn severities_are_configured() {
+        let config = Config::parse(
+            "[package]\nname = \"x\"\n\n[lint]\n# Comments are ignored\nempty-block = \"off\"\nbool-comparison = \"error\"  # trailing\nmax-if-depth = 1",
+        )
+        .unwrap();
+        assert_eq!(config.severity(Rule::EmptyBlock), Severity::Off);
+        assert_eq!(config.severity(Rule::BoolComparison), Severity::Error);
+        assert_eq!(config.severity(Rule::LongFunction), Severity::Warning);
+        assert_eq!(config.max_if_depth, 1);
+
+        let src = "let x = 1\nif x == true then\n    if x then end\nend";
+        let found = lint(&parse(src).unwrap(), src, &config);
+        let found: Vec<(Rule, Severity)> = found
+            .iter()
+            .map(|lint| (lint.rule, lint.severity))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Rule::BoolComparison, Severity::Error),
+                (Rule::DeepNesting, Severity::Warning)
+            ]
+        );
+
+        for (input, message) in [
+            ("[lint]\nno-such-rule = \"off\"", "line 2: unknown lint rule 'no-such-rule'"),
+            ("[lint]\nempty-block = \"loud\"", "line 2: the severity of 'empty-block' must be \"off\", \"warning\" or \"error\", not \"loud\""),
+            ("[lint]\nmax-if-depth = deep", "line 2: 'max-if-depth' must be a whole number, not deep"),
+            ("[lint]\nempty-block", "line 2: expected 'name = value', found 'empty-block'"),
+        ] {
+            assert_eq!(Config::parse(input).unwrap_err(), message);
+        }
+    }
+
+    #[test]
+    fn allow_comments_suppress_lints() {
+        let src = "let x = 1\n// sap:allow(empty-block)\nfn stub() end\nfn other() end // sap:allow(empty-block)\nif x then end // sap:allow(bool-comparison)\n";
+        let found = lint(&parse(src).unwrap(), src, &Config::default());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].rule, Rule::EmptyBlock);
+        assert_eq!(found[0].span, Span::new(91, 104));
+    }
+}
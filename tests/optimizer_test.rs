@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::core::{Int, Object};
+    use sap::interpreter::Interpreter;
+    use sap::lexer::Span;
+    use sap::optimizer::optimize;
+    use sap::parser::{ast::Node, parse};
+
+    fn optimized(src: &str) -> Vec<Node> {
+        let mut program = parse(src).unwrap();
+        optimize(&mut program);
+        match program {
+            Node::Program { statements, .. } => statements,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the value a variable declaration is initialised with.
+    fn declared_value(statement: &Node) -> &Node {
+        match statement {
+            Node::VariableDecl { value, .. } => value,
+            other => panic!("expected a variable declaration, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn literals_are_folded() {
+        let statements = optimized("let day = 60 * 60 * 24\nlet s = \"ab\" * 2 + \"c\"\nlet b = not (1 < 2) or -1.5 == -(3 / 2)");
+        match declared_value(&statements[0]) {
+            Node::Literal {
+                value: Object::Int(int),
+                span,
+            } => {
+                assert_eq!(*int, Int::new(86400));
+                assert_eq!(span, &Span::new(10, 22));
+            }
+            other => panic!("expected a literal, got {:?}", other),
+        }
+        assert!(
+            matches!(declared_value(&statements[1]), Node::Literal { value: Object::Str(s), .. } if s.value == "ababc")
+        );
+        // Integer division truncates, so the right hand side is -1
+        assert!(
+            matches!(declared_value(&statements[2]), Node::Literal { value: Object::Bool(b), .. } if !b.value)
+        );
+    }
+
+    #[test]
+    fn only_known_values_are_folded() {
+        let statements =
+            optimized("let x = 1\nlet a = x + 2 * 3\nlet b = false and x\nlet c = x and false");
+        match declared_value(&statements[1]) {
+            Node::BinaryOp { rhs, .. } => {
+                assert!(matches!(
+                    rhs.as_ref(),
+                    Node::Literal {
+                        value: Object::Int(Int::Small(6)),
+                        ..
+                    }
+                ))
+            }
+            other => panic!("expected a binary operation, got {:?}", other),
+        }
+        // `and` short circuits, so only needs to know its left operand
+        assert!(
+            matches!(declared_value(&statements[2]), Node::Literal { value: Object::Bool(b), .. } if !b.value)
+        );
+        assert!(matches!(
+            declared_value(&statements[3]),
+            Node::BinaryOp { .. }
+        ));
+    }
+
+    #[test]
+    fn errors_are_left_for_the_interpreter() {
+        let src = "let x = 1 / 0";
+        let statements = optimized(src);
+        assert!(matches!(
+            declared_value(&statements[0]),
+            Node::BinaryOp { .. }
+        ));
+
+        let mut program = parse(src).unwrap();
+        optimize(&mut program);
+        let error = Interpreter::new().run(&program).unwrap_err();
+        assert_eq!(error.message, "Integer division or modulo by zero");
+        assert_eq!(error.token.span, Span::new(8, 13));
+
+        // Whether overflow is an error depends on the interpreter
+        let statements = optimized("let big = 9223372036854775807 + 1");
+        assert!(matches!(
+            declared_value(&statements[0]),
+            Node::BinaryOp { .. }
+        ));
+    }
+
+    #[test]
+    fn dead_branches_are_removed() {
+        let src = "let x = 1\nif 1 > 2 then\n    x = 2\nelif x then\n    x = 3\nelif true then\n    x = 4\nelse\n    x = 5\nend";
+        let statements = optimized(src);
+        match &statements[1] {
+            Node::Selection {
+                if_conditionals,
+                else_conditional,
+                span,
+            } => {
+                assert_eq!(if_conditionals.len(), 1);
+                assert!(matches!(
+                    if_conditionals[0].condition.as_ref(),
+                    Node::Identifier { .. }
+                ));
+                // The `elif true` branch always runs if it is reached
+                assert_eq!(
+                    else_conditional.as_ref().unwrap().span(),
+                    &Span::new(74, 81)
+                );
+                assert_eq!(span, &Span::new(10, 99));
+            }
+            other => panic!("expected an if statement, got {:?}", other),
+        }
+
+        // An `if` with one branch which always runs is replaced by its body
+        let statements = optimized("let x = 1\nif false then x = 2 else x = 3 end");
+        assert!(matches!(&statements[1], Node::Block { statements, .. } if statements.len() == 1));
+        let statements = optimized(
+            "let x = 1\nif false then x = 2 end\nwhile 0 do x = 3 end\nwhile x do x = 0 end",
+        );
+        assert_eq!(statements.len(), 2);
+        assert!(matches!(&statements[1], Node::While { .. }));
+    }
+
+    #[test]
+    fn dead_code_is_not_folded() {
+        // The repetition would be too long to build, but never runs
+        let src = "if false then print(\"ab\" * 9223372036854775807) end\nwhile false do let s = [1] * 9223372036854775807 end\nlet x = 1";
+        let statements = optimized(src);
+        assert_eq!(statements.len(), 1);
+        let mut program = parse(src).unwrap();
+        optimize(&mut program);
+        Interpreter::new().run(&program).unwrap();
+
+        // Long repetitions are left for the interpreter
+        let statements = optimized("let s = \"ab\" * 1000\nlet t = \"ab\" * 3");
+        assert!(matches!(
+            declared_value(&statements[0]),
+            Node::BinaryOp { .. }
+        ));
+        assert!(
+            matches!(declared_value(&statements[1]), Node::Literal { value: Object::Str(s), .. } if s.value == "ababab")
+        );
+    }
+
+    #[test]
+    fn function_bodies_are_optimized() {
+        let statements = optimized(
+            "fn f(x = 2 * 3)\n    if false then return 1 end\n    return x * (4 - 1)\nend",
+        );
+        match &statements[0] {
+            Node::FunctionDecl { params, body, .. } => {
+                assert!(matches!(params[0].default, Some(Node::Literal { .. })));
+                match body.as_ref() {
+                    Node::Block { statements, .. } => {
+                        assert_eq!(statements.len(), 1);
+                        assert!(
+                            matches!(&statements[0], Node::Return { value: Some(value), .. } if matches!(value.as_ref(), Node::BinaryOp { rhs, .. } if matches!(rhs.as_ref(), Node::Literal { .. })))
+                        );
+                    }
+                    other => panic!("expected a block, got {:?}", other),
+                }
+            }
+            other => panic!("expected a function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn optimized_programs_behave_the_same() {
+        let src = "
+let total = 0
+fn add(n)
+    if true then
+        total += n * (2 + 3)
+    end
+end
+for i in range(4) do
+    add(i)
+    if i > 100 or false then
+        total = -1
+    end
+end
+let label = \"ab\" * (1 + 1) + \"!\" * (3 % 2)";
+        let mut program = parse(src).unwrap();
+        optimize(&mut program);
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program).unwrap();
+        assert!(matches!(
+            interpreter.get_variable("total"),
+            Some(Object::Int(Int::Small(30)))
+        ));
+        assert!(
+            matches!(interpreter.get_variable("label"), Some(Object::Str(s)) if s.value == "abab!")
+        );
+    }
+}
@@ -0,0 +1,321 @@
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::core::Object;
+    use sap::lexer::token::TokenKind;
+    use sap::lexer::{tokenize, Span};
+    use sap::parser::ast::*;
+    use sap::errors::Warning;
+    use sap::parser::{parse, parse_with_warnings};
+
+    fn statements(src: &str) -> Vec<Node> {
+        match parse(src).unwrap() {
+            Node::Program { statements, .. } => statements,
+            other => panic!("expected a program, got {:?}", other),
+        }
+    }
+
+    // region: Comments
+
+    #[test]
+    fn nested_block_comments() {
+        let (tokens, errors) = tokenize("a /* outer /* inner */ still outer */ b");
+        assert!(errors.is_empty());
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Ident("a".to_string()),
+            TokenKind::Ident("b".to_string()),
+            TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn unterminated_nested_block_comment() {
+        let (_, errors) = tokenize("a /* outer /* inner */ b");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].token.span, Span::new(2, 4));
+    }
+
+    #[test]
+    fn doc_comment_tokens() {
+        let (tokens, _) = tokenize("/// Adds two numbers\n//// not documentation\n///no space");
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::DocComment("Adds two numbers".to_string()),
+            TokenKind::Seperator,
+            TokenKind::DocComment("no space".to_string()),
+            TokenKind::Eof,
+        ]);
+    }
+
+    #[test]
+    fn doc_comments_attach_to_declarations() {
+        let src = "/// Adds two numbers.\n/// Returns their sum.\nfn add(a, b)\n    a + b\nend\n\n/// The answer\nconst x = 42";
+        let statements = statements(src);
+        match &statements[0] {
+            Node::FunctionDecl { name, params, doc, .. } => {
+                assert_eq!(name, "add");
+                let names: Vec<&str> = params.iter().map(|param| param.name.as_str()).collect();
+                assert_eq!(names, vec!["a", "b"]);
+                assert_eq!(doc.as_deref(), Some("Adds two numbers.\nReturns their sum."));
+            }
+            other => panic!("expected a function declaration, got {:?}", other),
+        }
+        match &statements[1] {
+            Node::VariableDecl { name, constant, doc, .. } => {
+                assert_eq!(name, "x");
+                assert!(constant);
+                assert_eq!(doc.as_deref(), Some("The answer"));
+            }
+            other => panic!("expected a variable declaration, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn doc_comment_before_other_statement_is_an_error() {
+        let error = parse("/// Dangling\nprint(1)").unwrap_err();
+        assert_eq!(error.token.span, Span::new(0, 12));
+    }
+
+    // endregion
+
+    // region: Statements and expressions
+
+    #[test]
+    fn selection_and_while() {
+        let src = "let y = 0\nif x > 1 then\n    y = 1\nelif x == 1 then\n    y = 2\nelse\n    y = 3\nend\nwhile y < 10 do y = y + 1 end";
+        let statements = statements(src);
+        match &statements[1] {
+            Node::Selection { if_conditionals, else_conditional, .. } => {
+                assert_eq!(if_conditionals.len(), 2);
+                assert!(else_conditional.is_some());
+            }
+            other => panic!("expected a selection, got {:?}", other),
+        }
+        assert!(matches!(statements[2], Node::While { .. }));
+    }
+
+    #[test]
+    fn operator_precedence() {
+        let statements = statements("1 + 2 * 3 % 4");
+        match &statements[0] {
+            Node::BinaryOp { op: Operator::Add, rhs, span, .. } => {
+                assert_eq!(span, &Span::new(0, 13));
+                match rhs.as_ref() {
+                    Node::BinaryOp { op: Operator::Mul, rhs, .. } => {
+                        assert!(matches!(rhs.as_ref(), Node::BinaryOp { op: Operator::Mod, .. }))
+                    }
+                    other => panic!("expected a multiplication, got {:?}", other),
+                }
+            }
+            other => panic!("expected an addition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lists_and_calls() {
+        let statements = statements("let x = [1, \"two\", f(3, 4),]");
+        match &statements[0] {
+            Node::VariableDecl { value, .. } => match value.as_ref() {
+                Node::List { elements, .. } => {
+                    assert_eq!(elements.len(), 3);
+                    assert!(matches!(elements[1], Node::Literal { value: Object::Str(_), .. }));
+                    assert!(matches!(&elements[2], Node::FunctionCall { args, .. } if args.len() == 2));
+                }
+                other => panic!("expected a list, got {:?}", other),
+            },
+            other => panic!("expected a variable declaration, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn calls_on_expressions() {
+        let statements = statements("f(1)(2)[0]\nfn(x) end(1)");
+        match &statements[0] {
+            Node::Index { object, .. } => match object.as_ref() {
+                Node::FunctionCall { function, span, .. } => {
+                    assert!(matches!(function.as_ref(), Node::FunctionCall { .. }));
+                    assert_eq!(span, &Span::new(0, 7));
+                }
+                other => panic!("expected a call, got {:?}", other),
+            },
+            other => panic!("expected an index, got {:?}", other),
+        }
+        match &statements[1] {
+            Node::FunctionCall { function, .. } => {
+                assert!(matches!(function.as_ref(), Node::Lambda { params, .. } if params.len() == 1))
+            }
+            other => panic!("expected a call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parameters_and_keyword_arguments() {
+        let statements = statements("fn f(a, b = 1, *rest)\nend\nf(1, b=2)\nprint(1, end=\"\")");
+        match &statements[0] {
+            Node::FunctionDecl { params, .. } => {
+                assert!(params[0].default.is_none() && !params[0].variadic);
+                assert!(matches!(params[1].default, Some(Node::Literal { .. })));
+                assert!(params[2].variadic);
+            }
+            other => panic!("expected a function declaration, got {:?}", other),
+        }
+        match &statements[1] {
+            Node::FunctionCall { args, kwargs, .. } => {
+                assert_eq!(args.len(), 1);
+                assert_eq!(kwargs[0].0, "b");
+            }
+            other => panic!("expected a call, got {:?}", other),
+        }
+        // `end` can name a keyword argument
+        match &statements[2] {
+            Node::FunctionCall { kwargs, .. } => assert_eq!(kwargs[0].0, "end"),
+            other => panic!("expected a call, got {:?}", other),
+        }
+
+        for (src, message) in [
+            ("fn f(a, a)\nend", "Duplicate parameter 'a'"),
+            ("fn f(*a, b)\nend", "'*' parameters must come last"),
+            ("fn f(a = 1, b)\nend", "Parameter 'b' must have a default, as it follows a parameter with one"),
+            ("f(a=1, 2)", "Positional arguments must come before keyword arguments"),
+            ("f(a=1, a=2)", "Keyword argument 'a' is repeated"),
+        ] {
+            let error = parse(src).unwrap_err();
+            assert_eq!(error.message, message, "{}", src);
+        }
+    }
+
+    #[test]
+    fn missing_end_is_an_error() {
+        let error = parse("while true do let x = 1").unwrap_err();
+        assert_eq!(error.token.kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn for_loops() {
+        let statements = statements("for x in range(3) do\n    if x == 1 then continue end\n    break\nend");
+        match &statements[0] {
+            Node::For { variable, iterable, span, .. } => {
+                assert_eq!(variable, "x");
+                assert!(matches!(iterable.as_ref(), Node::FunctionCall { .. }));
+                assert_eq!(span, &Span::new(0, 66));
+            }
+            other => panic!("expected a for loop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn loop_control_outside_of_a_loop_is_an_error() {
+        let error = parse("let x = 1\nbreak").unwrap_err();
+        assert_eq!(error.message, "'break' outside of a loop");
+        assert_eq!(error.token.span, Span::new(10, 15));
+
+        let error = parse("while true do\n    fn f()\n        continue\n    end\nend").unwrap_err();
+        assert_eq!(error.message, "'continue' outside of a loop");
+    }
+
+    #[test]
+    fn structs_and_fields() {
+        let statements = statements("struct Point x, y\n    fn norm()\n        return self.x + self.y\n    end\nend\nlet p = Point(1, 2)\np.x += p.y");
+        match &statements[0] {
+            Node::StructDecl { name, fields, methods, .. } => {
+                assert_eq!(name, "Point");
+                assert_eq!(fields, &["x", "y"]);
+                assert!(matches!(&methods[0], Node::FunctionDecl { name, .. } if name == "norm"));
+            }
+            other => panic!("expected a struct declaration, got {:?}", other),
+        }
+        match &statements[2] {
+            Node::FieldAssign { object, name, op, value, span } => {
+                assert!(matches!(object.as_ref(), Node::Identifier { name, .. } if name == "p"));
+                assert_eq!(name, "x");
+                assert!(matches!(op, Some(Operator::Add)));
+                assert!(matches!(value.as_ref(), Node::Field { name, .. } if name == "y"));
+                assert_eq!(span, &Span::new(95, 105));
+            }
+            other => panic!("expected a field assignment, got {:?}", other),
+        }
+
+        for (src, message) in [
+            ("struct P x, x end", "'x' is already a field or method of this struct"),
+            ("struct P x\n    fn x()\n    end\nend", "'x' is already a field or method of this struct"),
+            ("struct P\n    fn f()\n        self = 1\n    end\nend", "Cannot assign to constant 'self'"),
+        ] {
+            let error = parse(src).unwrap_err();
+            assert_eq!(error.message, message, "{}", src);
+        }
+    }
+
+    #[test]
+    fn match_statements() {
+        let (program, warnings) = parse_with_warnings("match xs\ncase [1, *rest] if rest then\n    let y = rest\ncase Point(x, y=[_, b]) then\nend").unwrap();
+        let Node::Program { statements, .. } = program else { panic!("expected a program") };
+        match &statements[0] {
+            Node::Match { subject, cases, span } => {
+                assert!(matches!(subject.as_ref(), Node::Identifier { name, .. } if name == "xs"));
+                assert_eq!(span, &Span::new(0, 87));
+                match &cases[0].pattern {
+                    Pattern::List { elements, .. } => {
+                        assert!(matches!(elements[0], Pattern::Literal { .. }));
+                        assert!(matches!(&elements[1], Pattern::Rest { name: Some(name), .. } if name == "rest"));
+                    }
+                    other => panic!("expected a list pattern, got {:?}", other),
+                }
+                assert!(cases[0].guard.is_some());
+                match &cases[1].pattern {
+                    Pattern::Struct { name, args, kwargs, .. } => {
+                        assert_eq!(name, "Point");
+                        assert!(matches!(&args[0], Pattern::Binding { name, .. } if name == "x"));
+                        assert_eq!(kwargs[0].0, "y");
+                    }
+                    other => panic!("expected a struct pattern, got {:?}", other),
+                }
+            }
+            other => panic!("expected a match, got {:?}", other),
+        }
+        assert_eq!(warnings, vec![Warning::new("This match has no catch-all case, so it may not handle every value", Span::new(0, 8))]);
+
+        let (_, warnings) = parse_with_warnings("match 1\ncase 1 then\ncase x if x then\ncase _ then\nend").unwrap();
+        assert!(warnings.is_empty());
+
+        for (src, message) in [
+            ("match 1\ncase [a, a] then\nend", "Name 'a' is bound more than once in this pattern"),
+            ("match 1\ncase [*a, *b] then\nend", "A list pattern can only contain one '*' pattern"),
+            ("match 1\ncase P(x=1, 2) then\nend", "Positional patterns must come before keyword patterns"),
+            ("match 1\ncase P(x=1, x=2) then\nend", "Field 'x' is matched more than once"),
+            ("match 1\nend", "Expected 'case', found 'end'"),
+        ] {
+            let error = parse(src).unwrap_err();
+            assert_eq!(error.message, message, "{}", src);
+        }
+    }
+
+    #[test]
+    fn type_annotations() {
+        let statements = statements("let xs: list[int] = []\nfn f(a: str, *rest: fn(int) -> bool) -> none\nend");
+        match &statements[0] {
+            Node::VariableDecl { ty: Some(ty), .. } => {
+                assert_eq!(ty.to_string(), "list[int]");
+                assert_eq!(ty.span(), &Span::new(8, 17));
+            }
+            other => panic!("expected an annotated declaration, got {:?}", other),
+        }
+        match &statements[1] {
+            Node::FunctionDecl { params, return_type, .. } => {
+                assert_eq!(params[0].ty.as_ref().map(ToString::to_string), Some("str".to_string()));
+                assert!(params[1].variadic);
+                assert_eq!(params[1].ty.as_ref().map(ToString::to_string), Some("fn(int) -> bool".to_string()));
+                assert_eq!(return_type.as_ref().map(ToString::to_string), Some("none".to_string()));
+            }
+            other => panic!("expected a function declaration, got {:?}", other),
+        }
+
+        let error = parse("let x: = 1").unwrap_err();
+        assert_eq!(error.message, "Expected type, found '='");
+    }
+
+    // endregion
+}
@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::errors::ErrorType;
+    use sap::lexer::Span;
+    use sap::parser::parse;
+    use sap::resolver::{resolve, DeclarationKind, Resolution, ScopeKind};
+
+    fn resolve_src(src: &str) -> Resolution {
+        resolve(&parse(src).unwrap())
+    }
+
+    fn errors(src: &str) -> Vec<String> {
+        resolve_src(src)
+            .errors
+            .into_iter()
+            .map(|error| error.message)
+            .collect()
+    }
+
+    fn warnings(src: &str) -> Vec<String> {
+        resolve_src(src)
+            .warnings
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    #[test]
+    fn names_resolve_to_their_declarations() {
+        let src = "let x = 1\nfn f(x)\n    return x\nend\nf(x)";
+        let resolution = resolve_src(src);
+        assert!(resolution.errors.is_empty());
+
+        // The `x` returned by `f` is its parameter, while the argument is
+        // the global variable
+        let param = resolution.declaration_at(&Span::new(29, 30)).unwrap();
+        assert_eq!(param.kind, DeclarationKind::Parameter);
+        assert_eq!(param.span, Span::new(15, 16));
+        let global = resolution.declaration_at(&Span::new(37, 38)).unwrap();
+        assert_eq!(global.kind, DeclarationKind::Variable);
+        assert_eq!(global.span, Span::new(0, 9));
+        assert_eq!(global.uses, 1);
+
+        let kinds: Vec<ScopeKind> = resolution.scopes.iter().map(|scope| scope.kind).collect();
+        assert_eq!(kinds, vec![ScopeKind::Program, ScopeKind::Function]);
+        assert_eq!(resolution.scopes[1].parent, Some(0));
+    }
+
+    #[test]
+    fn undefined_names_are_errors() {
+        let resolution = resolve_src("let x = 1\nx = y + 1");
+        assert_eq!(resolution.errors.len(), 1);
+        let error = &resolution.errors[0];
+        assert!(matches!(error.error_type, ErrorType::NameError));
+        assert_eq!(error.message, "Name 'y' is not defined");
+        assert_eq!(error.token.span, Span::new(14, 15));
+
+        // Builtins can only be called
+        assert_eq!(
+            errors("let r = range(3)\nr = range"),
+            vec!["Name 'range' is not defined"]
+        );
+        // A variable can't be used in its own declaration
+        assert_eq!(errors("let x = x"), vec!["Name 'x' is not defined"]);
+    }
+
+    #[test]
+    fn functions_can_use_names_declared_after_them() {
+        let src = "fn is_even(n)\n    return n == 0 or is_odd(n - 1)\nend\nfn is_odd(n)\n    return n != 0 and is_even(n - 1)\nend\nlet later = fn() return limit end\nlet limit = 10\nlater()";
+        assert_eq!(errors(src), Vec::<String>::new());
+        // But not names declared after them in their own body
+        assert_eq!(
+            errors("fn f()\n    let y = x\n    let x = 1\n    return y + x\nend\nf()"),
+            vec!["Name 'x' is not defined"]
+        );
+        assert_eq!(
+            errors("fn f()\n    return missing\nend\nf()"),
+            vec!["Name 'missing' is not defined"]
+        );
+    }
+
+    #[test]
+    fn blocks_have_their_own_scopes() {
+        let src = "let xs = [1]\nfor x in xs do\n    let y = x\n    xs = [y]\nend\nmatch xs\ncase [first, *rest] then\n    xs = rest + [first]\nend";
+        let resolution = resolve_src(src);
+        assert!(resolution.errors.is_empty() && resolution.warnings.is_empty());
+        let kinds: Vec<ScopeKind> = resolution.scopes.iter().map(|scope| scope.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ScopeKind::Program, ScopeKind::Block, ScopeKind::Block]
+        );
+
+        // Variables live until the end of the function, so a variable
+        // declared in a block may still be defined after it
+        assert_eq!(
+            warnings("let c = true\nif c then\n    let y = 1\nend\nc = y"),
+            vec!["'y' is declared in a block which has ended, so it may not be defined here"]
+        );
+    }
+
+    #[test]
+    fn unused_variables_are_warnings() {
+        let resolution = resolve_src("fn f(a, b, _c)\n    let d = 1\n    d = a\nend\nf(1, 2, 3)");
+        let found: Vec<(&str, &Span)> = resolution
+            .warnings
+            .iter()
+            .map(|warning| (warning.message.as_str(), &warning.span))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Argument 'b' is never used", &Span::new(8, 9)),
+                ("Variable 'd' is never used", &Span::new(19, 28)),
+            ]
+        );
+
+        assert_eq!(
+            warnings("for i in range(3) do end\nlet x = 1\nx += 1"),
+            vec!["Variable 'i' is never used"]
+        );
+        assert_eq!(
+            warnings("match [1]\ncase [a, *rest] then\n    let b = a\n    b\nend"),
+            vec!["Variable 'rest' is never used"]
+        );
+        // Unused functions, structs and `self` aren't reported
+        assert_eq!(
+            warnings("struct P x\n    fn get()\n        return 1\n    end\nend\nfn unused() end"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn shadowing_is_a_warning() {
+        assert_eq!(
+            warnings("let x = 1\nfn f(x)\n    return x\nend\nf(x)"),
+            vec!["'x' shadows a declaration in an enclosing scope"]
+        );
+        assert_eq!(
+            warnings("let x = 1\nif x then\n    let x = 2\n    x\nend"),
+            vec!["'x' shadows a declaration in an enclosing scope"]
+        );
+        // Declaring a name again in the same scope replaces it
+        assert_eq!(
+            warnings("let x = 1\nlet x = x + 1\nx"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn unreachable_code_is_a_warning() {
+        let resolution = resolve_src("fn f()\n    return 1\n    let x = 2\n    x\nend\nf()");
+        assert_eq!(resolution.warnings.len(), 1);
+        let warning = &resolution.warnings[0];
+        assert_eq!(
+            warning.message,
+            "This code is unreachable, as it comes after a 'return'"
+        );
+        assert_eq!(warning.span, Span::new(24, 39));
+
+        assert_eq!(
+            warnings("while true do\n    break\n    continue\nend"),
+            vec!["This code is unreachable, as it comes after a 'break'"]
+        );
+    }
+}
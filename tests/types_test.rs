@@ -0,0 +1,294 @@
+#[cfg(test)]
+mod tests {
+
+    extern crate sap;
+
+    use sap::errors::ErrorType;
+    use sap::lexer::Span;
+    use sap::parser::parse;
+    use sap::types::{check, Type};
+
+    /// Checks a program, returning the messages of the errors found.
+    fn errors(src: &str) -> Vec<String> {
+        let program = parse(src).unwrap();
+        check(&program)
+            .into_iter()
+            .map(|error| error.message)
+            .collect()
+    }
+
+    #[test]
+    fn valid_programs_have_no_errors() {
+        let src = "
+struct Point x, y
+    fn norm() -> int
+        return self.x * self.x + self.y * self.y
+    end
+end
+let xs: list[float] = [1, 2.5]
+let total: float = 0
+for x in xs do total += x end
+fn apply(f: fn(int) -> int, x: int) -> int
+    return f(x)
+end
+let doubled = apply(fn(x: int) -> int return x * 2 end, 2)
+let n: int = Point(1, 2).norm()
+let anything = none
+anything = \"now a str\"
+let items = [1, \"a\"]
+let first: int = items[0]
+let ys = [1, 2]
+ys.push(\"a\")
+let popped: int = ys.pop()
+ys.sort()
+let size: int = len(ys)
+let name: str = input(\"Name? \")
+print(name, size, sep=\": \")
+match xs
+case [a, *rest] then
+    let y: float = a
+end";
+        assert_eq!(errors(src), Vec::<String>::new());
+    }
+
+    #[test]
+    fn annotations_are_checked() {
+        assert_eq!(
+            errors("let x: int = \"a\"\nlet y: float = 1\ny = \"b\""),
+            vec![
+                "Cannot assign a value of type 'str' to 'x', which has type 'int'",
+                "Cannot assign a value of type 'str' to 'y', which has type 'float'",
+            ]
+        );
+        assert_eq!(
+            errors("let ys = [1, 2.5]\nlet xs: list[int] = ys"),
+            vec!["Cannot assign a value of type 'list[float]' to 'xs', which has type 'list[int]'"]
+        );
+        assert_eq!(
+            errors("let xs: list[int] = [1, 2.5]"),
+            vec!["Cannot use a value of type 'float' as an element of 'list[int]'"]
+        );
+        assert_eq!(
+            errors("let xs: list[int] = []\nxs[0] = \"a\""),
+            vec!["Cannot assign a value of type 'str' to an element of 'xs', which has type 'list[int]'"]
+        );
+        assert_eq!(
+            errors("let x: number = 1\nlet y: int[str] = 1"),
+            vec![
+                "Unknown type 'number'",
+                "'int' does not take type arguments"
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_are_checked() {
+        let define = "fn greet(name: str, times: int = 1) -> str\n    return name * times\nend\n";
+        for (program, message) in [
+            ("greet(1)", "Cannot pass a value of type 'int' as the argument 'name', which has type 'str'"),
+            ("greet(\"a\", times=\"b\")", "Cannot pass a value of type 'str' as the argument 'times', which has type 'int'"),
+            ("let x: int = greet(\"a\")", "Cannot assign a value of type 'str' to 'x', which has type 'int'"),
+            ("let f: fn(int) -> str = greet", "Cannot assign a value of type 'fn(str, int) -> str' to 'f', which has type 'fn(int) -> str'"),
+            ("greet(\"a\")(1)", "'str' object is not callable"),
+        ] {
+            assert_eq!(errors(&format!("{}{}", define, program)), vec![message], "{}", program);
+        }
+        assert_eq!(
+            errors(&format!("{}let f: fn(str) -> str = greet", define)),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            errors("fn f(x: int = \"a\") -> bool\n    return x\nend"),
+            vec![
+                "Cannot use a value of type 'str' as the default for 'x', which has type 'int'",
+                "Cannot return a value of type 'int' from 'f', which returns 'bool'",
+            ]
+        );
+        assert_eq!(
+            errors("fn f(*rest: int)\n    let s: str = rest[0]\nend\nf(1, \"a\")"),
+            vec![
+                "Cannot assign a value of type 'int' to 's', which has type 'str'",
+                "Cannot pass a value of type 'str' as argument 2, which has type 'int'",
+            ]
+        );
+    }
+
+    #[test]
+    fn argument_counts_are_checked() {
+        let define = "fn f(x: int, y = 1, *rest) -> int\n    return x\nend\nfn g(x: int) -> int\n    return x\nend\nlet h: fn(int) -> int = g\n";
+        for (program, message) in [
+            ("g(1, 2)", "g() takes 1 arguments but 2 were given"),
+            ("g()", "g() is missing the argument 'x'"),
+            ("f(y=2)", "f() is missing the argument 'x'"),
+            ("h()", "h() is missing argument 1"),
+            (
+                "[g][0](1, 2)",
+                "The function takes 1 arguments but 2 were given",
+            ),
+            ("range()", "range() is missing argument 1"),
+            (
+                "range(1, 2, 3, 4)",
+                "range() takes 1 to 3 arguments but 4 were given",
+            ),
+            ("[1].push()", "push() is missing argument 1"),
+        ] {
+            assert_eq!(
+                errors(&format!("{}{}", define, program)),
+                vec![message],
+                "{}",
+                program
+            );
+        }
+        // Defaults, `*rest` parameters and keyword arguments are accounted for
+        assert_eq!(
+            errors(&format!(
+                "{}f(1)\nf(1, 2, 3, 4)\nf(y=2, x=1)\ng(x=1)\nprint()",
+                define
+            )),
+            Vec::<String>::new()
+        );
+
+        let program = parse("fn g(x)\nend\ng(1, 2)").unwrap();
+        let error = &check(&program)[0];
+        assert_eq!(error.token.span, Span::new(12, 19));
+    }
+
+    #[test]
+    fn return_types_are_inferred() {
+        let src = "fn f(x)\n    if x then\n        return 1\n    end\n    return 2.5\nend\nlet y: str = f(1)";
+        assert_eq!(
+            errors(src),
+            vec!["Cannot assign a value of type 'float' to 'y', which has type 'str'"]
+        );
+        // A function which may finish without returning could return none
+        let src = "fn f(x)\n    if x then\n        return 1\n    end\nend\nlet y: str = f(1)";
+        assert_eq!(errors(src), Vec::<String>::new());
+        let src = "fn f()\nend\nlet y: str = f()";
+        assert_eq!(
+            errors(src),
+            vec!["Cannot assign a value of type 'none' to 'y', which has type 'str'"]
+        );
+    }
+
+    #[test]
+    fn operations_are_checked() {
+        for (program, message) in [
+            (
+                "let x = 1 + \"a\"",
+                "Unsupported operand types for '+': 'int' and 'str'",
+            ),
+            (
+                "let x = [1] < [2]",
+                "Unsupported operand types for '<': 'list[int]' and 'list[int]'",
+            ),
+            (
+                "let x = -\"a\"",
+                "Unsupported operand type for unary '-': 'str'",
+            ),
+            (
+                "let x = [1][\"a\"]",
+                "list indices must be integers, not 'str'",
+            ),
+            ("let x = 1[0]", "'int' object is not subscriptable"),
+            ("for x in 1 do end", "'int' object is not iterable"),
+            (
+                "let s = \"ab\"\ns[0] = \"c\"",
+                "'str' object does not support index assignment",
+            ),
+            ("struct P x end\nlet y = P(1).y", "'P' has no field 'y'"),
+            (
+                "let xs = [1]\nxs.append(2)",
+                "'list[int]' object has no field 'append'",
+            ),
+            (
+                "let xs = [\"a\"]\nlet x: int = xs.pop()",
+                "Cannot assign a value of type 'str' to 'x', which has type 'int'",
+            ),
+            (
+                "let x: decimal = 1.5d * 2\nlet y = x + 0.5",
+                "Unsupported operand types for '+': 'decimal' and 'float'",
+            ),
+        ] {
+            assert_eq!(errors(program), vec![message], "{}", program);
+        }
+    }
+
+    #[test]
+    fn list_elements_are_checked_against_annotations() {
+        for (program, message) in [
+            (
+                "let z: list[int] = [1, \"a\"]",
+                "Cannot use a value of type 'str' as an element of 'list[int]'",
+            ),
+            (
+                "let z: list[list[int]] = [[1], [2, 3.5]]",
+                "Cannot use a value of type 'float' as an element of 'list[int]'",
+            ),
+            (
+                "let z: list[int] = []\nz = [true]",
+                "Cannot use a value of type 'bool' as an element of 'list[int]'",
+            ),
+            (
+                "fn f() -> list[str]\n    return [\"a\", none]\nend",
+                "Cannot use a value of type 'none' as an element of 'list[str]'",
+            ),
+        ] {
+            assert_eq!(errors(program), vec![message], "{}", program);
+        }
+        assert!(errors("let z: list[float] = [1, 2.5]").is_empty());
+    }
+
+    #[test]
+    fn functions_must_return_on_every_path() {
+        let src = "
+fn f(x) -> int
+    if x then
+        return 1
+    end
+end";
+        assert_eq!(
+            errors(src),
+            vec!["'f' returns 'int', but can reach the end of its body without returning a value"]
+        );
+        let src = "
+fn f(x) -> int
+    if x then
+        return 1
+    else
+        return 2
+    end
+end
+fn g(x) -> int
+    match x
+    case 1 then return 1
+    case _ then return 0
+    end
+end
+fn h() -> none
+    print(1)
+end";
+        assert!(errors(src).is_empty(), "{:?}", errors(src));
+    }
+
+    #[test]
+    fn errors_have_spans() {
+        let program = parse("let x: int = 1\nlet y: str = x + 1").unwrap();
+        let errors = check(&program);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].error_type, ErrorType::TypeError));
+        assert_eq!(errors[0].token.span, Span::new(28, 33));
+    }
+
+    #[test]
+    fn types_are_shown_as_annotations() {
+        assert_eq!(
+            Type::list(Type::list(Type::Int)).to_string(),
+            "list[list[int]]"
+        );
+        assert!(Type::Float.accepts(&Type::Int));
+        assert!(!Type::Int.accepts(&Type::Float));
+        assert_eq!(Type::Int.join(&Type::Float), Type::Float);
+        assert_eq!(Type::Int.join(&Type::Str), Type::Any);
+    }
+}